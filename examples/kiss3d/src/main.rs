@@ -125,10 +125,11 @@ fn scene_to_kiss3d_scene(
         } else {
             Some(mesh.normals.into_iter().map(Into::into).collect())
         };
-        let uvs = if mesh.texcoords[0].is_empty() {
-            None
-        } else {
-            Some(mesh.texcoords[0].iter().copied().map(Into::into).collect())
+        let uvs = match mesh.texcoords.first() {
+            Some(texcoords) if !texcoords.is_empty() => {
+                Some(texcoords.iter().copied().map(Into::into).collect())
+            }
+            _ => None,
         };
         let kiss3d_mesh = Rc::new(RefCell::new(kiss3d::resource::Mesh::new(
             coords, faces, normals, uvs, false,
@@ -137,10 +138,20 @@ fn scene_to_kiss3d_scene(
         if let Some(color) = material.color.diffuse {
             kiss3d_scene.set_color(color[0], color[1], color[2]);
         }
-        if let Some(path) = &material.texture.diffuse {
+        if let Some(path) = material
+            .texture
+            .diffuse
+            .as_ref()
+            .and_then(mesh_loader::Texture::path)
+        {
             kiss3d_scene.set_texture_from_file(path, path.to_str().unwrap());
         }
-        if let Some(path) = &material.texture.ambient {
+        if let Some(path) = material
+            .texture
+            .ambient
+            .as_ref()
+            .and_then(mesh_loader::Texture::path)
+        {
             kiss3d_scene.set_texture_from_file(path, path.to_str().unwrap());
         }
     }
@@ -0,0 +1,43 @@
+/*
+Run with libFuzzer:
+
+```sh
+cargo fuzz run --release --features libfuzzer gltf
+```
+
+Run with AFL++:
+
+```sh
+cd fuzz
+cargo afl build --release --features afl
+cargo afl fuzz -i seeds/gltf -o out/gltf target/release/gltf
+```
+*/
+
+#![cfg_attr(feature = "libfuzzer", no_main)]
+
+use std::io;
+
+use mesh_loader::gltf::from_slice;
+
+#[cfg(any(
+    not(any(feature = "libfuzzer", feature = "afl")),
+    all(feature = "libfuzzer", feature = "afl"),
+))]
+compile_error!("exactly one of 'libfuzzer' or 'afl' feature must be enabled");
+
+#[cfg(feature = "libfuzzer")]
+libfuzzer_sys::fuzz_target!(|bytes: &[u8]| {
+    run(bytes);
+});
+
+#[cfg(feature = "afl")]
+fn main() {
+    afl::fuzz!(|bytes: &[u8]| {
+        run(bytes);
+    });
+}
+
+fn run(bytes: &[u8]) {
+    let _result = from_slice::<Vec<u8>, _>(bytes, None, |_| Err(io::ErrorKind::NotFound.into()));
+}
@@ -0,0 +1,41 @@
+/*
+Run with libFuzzer:
+
+```sh
+cargo fuzz run --release --features libfuzzer ply
+```
+
+Run with AFL++:
+
+```sh
+cd fuzz
+cargo afl build --release --features afl
+cargo afl fuzz -i seeds/ply -o out/ply target/release/ply
+```
+*/
+
+#![cfg_attr(feature = "libfuzzer", no_main)]
+
+use mesh_loader::ply::from_slice;
+
+#[cfg(any(
+    not(any(feature = "libfuzzer", feature = "afl")),
+    all(feature = "libfuzzer", feature = "afl"),
+))]
+compile_error!("exactly one of 'libfuzzer' or 'afl' feature must be enabled");
+
+#[cfg(feature = "libfuzzer")]
+libfuzzer_sys::fuzz_target!(|bytes: &[u8]| {
+    run(bytes);
+});
+
+#[cfg(feature = "afl")]
+fn main() {
+    afl::fuzz!(|bytes: &[u8]| {
+        run(bytes);
+    });
+}
+
+fn run(bytes: &[u8]) {
+    let _result = from_slice(bytes);
+}
@@ -0,0 +1,564 @@
+//! Zip archive reader used to load asset bundles, COLLADA (.zae), and
+//! [3MF] containers.
+//!
+//! [3MF]: https://en.wikipedia.org/wiki/3D_Manufacturing_Format
+
+pub(crate) mod threemf;
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    io,
+    path::{Component, Path, PathBuf},
+    str,
+    sync::Arc,
+};
+
+use crate::{common, utils::uri::percent_decode};
+
+const LOCAL_FILE_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_DIRECTORY_HEADER: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+const ZIP64_END_OF_CENTRAL_DIRECTORY: u32 = 0x0606_4b50;
+const ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR: u32 = 0x0706_4b50;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+/// Extensions of files that can be the root document of an archive.
+const MODEL_EXTENSIONS: &[&str] = &["dae", "gltf", "glb", "obj", "stl", "ply", "model"];
+
+pub(crate) struct Archive<'a> {
+    bytes: &'a [u8],
+    /// The path of the archive. The paths of entries are relative to this.
+    path: PathBuf,
+    /// Entries in the order they appear in the central directory.
+    entries: Vec<Entry>,
+    /// Maps normalized entry names to indices into `entries`.
+    names: Arc<HashMap<String, usize>>,
+}
+
+struct Entry {
+    name: String,
+    flags: u16,
+    method: u16,
+    compressed_size: usize,
+    size: usize,
+    offset: usize,
+}
+
+impl<'a> Archive<'a> {
+    pub(crate) fn new(bytes: &'a [u8], path: &Path) -> io::Result<Self> {
+        let entries = read_central_directory(bytes)?;
+        let mut names = HashMap::with_capacity(entries.len());
+        for (i, entry) in entries.iter().enumerate() {
+            names.entry(entry.name.clone()).or_insert(i);
+        }
+        Ok(Self {
+            bytes,
+            path: path.to_owned(),
+            entries,
+            names: Arc::new(names),
+        })
+    }
+
+    /// Returns the path of the document to load.
+    ///
+    /// This is the document listed in `manifest.xml` for COLLADA (.zae)
+    /// archives, the model listed in `_rels/.rels` for 3MF containers, and
+    /// otherwise the model file closest to the root of the archive.
+    pub(crate) fn root(&self) -> io::Result<PathBuf> {
+        if let Some(manifest) = self.get("manifest.xml") {
+            let manifest = self.read_entry(manifest)?;
+            let manifest = str::from_utf8(&manifest).map_err(crate::error::invalid_data)?;
+            let doc = roxmltree::Document::parse(manifest).map_err(crate::error::invalid_data)?;
+            if let Some(root) = doc
+                .descendants()
+                .find(|n| n.has_tag_name("dae_root"))
+                .and_then(|n| n.text())
+            {
+                return Ok(self.path.join(&*percent_decode(root.trim())));
+            }
+        }
+        if let Some(rels) = self.get("_rels/.rels") {
+            let rels = self.read_entry(rels)?;
+            let rels = str::from_utf8(&rels).map_err(crate::error::invalid_data)?;
+            let doc = roxmltree::Document::parse(rels).map_err(crate::error::invalid_data)?;
+            if let Some(target) = doc
+                .descendants()
+                .filter(|n| n.has_tag_name("Relationship"))
+                .find(|n| {
+                    n.attribute("Type")
+                        .map_or(false, |t| t.ends_with("/3dmodel"))
+                })
+                .and_then(|n| n.attribute("Target"))
+            {
+                return Ok(self.path.join(target.trim_start_matches('/')));
+            }
+        }
+        self.entries
+            .iter()
+            .filter(|e| !e.name.starts_with("__MACOSX/"))
+            .filter(|e| {
+                let name = e.name.rsplit('/').next().unwrap().to_ascii_lowercase();
+                // Compressed files (e.g., a.stl.gz) are decompressed when loaded.
+                let name = name.strip_suffix(".gz").unwrap_or(&name);
+                name.rsplit_once('.')
+                    .map_or(false, |(_, ext)| MODEL_EXTENSIONS.contains(&ext))
+            })
+            .min_by_key(|e| e.name.matches('/').count())
+            .map(|e| self.path.join(&e.name))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("no model file found in archive {}", self.path.display()),
+                )
+            })
+    }
+
+    /// Reads the entry at the given path.
+    pub(crate) fn read(&self, path: &Path) -> io::Result<Cow<'a, [u8]>> {
+        match entry_name(&self.path, path).and_then(|name| self.get(&name)) {
+            Some(entry) => self.read_entry(entry),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "{} is not found in archive {}",
+                    path.display(),
+                    self.path.display()
+                ),
+            )),
+        }
+    }
+
+    /// Returns a function that checks whether an entry exists at the given
+    /// path, for use by [`TextureResolver`](crate::TextureResolver).
+    pub(crate) fn exists_fn(&self) -> Arc<dyn Fn(&Path) -> bool + Send + Sync> {
+        let archive_path = self.path.clone();
+        let names = Arc::clone(&self.names);
+        Arc::new(move |path| {
+            entry_name(&archive_path, path).map_or(false, |name| lookup(&names, &name).is_some())
+        })
+    }
+
+    /// Moves textures stored in the archive into `scene.embedded_textures`,
+    /// since their paths cannot be read from the file system.
+    pub(crate) fn embed_textures(&self, scene: &mut common::Scene) -> io::Result<()> {
+        let mut embedded: HashMap<String, usize> = HashMap::new();
+        for material in &mut scene.materials {
+            let t = &mut material.texture;
+            for texture in [
+                &mut t.diffuse,
+                &mut t.specular,
+                &mut t.ambient,
+                &mut t.emissive,
+                &mut t.height,
+                &mut t.normal,
+                &mut t.shininess,
+                &mut t.opacity,
+                &mut t.displacement,
+                &mut t.lightmap,
+                &mut t.reflection,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                let name = match texture.path().and_then(|p| entry_name(&self.path, p)) {
+                    Some(name) => name,
+                    None => continue,
+                };
+                let entry = match self.get(&name) {
+                    Some(entry) => entry,
+                    None => continue,
+                };
+                let index = match embedded.get(&entry.name) {
+                    Some(&index) => index,
+                    None => {
+                        scene.embedded_textures.push(common::EmbeddedTexture {
+                            name: entry.name.clone(),
+                            data: self.read_entry(entry)?.into_owned(),
+                            format_hint: entry
+                                .name
+                                .rsplit('/')
+                                .next()
+                                .unwrap()
+                                .rsplit_once('.')
+                                .map(|(_, ext)| ext.to_ascii_lowercase()),
+                        });
+                        let index = scene.embedded_textures.len() - 1;
+                        embedded.insert(entry.name.clone(), index);
+                        index
+                    }
+                };
+                texture.source = common::TextureSource::Embedded(index);
+            }
+        }
+        Ok(())
+    }
+
+    fn get(&self, name: &str) -> Option<&Entry> {
+        lookup(&self.names, name).map(|i| &self.entries[i])
+    }
+
+    fn read_entry(&self, entry: &Entry) -> io::Result<Cow<'a, [u8]>> {
+        if entry.flags & 1 != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("encrypted archive entry {:?} is not supported", entry.name),
+            ));
+        }
+        let bytes = self.bytes;
+        let data = (|| {
+            let header = bytes.get(entry.offset..)?;
+            if read_u32(header, 0)? != LOCAL_FILE_HEADER {
+                return None;
+            }
+            let start = 30 + read_u16(header, 26)? as usize + read_u16(header, 28)? as usize;
+            header.get(start..start.checked_add(entry.compressed_size)?)
+        })()
+        .ok_or_else(|| {
+            crate::error::invalid_data(format!("invalid archive entry {:?}", entry.name))
+        })?;
+        let data = match entry.method {
+            STORED => Cow::Borrowed(data),
+            DEFLATED => Cow::Owned(
+                miniz_oxide::inflate::decompress_to_vec_with_limit(data, entry.size).map_err(
+                    |e| {
+                        crate::error::invalid_data(format!(
+                            "failed to decompress archive entry {:?}: {e}",
+                            entry.name
+                        ))
+                    },
+                )?,
+            ),
+            method => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!(
+                        "compression method {method} of archive entry {:?} is not supported",
+                        entry.name
+                    ),
+                ))
+            }
+        };
+        if data.len() != entry.size {
+            return Err(crate::error::invalid_data(format!(
+                "size of archive entry {:?} is inconsistent with its contents",
+                entry.name
+            )));
+        }
+        Ok(data)
+    }
+}
+
+/// Looks up an entry, ignoring ASCII case if there is no exact match.
+fn lookup(names: &HashMap<String, usize>, name: &str) -> Option<usize> {
+    if let Some(&i) = names.get(name) {
+        return Some(i);
+    }
+    names
+        .iter()
+        .filter(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, &i)| i)
+        .min()
+}
+
+/// Converts a path under the archive to the normalized name of an entry.
+///
+/// Absolute paths that are not under the archive, such as `/3D/3dmodel.model`
+/// in 3MF containers, are relative to the root of the archive.
+fn entry_name(archive_path: &Path, path: &Path) -> Option<String> {
+    let rel = match path.strip_prefix(archive_path) {
+        Ok(rel) => rel,
+        Err(_) if path.has_root() => path,
+        Err(_) => return None,
+    };
+    let mut name: Vec<&str> = vec![];
+    for component in rel.components() {
+        match component {
+            Component::Normal(n) => name.push(n.to_str()?),
+            Component::ParentDir => {
+                name.pop()?;
+            }
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+        }
+    }
+    Some(name.join("/"))
+}
+
+fn read_central_directory(bytes: &[u8]) -> io::Result<Vec<Entry>> {
+    fn invalid() -> io::Error {
+        crate::error::invalid_data("invalid or unsupported zip archive")
+    }
+    fn to_usize(n: u64) -> io::Result<usize> {
+        usize::try_from(n).map_err(|_| invalid())
+    }
+
+    // The end of central directory record is followed by a comment of up to
+    // 65535 bytes.
+    let min = bytes.len().saturating_sub(22 + 0xFFFF);
+    let eocd = (min..bytes.len().saturating_sub(21))
+        .rev()
+        .find(|&i| read_u32(bytes, i) == Some(END_OF_CENTRAL_DIRECTORY))
+        .ok_or_else(invalid)?;
+    let mut count = read_u16(bytes, eocd + 10).ok_or_else(invalid)? as u64;
+    let mut offset = read_u32(bytes, eocd + 16).ok_or_else(invalid)? as u64;
+    if count == 0xFFFF || offset == 0xFFFF_FFFF {
+        let zip64 = eocd
+            .checked_sub(20)
+            .filter(|&i| read_u32(bytes, i) == Some(ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR))
+            .and_then(|i| read_u64(bytes, i + 8))
+            .ok_or_else(invalid)?;
+        let zip64 = to_usize(zip64)?;
+        if read_u32(bytes, zip64) != Some(ZIP64_END_OF_CENTRAL_DIRECTORY) {
+            return Err(invalid());
+        }
+        count = read_u64(bytes, zip64 + 32).ok_or_else(invalid)?;
+        offset = read_u64(bytes, zip64 + 48).ok_or_else(invalid)?;
+    }
+
+    let mut pos = to_usize(offset)?;
+    // Each header is at least 46 bytes.
+    let mut entries = Vec::with_capacity(to_usize(count)?.min(bytes.len() / 46));
+    for _ in 0..count {
+        if read_u32(bytes, pos) != Some(CENTRAL_DIRECTORY_HEADER) {
+            return Err(invalid());
+        }
+        let header = &bytes[pos..];
+        let field = |i| read_u16(header, i).ok_or_else(invalid);
+        let flags = field(8)?;
+        let method = field(10)?;
+        let mut compressed_size = read_u32(header, 20).ok_or_else(invalid)? as u64;
+        let mut size = read_u32(header, 24).ok_or_else(invalid)? as u64;
+        let name_len = field(28)? as usize;
+        let extra_len = field(30)? as usize;
+        let comment_len = field(32)? as usize;
+        let mut offset = read_u32(header, 42).ok_or_else(invalid)? as u64;
+        let name = header.get(46..46 + name_len).ok_or_else(invalid)?;
+        let mut extra = header
+            .get(46 + name_len..46 + name_len + extra_len)
+            .ok_or_else(invalid)?;
+        // Zip64 extended information extra field contains the fields that
+        // do not fit in the header, in this order.
+        while let (Some(id), Some(len)) = (read_u16(extra, 0), read_u16(extra, 2)) {
+            let data = extra.get(4..4 + len as usize).ok_or_else(invalid)?;
+            if id == 0x0001 {
+                let mut data = data;
+                for field in [&mut size, &mut compressed_size, &mut offset] {
+                    if *field == 0xFFFF_FFFF {
+                        *field = read_u64(data, 0).ok_or_else(invalid)?;
+                        data = &data[8..];
+                    }
+                }
+            }
+            extra = &extra[4 + len as usize..];
+        }
+        pos += 46 + name_len + extra_len + comment_len;
+
+        let name = String::from_utf8_lossy(name).replace('\\', "/");
+        if name.ends_with('/') {
+            // Directory
+            continue;
+        }
+        entries.push(Entry {
+            name,
+            flags,
+            method,
+            compressed_size: to_usize(compressed_size)?,
+            size: to_usize(size)?,
+            offset: to_usize(offset)?,
+        });
+    }
+    Ok(entries)
+}
+
+fn read_u16(bytes: &[u8], i: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(i..i.checked_add(2)?)?.try_into().unwrap(),
+    ))
+}
+fn read_u32(bytes: &[u8], i: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(i..i.checked_add(4)?)?.try_into().unwrap(),
+    ))
+}
+fn read_u64(bytes: &[u8], i: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        bytes.get(i..i.checked_add(8)?)?.try_into().unwrap(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write as _;
+
+    use super::*;
+
+    /// Creates a zip archive from (name, contents, compress) entries.
+    #[allow(clippy::cast_possible_truncation)]
+    fn zip(entries: &[(&str, &[u8], bool)]) -> Vec<u8> {
+        let mut bytes = vec![];
+        let mut central_directory = vec![];
+        for &(name, contents, compress) in entries {
+            let (method, data) = if compress {
+                (DEFLATED, miniz_oxide::deflate::compress_to_vec(contents, 6))
+            } else {
+                (STORED, contents.to_vec())
+            };
+            let offset = bytes.len() as u32;
+            let mut header = vec![];
+            header.extend_from_slice(&[20, 0, 0, 0]); // version needed, flags
+            header.extend_from_slice(&method.to_le_bytes());
+            header.extend_from_slice(&[0; 8]); // time, date, crc-32
+            header.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            header.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            header.extend_from_slice(&[0, 0]); // extra field length
+            bytes.extend_from_slice(&LOCAL_FILE_HEADER.to_le_bytes());
+            bytes.extend_from_slice(&header);
+            bytes.extend_from_slice(name.as_bytes());
+            bytes.extend_from_slice(&data);
+            central_directory.extend_from_slice(&CENTRAL_DIRECTORY_HEADER.to_le_bytes());
+            central_directory.extend_from_slice(&[20, 0]); // version made by
+            central_directory.extend_from_slice(&header);
+            central_directory.extend_from_slice(&[0; 10]); // comment length, disk, attributes
+            central_directory.extend_from_slice(&offset.to_le_bytes());
+            central_directory.extend_from_slice(name.as_bytes());
+        }
+        let offset = bytes.len() as u32;
+        bytes.extend_from_slice(&central_directory);
+        bytes.extend_from_slice(&END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+        bytes.extend_from_slice(&[0; 4]); // disk numbers
+        bytes.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&(central_directory.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&offset.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0]); // comment length
+        bytes
+    }
+
+    #[cfg(feature = "obj")]
+    #[test]
+    fn obj() {
+        let bytes = zip(&[
+            ("readme.txt", b"", false),
+            (
+                "models/box.obj",
+                b"mtllib box.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl red\nf 1 2 3\n",
+                true,
+            ),
+            (
+                "models/box.mtl",
+                b"newmtl red\nKd 1 0 0\nmap_Kd ../Textures/Red.png\n",
+                true,
+            ),
+            ("textures/red.png", b"\x89PNG", false),
+        ]);
+        let scene = crate::Loader::default()
+            .collect_warnings(true)
+            .load_from_slice(&bytes, "assets/box.zip")
+            .unwrap();
+        assert_eq!(scene.meshes.len(), 1);
+        assert_eq!(scene.meshes[0].faces, [[0, 1, 2]]);
+        assert!(scene.warnings.is_empty(), "{:?}", scene.warnings);
+        let texture = scene.materials[0].texture.diffuse.as_ref().unwrap();
+        assert_eq!(texture.source, common::TextureSource::Embedded(0));
+        assert_eq!(scene.embedded_textures[0].name, "textures/red.png");
+        assert_eq!(scene.embedded_textures[0].data, b"\x89PNG");
+        assert_eq!(
+            scene.embedded_textures[0].format_hint.as_deref(),
+            Some("png")
+        );
+
+        let e = crate::Loader::default()
+            .load_zip_from_slice(&zip(&[("readme.txt", b"", true)]), "a.zip")
+            .unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[cfg(all(feature = "gzip", feature = "stl"))]
+    #[allow(clippy::cast_possible_truncation)]
+    #[test]
+    fn gzip() {
+        let stl = b"solid a\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\nvertex 1 0 0\n\
+            vertex 0 1 0\nendloop\nendfacet\nendsolid a\n";
+        let mut gz = b"\x1f\x8b\x08\0\0\0\0\0\0\xff".to_vec();
+        gz.extend_from_slice(&miniz_oxide::deflate::compress_to_vec(stl, 6));
+        gz.extend_from_slice(&[0; 4]); // crc-32
+        gz.extend_from_slice(&(stl.len() as u32).to_le_bytes());
+        let bytes = zip(&[("readme.txt", b"", false), ("models/a.stl.gz", &gz, false)]);
+        let scene = crate::Loader::default()
+            .load_from_slice(&bytes, "a.zip")
+            .unwrap();
+        assert_eq!(scene.meshes[0].faces, [[0, 1, 2]]);
+    }
+
+    #[test]
+    fn three_mf() {
+        let model = br#"<?xml version="1.0" encoding="UTF-8"?>
+<model unit="millimeter" xmlns="http://schemas.microsoft.com/3dmanufacturing/core/2015/02">
+  <resources>
+    <object id="1" name="triangle" type="model">
+      <mesh>
+        <vertices>
+          <vertex x="0" y="0" z="0" />
+          <vertex x="1" y="0" z="0" />
+          <vertex x="0" y="1" z="0" />
+        </vertices>
+        <triangles>
+          <triangle v1="0" v2="1" v3="2" />
+        </triangles>
+      </mesh>
+    </object>
+    <object id="2" type="model">
+      <components>
+        <component objectid="1" transform="0 1 0 -1 0 0 0 0 1 0 0 1" />
+      </components>
+    </object>
+  </resources>
+  <build>
+    <item objectid="1" />
+    <item objectid="2" transform="1 0 0 0 1 0 0 0 1 10 0 0" />
+  </build>
+</model>"#;
+        let rels = br#"<?xml version="1.0" encoding="UTF-8"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+  <Relationship Target="/3D/3dmodel.model" Id="rel0" Type="http://schemas.microsoft.com/3dmanufacturing/2013/01/3dmodel" />
+</Relationships>"#;
+        let bytes = zip(&[
+            ("[Content_Types].xml", b"", false),
+            ("_rels/.rels", rels, true),
+            ("3D/3dmodel.model", model, true),
+        ]);
+        let scene = crate::Loader::default()
+            .load_from_slice(&bytes, "a.3mf")
+            .unwrap();
+        assert_eq!(scene.meshes.len(), 2);
+        assert_eq!(scene.meshes[0].name, "triangle");
+        assert_eq!(scene.meshes[0].vertices[1], [1., 0., 0.]);
+        // Rotated by the component and then translated by the build item.
+        assert_eq!(
+            scene.meshes[1].vertices,
+            [[10., 0., 1.], [10., 1., 1.], [9., 0., 1.]]
+        );
+
+        // Shared components must not be expanded exponentially.
+        let mut objects = String::new();
+        for id in 1..=30 {
+            let c = id - 1;
+            write!(
+                objects,
+                r#"<object id="{id}"><components><component objectid="{c}" /><component objectid="{c}" /></components></object>"#
+            )
+            .unwrap();
+        }
+        let model = format!(
+            r#"<model><resources><object id="0"><mesh /></object>{objects}</resources><build><item objectid="30" /></build></model>"#
+        );
+        let e = threemf::from_slice(model.as_bytes(), None).unwrap_err();
+        assert_eq!(
+            crate::Error::from_io_error(&e).unwrap().kind(),
+            crate::ErrorKind::TooLarge
+        );
+    }
+}
@@ -0,0 +1,265 @@
+//! Parser of the model part of 3MF containers.
+//!
+//! Only the geometry of the [3MF core specification] is supported: meshes of
+//! objects placed by build items, including objects composed of other
+//! objects. Materials and extensions are ignored.
+//!
+//! [3MF core specification]: https://github.com/3MFConsortium/spec_core/blob/master/3MF%20Core%20Specification.md
+
+use std::{collections::HashMap, io, path::Path, str};
+
+use crate::common::{Material, Mesh, Scene};
+
+/// Affine transform in the row-vector convention of 3MF: the first three rows
+/// are the linear part and the last row is the translation.
+type Transform = [[f32; 3]; 4];
+
+const IDENTITY: Transform = [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.], [0., 0., 0.]];
+
+/// Components nested deeper than this are considered a reference cycle.
+const MAX_DEPTH: usize = 32;
+/// The maximum number of object instances in the build.
+///
+/// An object can be used as a component by multiple objects, so the number
+/// of instances grows exponentially with the depth of the components.
+const MAX_INSTANCES: usize = 1 << 16;
+
+struct Object<'a, 'input> {
+    name: Option<&'a str>,
+    mesh: Option<roxmltree::Node<'a, 'input>>,
+    components: Vec<(u32, Transform)>,
+}
+
+#[derive(Default)]
+struct Builder {
+    /// Meshes of objects that have already been parsed.
+    parsed: HashMap<u32, Mesh>,
+    instances: usize,
+    meshes: Vec<Mesh>,
+}
+
+pub(crate) fn from_slice(bytes: &[u8], path: Option<&Path>) -> io::Result<Scene> {
+    let s = str::from_utf8(bytes).map_err(|e| format_err!(ErrorKind::Syntax, "{e}"))?;
+    let doc = roxmltree::Document::parse(s).map_err(|e| format_err!(ErrorKind::Syntax, "{e}"))?;
+    let model = doc.root_element();
+    if model.tag_name().name() != "model" {
+        bail!(
+            ErrorKind::UnexpectedElement,
+            "expected <model> element in 3MF model {path:?}"
+        );
+    }
+
+    let mut objects = HashMap::new();
+    let resources = model.children().filter(|n| n.has_tag_name("resources"));
+    for node in resources
+        .flat_map(|n| n.children())
+        .filter(|n| n.has_tag_name("object"))
+    {
+        let id = parse_attribute(node, "id")?;
+        let mut object = Object {
+            name: node.attribute("name"),
+            mesh: None,
+            components: vec![],
+        };
+        for child in node.children().filter(roxmltree::Node::is_element) {
+            match child.tag_name().name() {
+                "mesh" => object.mesh = Some(child),
+                "components" => {
+                    for c in child.children().filter(|n| n.has_tag_name("component")) {
+                        object
+                            .components
+                            .push((parse_attribute(c, "objectid")?, parse_transform(c)?));
+                    }
+                }
+                _ => {}
+            }
+        }
+        objects.insert(id, object);
+    }
+
+    let mut builder = Builder::default();
+    let build = model.children().find(|n| n.has_tag_name("build"));
+    for item in build
+        .iter()
+        .flat_map(roxmltree::Node::children)
+        .filter(|n| n.has_tag_name("item"))
+    {
+        let id = parse_attribute(item, "objectid")?;
+        builder.push_object(&objects, id, parse_transform(item)?, 0)?;
+    }
+
+    Ok(Scene {
+        materials: vec![Material::default()],
+        meshes: builder.meshes,
+        ..Default::default()
+    })
+}
+
+impl Builder {
+    fn push_object(
+        &mut self,
+        objects: &HashMap<u32, Object<'_, '_>>,
+        id: u32,
+        transform: Transform,
+        depth: usize,
+    ) -> io::Result<()> {
+        let object = match objects.get(&id) {
+            Some(object) => object,
+            None => bail!(
+                ErrorKind::MissingElement,
+                "object {id} is not found in 3MF model"
+            ),
+        };
+        if depth > MAX_DEPTH {
+            bail!(
+                ErrorKind::TooLarge,
+                "components of object {id} are nested too deeply in 3MF model"
+            );
+        }
+        self.instances += 1;
+        if self.instances > MAX_INSTANCES {
+            bail!(
+                ErrorKind::TooLarge,
+                "too many object instances in 3MF model (more than {MAX_INSTANCES})"
+            );
+        }
+        if let Some(node) = object.mesh {
+            let mut mesh = match self.parsed.get(&id) {
+                Some(mesh) => mesh.clone(),
+                None => {
+                    let mut mesh = parse_mesh(node)?;
+                    mesh.name = object.name.unwrap_or_default().to_owned();
+                    self.parsed.insert(id, mesh.clone());
+                    mesh
+                }
+            };
+            if transform != IDENTITY {
+                for v in &mut mesh.vertices {
+                    *v = transform_point(&transform, *v);
+                }
+            }
+            self.meshes.push(mesh);
+        }
+        for &(child, child_transform) in &object.components {
+            self.push_object(objects, child, mul(&child_transform, &transform), depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_mesh(node: roxmltree::Node<'_, '_>) -> io::Result<Mesh> {
+    let mut mesh = Mesh::default();
+    for child in node.children().filter(roxmltree::Node::is_element) {
+        match child.tag_name().name() {
+            "vertices" => {
+                for v in child.children().filter(|n| n.has_tag_name("vertex")) {
+                    mesh.vertices.push([
+                        parse_float_attribute(v, "x")?,
+                        parse_float_attribute(v, "y")?,
+                        parse_float_attribute(v, "z")?,
+                    ]);
+                }
+            }
+            "triangles" => {
+                for t in child.children().filter(|n| n.has_tag_name("triangle")) {
+                    let face = [
+                        parse_attribute(t, "v1")?,
+                        parse_attribute(t, "v2")?,
+                        parse_attribute(t, "v3")?,
+                    ];
+                    if face.iter().any(|&i| i as usize >= mesh.vertices.len()) {
+                        bail!(
+                            ErrorKind::FaceIndexOutOfBounds,
+                            "triangle refers to a vertex that does not exist in 3MF model"
+                        );
+                    }
+                    mesh.faces.push(face);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(mesh)
+}
+
+fn parse_attribute(node: roxmltree::Node<'_, '_>, name: &str) -> io::Result<u32> {
+    match node.attribute(name) {
+        Some(v) => v.trim().parse().map_err(|_| {
+            format_err!(
+                ErrorKind::InvalidInt,
+                "invalid value {v:?} of {name:?} attribute of <{}> in 3MF model",
+                node.tag_name().name()
+            )
+        }),
+        None => bail!(
+            ErrorKind::MissingElement,
+            "missing {name:?} attribute of <{}> in 3MF model",
+            node.tag_name().name()
+        ),
+    }
+}
+
+fn parse_float_attribute(node: roxmltree::Node<'_, '_>, name: &str) -> io::Result<f32> {
+    match node.attribute(name) {
+        Some(v) => v.trim().parse().map_err(|_| {
+            format_err!(
+                ErrorKind::InvalidFloat,
+                "invalid value {v:?} of {name:?} attribute of <{}> in 3MF model",
+                node.tag_name().name()
+            )
+        }),
+        None => bail!(
+            ErrorKind::MissingElement,
+            "missing {name:?} attribute of <{}> in 3MF model",
+            node.tag_name().name()
+        ),
+    }
+}
+
+/// Parses the `transform` attribute "m00 m01 m02 m10 m11 m12 m20 m21 m22 m30 m31 m32".
+fn parse_transform(node: roxmltree::Node<'_, '_>) -> io::Result<Transform> {
+    let s = match node.attribute("transform") {
+        Some(s) => s,
+        None => return Ok(IDENTITY),
+    };
+    let mut values = s.split_ascii_whitespace().map(|v| v.parse::<f32>().ok());
+    let mut transform = IDENTITY;
+    for row in &mut transform {
+        for x in row {
+            *x = match values.next() {
+                Some(Some(v)) => v,
+                _ => bail!(
+                    ErrorKind::InvalidFloat,
+                    "invalid transform {s:?} in 3MF model"
+                ),
+            };
+        }
+    }
+    if values.next().is_some() {
+        bail!(
+            ErrorKind::InvalidSize,
+            "invalid transform {s:?} in 3MF model"
+        );
+    }
+    Ok(transform)
+}
+
+fn transform_point(m: &Transform, [x, y, z]: [f32; 3]) -> [f32; 3] {
+    let mut out = m[3];
+    for (i, o) in out.iter_mut().enumerate() {
+        *o += x * m[0][i] + y * m[1][i] + z * m[2][i];
+    }
+    out
+}
+
+/// Returns the transform that applies `a` and then `b`.
+fn mul(a: &Transform, b: &Transform) -> Transform {
+    let mut out = [[0.; 3]; 4];
+    for (i, row) in out.iter_mut().enumerate().take(3) {
+        for (j, x) in row.iter_mut().enumerate() {
+            *x = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    out[3] = transform_point(b, a[3]);
+    out
+}
@@ -0,0 +1,218 @@
+use super::*;
+
+/// The `<library_animations>` element.
+///
+/// See the [specification][1.4] for details.
+///
+/// [1.4]: https://www.khronos.org/files/collada_spec_1_4.pdf#page=85
+#[derive(Default)]
+pub(super) struct LibraryAnimations<'a> {
+    // /// The unique identifier of this element.
+    // pub(super) id: Option<&'a str>,
+    // /// The name of this element.
+    // pub(super) name: Option<&'a str>,
+    pub(super) animations: Vec<Animation<'a>>,
+}
+
+/// The `<animation>` element.
+///
+/// Channels and samplers of nested `<animation>` elements are merged into
+/// the outermost `<animation>` element.
+///
+/// See the [specification][1.4] for details.
+///
+/// [1.4]: https://www.khronos.org/files/collada_spec_1_4.pdf#page=30
+pub(super) struct Animation<'a> {
+    /// The unique identifier of this element.
+    pub(super) id: Option<&'a str>,
+    /// The name of this element.
+    pub(super) name: Option<&'a str>,
+    pub(super) samplers: HashMap<&'a str, Sampler<'a>>,
+    pub(super) channels: Vec<Channel<'a>>,
+}
+
+/// The `<sampler>` element.
+///
+/// See the [specification][1.4] for details.
+///
+/// [1.4]: https://www.khronos.org/files/collada_spec_1_4.pdf#page=137
+pub(super) struct Sampler<'a> {
+    pub(super) input: UnsharedInput<'a>,
+    pub(super) output: UnsharedInput<'a>,
+    pub(super) interpolation: Option<UnsharedInput<'a>>,
+    pub(super) in_tangent: Option<UnsharedInput<'a>>,
+    pub(super) out_tangent: Option<UnsharedInput<'a>>,
+}
+
+/// The `<channel>` element.
+///
+/// See the [specification][1.4] for details.
+///
+/// [1.4]: https://www.khronos.org/files/collada_spec_1_4.pdf#page=44
+pub(super) struct Channel<'a> {
+    /// The ID of the sampler.
+    pub(super) source: &'a str,
+    /// The SID path of the animated element, e.g., `node/translate.X`.
+    pub(super) target: &'a str,
+}
+
+// -----------------------------------------------------------------------------
+// Parsing
+
+pub(super) fn parse_library_animations<'a>(
+    cx: &mut Context<'a>,
+    node: xml::Node<'a, '_>,
+) -> io::Result<()> {
+    debug_assert_eq!(node.tag_name().name(), "library_animations");
+    // cx.library_animations.id = node.attribute("id");
+    // cx.library_animations.name = node.attribute("name");
+
+    for node in node.element_children() {
+        match node.tag_name().name() {
+            "animation" => {
+                let mut animation = Animation {
+                    id: node.attribute("id"),
+                    name: node.attribute("name"),
+                    samplers: HashMap::new(),
+                    channels: vec![],
+                };
+                parse_animation(cx, node, &mut animation)?;
+                cx.library_animations.animations.push(animation);
+            }
+            "asset" | "extra" => { /* skip */ }
+            _ => return Err(error::unexpected_child_elem(node)),
+        }
+    }
+
+    Ok(())
+}
+
+/*
+The `<animation>` element
+
+Attributes:
+- `id` (xs:ID, Optional)
+- `name` (xs:token, Optional)
+
+Child elements must appear in the following order if present:
+- `<asset>` (0 or 1)
+- `<animation>` (0 or more)
+- `<source>` (0 or more)
+- `<sampler>` (0 or more)
+- `<channel>` (0 or more)
+- `<extra>` (0 or more)
+*/
+fn parse_animation<'a>(
+    cx: &mut Context<'a>,
+    node: xml::Node<'a, '_>,
+    animation: &mut Animation<'a>,
+) -> io::Result<()> {
+    debug_assert_eq!(node.tag_name().name(), "animation");
+
+    for child in node.element_children() {
+        match child.tag_name().name() {
+            "animation" => {
+                parse_animation(cx, child, animation)?;
+            }
+            "source" => {
+                // Sources are referenced by ID in the same way as sources in
+                // <mesh>, so they share the same table.
+                let s = Source::parse(child)?;
+                if let Some(acc) = s.accessor {
+                    cx.library_geometries.accessors.insert(s.id, acc);
+                }
+                if let Some(data) = s.array_element {
+                    cx.library_geometries.array_data.insert(data.id, data.data);
+                }
+            }
+            "sampler" => {
+                let sampler = parse_sampler(child)?;
+                // Channels refer to samplers by ID, so a sampler without an
+                // ID (which is allowed) cannot be used.
+                if let Some(id) = child.attribute("id") {
+                    animation.samplers.insert(id, sampler);
+                }
+            }
+            "channel" => {
+                let source = child.required_attribute("source")?;
+                let source = match source.strip_prefix('#') {
+                    Some(source) => source,
+                    None => bail!(
+                        ErrorKind::Unsupported,
+                        "unsupported reference {:?} in source attribute of <{}> element ({})",
+                        source,
+                        child.tag_name().name(),
+                        child.node_location()
+                    ),
+                };
+                animation.channels.push(Channel {
+                    source,
+                    target: child.required_attribute("target")?,
+                });
+            }
+            "asset" | "extra" => { /* skip */ }
+            _ => return Err(error::unexpected_child_elem(child)),
+        }
+    }
+
+    Ok(())
+}
+
+/*
+The `<sampler>` element
+
+Attributes:
+- `id` (xs:ID, Optional)
+- `pre_behavior` (Enumeration, Optional)
+- `post_behavior` (Enumeration, Optional)
+
+Child elements:
+- `<input>` (1 or more)
+*/
+fn parse_sampler<'a>(node: xml::Node<'a, '_>) -> io::Result<Sampler<'a>> {
+    debug_assert_eq!(node.tag_name().name(), "sampler");
+    let mut input = None;
+    let mut output = None;
+    let mut interpolation = None;
+    let mut in_tangent = None;
+    let mut out_tangent = None;
+
+    for child in node.element_children() {
+        match child.tag_name().name() {
+            "input" => {
+                let i = UnsharedInput::parse(child)?;
+                match i.semantic {
+                    InputSemantic::INPUT => input = Some(i),
+                    InputSemantic::OUTPUT => output = Some(i),
+                    InputSemantic::INTERPOLATION => interpolation = Some(i),
+                    InputSemantic::IN_TANGENT => in_tangent = Some(i),
+                    InputSemantic::OUT_TANGENT => out_tangent = Some(i),
+                    _semantic => {
+                        // warn!(
+                        //     "unsupported semantic {:?} in <input> ({})",
+                        //     semantic,
+                        //     child.node_location(),
+                        // );
+                    }
+                }
+            }
+            _ => return Err(error::unexpected_child_elem(child)),
+        }
+    }
+
+    match (input, output) {
+        (Some(input), Some(output)) => Ok(Sampler {
+            input,
+            output,
+            interpolation,
+            in_tangent,
+            out_tangent,
+        }),
+        _ => bail!(
+            ErrorKind::MissingElement,
+            "<{}> element must contain <input> elements with semantic=\"INPUT\" and semantic=\"OUTPUT\" ({})",
+            node.tag_name().name(),
+            node.node_location()
+        ),
+    }
+}
@@ -0,0 +1,362 @@
+use super::*;
+
+/// The `<library_controllers>` element.
+///
+/// See the [specification][1.4] for details.
+///
+/// [1.4]: https://www.khronos.org/files/collada_spec_1_4.pdf#page=95
+#[derive(Default)]
+pub(super) struct LibraryControllers<'a> {
+    // /// The unique identifier of this element.
+    // pub(super) id: Option<&'a str>,
+    // /// The name of this element.
+    // pub(super) name: Option<&'a str>,
+    pub(super) controllers: HashMap<&'a str, Controller<'a>>,
+}
+
+/// The `<controller>` element.
+///
+/// See the [specification][1.4] for details.
+///
+/// [1.4]: https://www.khronos.org/files/collada_spec_1_4.pdf#page=58
+pub(super) struct Controller<'a> {
+    /// The unique identifier of this element.
+    pub(super) id: &'a str,
+    // /// The name of this element.
+    // pub(super) name: Option<&'a str>,
+    pub(super) skin: Skin<'a>,
+}
+
+/// The `<skin>` element.
+///
+/// See the [specification][1.4] for details.
+///
+/// [1.4]: https://www.khronos.org/files/collada_spec_1_4.pdf#page=142
+pub(super) struct Skin<'a> {
+    /// The URI of the base mesh.
+    pub(super) source: Uri<'a, Geometry<'a>>,
+    pub(super) bind_shape_matrix: Matrix4x4,
+    pub(super) joints: Joints<'a>,
+    pub(super) vertex_weights: VertexWeights<'a>,
+}
+
+/// The `<joints>` element.
+///
+/// See the [specification][1.4] for details.
+///
+/// [1.4]: https://www.khronos.org/files/collada_spec_1_4.pdf#page=92
+pub(super) struct Joints<'a> {
+    pub(super) joint: UnsharedInput<'a>,
+    pub(super) inv_bind_matrix: UnsharedInput<'a>,
+}
+
+/// The `<vertex_weights>` element.
+///
+/// See the [specification][1.4] for details.
+///
+/// [1.4]: https://www.khronos.org/files/collada_spec_1_4.pdf#page=158
+pub(super) struct VertexWeights<'a> {
+    pub(super) joint: SharedInput<'a>,
+    pub(super) weight: SharedInput<'a>,
+    /// The number of joints that influence each vertex in the base mesh.
+    pub(super) vcount: Vec<u32>,
+    /// The indices of joints and weights for each influence.
+    ///
+    /// The joint index `-1` refers to the bind shape.
+    pub(super) v: Vec<i32>,
+    pub(super) stride: u32,
+}
+
+// -----------------------------------------------------------------------------
+// Parsing
+
+pub(super) fn parse_library_controllers<'a>(
+    cx: &mut Context<'a>,
+    node: xml::Node<'a, '_>,
+) -> io::Result<()> {
+    debug_assert_eq!(node.tag_name().name(), "library_controllers");
+    // cx.library_controllers.id = node.attribute("id");
+    // cx.library_controllers.name = node.attribute("name");
+
+    for node in node.element_children() {
+        match node.tag_name().name() {
+            "controller" => {
+                if let Some(controller) = parse_controller(cx, node)? {
+                    cx.library_controllers
+                        .controllers
+                        .insert(controller.id, controller);
+                }
+            }
+            "asset" | "extra" => { /* skip */ }
+            _ => return Err(error::unexpected_child_elem(node)),
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_controller<'a>(
+    cx: &mut Context<'a>,
+    node: xml::Node<'a, '_>,
+) -> io::Result<Option<Controller<'a>>> {
+    debug_assert_eq!(node.tag_name().name(), "controller");
+    // The specification say it is optional, but it is actually required.
+    let id = node.required_attribute("id")?;
+    let mut skin = None;
+
+    for child in node.element_children() {
+        match child.tag_name().name() {
+            "skin" => {
+                skin = Some(parse_skin(cx, child)?);
+            }
+            "morph" => {
+                // warn!(
+                //     "<{}> child element in <{}> element is unsupported ({})",
+                //     child.tag_name().name(),
+                //     child.parent_element().unwrap().tag_name().name(),
+                //     child.node_location()
+                // );
+                return Ok(None);
+            }
+            "asset" | "extra" => { /* skip */ }
+            _ => return Err(error::unexpected_child_elem(child)),
+        }
+    }
+
+    let skin = match skin {
+        Some(skin) => skin,
+        None => return Err(error::exactly_one_elem(node, "skin")),
+    };
+
+    Ok(Some(Controller {
+        id,
+        // name: node.attribute("name"),
+        skin,
+    }))
+}
+
+/*
+The `<skin>` element
+
+Attributes:
+- `source` (xs:anyURI, Required)
+
+Child elements must appear in the following order if present:
+- `<bind_shape_matrix>` (0 or 1)
+- `<source>` (3 or more)
+- `<joints>` (1)
+- `<vertex_weights>` (1)
+- `<extra>` (0 or more)
+*/
+fn parse_skin<'a>(cx: &mut Context<'a>, node: xml::Node<'a, '_>) -> io::Result<Skin<'a>> {
+    debug_assert_eq!(node.tag_name().name(), "skin");
+    let source = node.parse_url("source")?;
+    let mut bind_shape_matrix = None;
+    let mut joints = None;
+    let mut vertex_weights = None;
+
+    for child in node.element_children() {
+        match child.tag_name().name() {
+            "bind_shape_matrix" => {
+                let content = xml::comma_to_period(child.trimmed_text());
+                let mut m = [0.; 16];
+                for (m, value) in m.iter_mut().zip(xml::parse_float_array_exact(&content, 16)) {
+                    *m = value.map_err(|e| {
+                        crate::error::with_message(
+                            &e,
+                            format!(
+                                "{e} in <{}> element ({})",
+                                child.tag_name().name(),
+                                child.text_location(),
+                            ),
+                        )
+                    })?;
+                }
+                bind_shape_matrix = Some(Matrix4x4::new(
+                    m[0], m[1], m[2], m[3], m[4], m[5], m[6], m[7], m[8], m[9], m[10], m[11],
+                    m[12], m[13], m[14], m[15],
+                ));
+            }
+            "source" => {
+                // Sources are referenced by ID in the same way as sources in
+                // <mesh>, so they share the same table.
+                let s = Source::parse(child)?;
+                if let Some(acc) = s.accessor {
+                    cx.library_geometries.accessors.insert(s.id, acc);
+                }
+                if let Some(data) = s.array_element {
+                    cx.library_geometries.array_data.insert(data.id, data.data);
+                }
+            }
+            "joints" => {
+                joints = Some(parse_joints(child)?);
+            }
+            "vertex_weights" => {
+                vertex_weights = Some(parse_vertex_weights(child)?);
+            }
+            "extra" => { /* skip */ }
+            _ => return Err(error::unexpected_child_elem(child)),
+        }
+    }
+
+    let joints = match joints {
+        Some(joints) => joints,
+        None => return Err(error::exactly_one_elem(node, "joints")),
+    };
+    let vertex_weights = match vertex_weights {
+        Some(vertex_weights) => vertex_weights,
+        None => return Err(error::exactly_one_elem(node, "vertex_weights")),
+    };
+
+    Ok(Skin {
+        source,
+        bind_shape_matrix: bind_shape_matrix.unwrap_or_default(),
+        joints,
+        vertex_weights,
+    })
+}
+
+fn parse_joints<'a>(node: xml::Node<'a, '_>) -> io::Result<Joints<'a>> {
+    debug_assert_eq!(node.tag_name().name(), "joints");
+    let mut input_joint = None;
+    let mut input_inv_bind_matrix = None;
+
+    for child in node.element_children() {
+        match child.tag_name().name() {
+            "input" => {
+                let i = UnsharedInput::parse(child)?;
+                match i.semantic {
+                    InputSemantic::JOINT => input_joint = Some(i),
+                    InputSemantic::INV_BIND_MATRIX => input_inv_bind_matrix = Some(i),
+                    _semantic => {
+                        // warn!(
+                        //     "unsupported semantic {:?} in <input> ({})",
+                        //     semantic,
+                        //     child.node_location(),
+                        // );
+                    }
+                }
+            }
+            "extra" => { /* skip */ }
+            _ => return Err(error::unexpected_child_elem(child)),
+        }
+    }
+
+    match (input_joint, input_inv_bind_matrix) {
+        (Some(joint), Some(inv_bind_matrix)) => Ok(Joints {
+            joint,
+            inv_bind_matrix,
+        }),
+        _ => bail!(
+            ErrorKind::MissingElement,
+            "<{}> element must contain <input> elements with semantic=\"JOINT\" and semantic=\"INV_BIND_MATRIX\" ({})",
+            node.tag_name().name(),
+            node.node_location()
+        ),
+    }
+}
+
+fn parse_vertex_weights<'a>(node: xml::Node<'a, '_>) -> io::Result<VertexWeights<'a>> {
+    debug_assert_eq!(node.tag_name().name(), "vertex_weights");
+    let count: u32 = node.parse_required_attribute("count")?;
+    let mut input_joint = None;
+    let mut input_weight = None;
+    let mut stride = 0;
+    let mut vcount = vec![];
+    let mut v = vec![];
+
+    for child in node.element_children() {
+        match child.tag_name().name() {
+            "input" => {
+                let i = SharedInput::parse(child)?;
+                stride = cmp::max(stride, i.offset + 1);
+                match i.semantic {
+                    InputSemantic::JOINT => input_joint = Some(i),
+                    InputSemantic::WEIGHT => input_weight = Some(i),
+                    _semantic => {
+                        // warn!(
+                        //     "unsupported semantic {:?} in <input> ({})",
+                        //     semantic,
+                        //     child.node_location(),
+                        // );
+                    }
+                }
+            }
+            "vcount" => {
+                if !vcount.is_empty() {
+                    return Err(error::multiple_elems(child));
+                }
+                // TODO: check large count
+                vcount.reserve(count as usize);
+                for value in xml::parse_int_array(child.trimmed_text()) {
+                    vcount.push(value.map_err(|e| {
+                        crate::error::with_message(
+                            &e,
+                            format!(
+                                "{e} in <{}> element ({})",
+                                child.tag_name().name(),
+                                child.text_location(),
+                            ),
+                        )
+                    })?);
+                }
+                if vcount.len() != count as usize {
+                    bail!(
+                        ErrorKind::InvalidSize,
+                        "incorrect number of values in <vcount> element, expected {} but found {} ({})",
+                        count,
+                        vcount.len(),
+                        child.node_location()
+                    );
+                }
+            }
+            "v" => {
+                if !v.is_empty() {
+                    return Err(error::multiple_elems(child));
+                }
+                for value in xml::parse_int_array(child.trimmed_text()) {
+                    v.push(value.map_err(|e| {
+                        crate::error::with_message(
+                            &e,
+                            format!(
+                                "{e} in <{}> element ({})",
+                                child.tag_name().name(),
+                                child.text_location(),
+                            ),
+                        )
+                    })?);
+                }
+            }
+            "extra" => { /* skip */ }
+            _ => return Err(error::unexpected_child_elem(child)),
+        }
+    }
+
+    let (joint, weight) = match (input_joint, input_weight) {
+        (Some(joint), Some(weight)) => (joint, weight),
+        _ => bail!(
+            ErrorKind::MissingElement,
+            "<{}> element must contain <input> elements with semantic=\"JOINT\" and semantic=\"WEIGHT\" ({})",
+            node.tag_name().name(),
+            node.node_location()
+        ),
+    };
+    let expected_count = vcount.iter().map(|&n| n as usize).sum::<usize>() * stride as usize;
+    if v.len() != expected_count {
+        bail!(
+            ErrorKind::InvalidSize,
+            "incorrect index count in <v> element, expected {} but found {} ({})",
+            expected_count,
+            v.len(),
+            node.node_location()
+        );
+    }
+
+    Ok(VertexWeights {
+        joint,
+        weight,
+        vcount,
+        v,
+        stride,
+    })
+}
@@ -310,7 +310,7 @@ impl FromStr for ShadeType {
             "lambert" => Self::Lambert,
             "phong" => Self::Phong,
             "blinn" => Self::Blinn,
-            _ => bail!("unknown shade type {:?}", s),
+            _ => bail!(ErrorKind::Syntax, "unknown shade type {:?}", s),
         })
     }
 }
@@ -486,10 +486,13 @@ fn parse_effect_color<'a>(
                 let mut iter = xml::parse_float_array_exact(&content, 4);
                 // TODO: include in parse_float_array_exact?
                 let map_err = |e| {
-                    format_err!(
-                        "{e} in <{}> element ({})",
-                        child.tag_name().name(),
-                        child.text_location(),
+                    crate::error::with_message(
+                        &e,
+                        format!(
+                            "{e} in <{}> element ({})",
+                            child.tag_name().name(),
+                            child.text_location(),
+                        ),
                     )
                 };
                 let r = iter.next().unwrap().map_err(map_err)?;
@@ -526,10 +529,9 @@ fn parse_effect_float(node: xml::Node<'_, '_>) -> io::Result<Option<f32>> {
         match child.tag_name().name() {
             "float" => {
                 let content = xml::comma_to_period(child.trimmed_text());
-                float = Some(
-                    float::parse(content.as_bytes())
-                        .ok_or_else(|| format_err!("error while parsing a float"))?,
-                );
+                float = Some(float::parse(content.as_bytes()).ok_or_else(|| {
+                    format_err!(ErrorKind::InvalidFloat, "error while parsing a float")
+                })?);
             }
             "param" => {
                 // warn!(
@@ -573,7 +575,7 @@ impl FromStr for Opaque {
             "A_ONE" => Self::A_ONE,
             "RGB_ZERO" => Self::RGB_ZERO,
             "RGB_ONE" => Self::RGB_ONE,
-            _ => bail!("unknown opaque type {:?}", s),
+            _ => bail!(ErrorKind::Syntax, "unknown opaque type {:?}", s),
         })
     }
 }
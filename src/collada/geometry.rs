@@ -90,7 +90,7 @@ impl PrimitiveType {
 pub(super) struct PrimitiveInputs<'a> {
     pub(super) vertex: SharedInput<'a, Vertices<'a>>,
     pub(super) normal: Option<SharedInput<'a>>,
-    pub(super) color: Option<SharedInput<'a>>,
+    pub(super) color: Vec<SharedInput<'a>>,
     pub(super) texcoord: Vec<SharedInput<'a>>,
 }
 
@@ -232,7 +232,7 @@ fn parse_mesh<'a>(cx: &mut Context<'a>, node: xml::Node<'a, '_>) -> io::Result<M
             }
             "lines" | "linestrips" | "polygons" | "polylist" | "triangles" | "trifans"
             | "tristrips" => {
-                primitives.push(parse_primitive(node, name.parse().unwrap())?);
+                primitives.push(parse_primitive(node, name.parse().unwrap(), cx.strict)?);
             }
             "extra" => { /* skip */ }
             _ => return Err(error::unexpected_child_elem(node)),
@@ -315,21 +315,25 @@ impl FromStr for PrimitiveType {
             "triangles" => Self::Triangles,
             "trifans" => Self::TriFans,
             "tristrips" => Self::TriStrips,
-            _ => bail!("unknown primitive type {:?}", s),
+            _ => bail!(ErrorKind::Syntax, "unknown primitive type {:?}", s),
         })
     }
 }
 
-fn parse_primitive<'a>(node: xml::Node<'a, '_>, ty: PrimitiveType) -> io::Result<Primitive<'a>> {
+fn parse_primitive<'a>(
+    node: xml::Node<'a, '_>,
+    ty: PrimitiveType,
+    strict: bool,
+) -> io::Result<Primitive<'a>> {
     debug_assert_eq!(node.tag_name().name().parse::<PrimitiveType>().unwrap(), ty);
-    let count: u32 = node.parse_required_attribute("count")?;
+    let mut count: u32 = node.parse_required_attribute("count")?;
     let mut vcount = vec![];
     let mut p = vec![];
     let mut stride = 0;
 
     let mut input_vertex = None;
     let mut input_normal = None;
-    let mut input_color = None;
+    let mut input_color = vec![];
     let mut input_texcoord = vec![];
 
     for node in node.element_children() {
@@ -350,7 +354,7 @@ fn parse_primitive<'a>(node: xml::Node<'a, '_>, ty: PrimitiveType) -> io::Result
                             input_normal = Some(i);
                         }
                     }
-                    InputSemantic::COLOR => input_color = Some(i),
+                    InputSemantic::COLOR => input_color.push(i),
                     InputSemantic::TEXCOORD => input_texcoord.push(i),
                     _semantic => {
                         // warn!(
@@ -381,6 +385,7 @@ fn parse_primitive<'a>(node: xml::Node<'a, '_>, ty: PrimitiveType) -> io::Result
                 for _ in 0..count {
                     let value = iter.next().ok_or_else(|| {
                         format_err!(
+                            ErrorKind::InvalidSize,
                             "expected more values while reading <{}> contents at {}",
                             node.tag_name().name(),
                             node.node_location()
@@ -390,6 +395,7 @@ fn parse_primitive<'a>(node: xml::Node<'a, '_>, ty: PrimitiveType) -> io::Result
                         vcount.push(value);
                     } else {
                         bail!(
+                            ErrorKind::InvalidSize,
                             "incorrect number of indices in <p> element ({})",
                             node.node_location()
                         );
@@ -435,17 +441,33 @@ fn parse_primitive<'a>(node: xml::Node<'a, '_>, ty: PrimitiveType) -> io::Result
                     // TODO: use parse_int_array_exact?
                     for value in xml::parse_int_array(node.trimmed_text()) {
                         p.push(value.map_err(|e| {
-                            format_err!(
-                                "{e} in <{}> element ({})",
-                                node.tag_name().name(),
-                                node.text_location(),
+                            crate::error::with_message(
+                                &e,
+                                format!(
+                                    "{e} in <{}> element ({})",
+                                    node.tag_name().name(),
+                                    node.text_location(),
+                                ),
                             )
                         })?);
                     }
 
                     if p.len() != expected_count * stride as usize {
-                        // TODO: It seems SketchUp 15.3.331 writes the wrong 'count' for 'lines'.
+                        // It seems SketchUp 15.3.331 writes the wrong 'count' for 'lines',
+                        // so accept any number of complete lines unless strict.
+                        let primitive_len = 2 * stride as usize;
+                        if !strict
+                            && ty == PrimitiveType::Lines
+                            && primitive_len != 0
+                            && p.len() % primitive_len == 0
+                        {
+                            if let Ok(n) = u32::try_from(p.len() / primitive_len) {
+                                count = n;
+                                continue;
+                            }
+                        }
                         bail!(
+                            ErrorKind::InvalidSize,
                             "incorrect index count in <p> element, expected {} but found {} ({})",
                             expected_count * stride as usize,
                             p.len(),
@@ -468,10 +490,13 @@ fn parse_primitive<'a>(node: xml::Node<'a, '_>, ty: PrimitiveType) -> io::Result
                     // TODO: use parse_int_array_exact?
                     for value in xml::parse_int_array(node.trimmed_text()) {
                         p.push(value.map_err(|e| {
-                            format_err!(
-                                "{e} in <{}> element ({})",
-                                node.tag_name().name(),
-                                node.text_location(),
+                            crate::error::with_message(
+                                &e,
+                                format!(
+                                    "{e} in <{}> element ({})",
+                                    node.tag_name().name(),
+                                    node.text_location(),
+                                ),
                             )
                         })?);
                     }
@@ -480,6 +505,7 @@ fn parse_primitive<'a>(node: xml::Node<'a, '_>, ty: PrimitiveType) -> io::Result
                     let added = (p.len() - prev_len) as u32;
                     if added % stride != 0 {
                         bail!(
+                            ErrorKind::InvalidSize,
                             "incorrect index count in <p> element, expected multiple of {}, but found {} ({})",
                             stride,
                             p.len(),
@@ -491,6 +517,7 @@ fn parse_primitive<'a>(node: xml::Node<'a, '_>, ty: PrimitiveType) -> io::Result
                         vcount.push(vc);
                     } else {
                         bail!(
+                            ErrorKind::InvalidSize,
                             "incorrect number of indices in <p> element ({})",
                             node.node_location()
                         );
@@ -512,9 +539,10 @@ fn parse_primitive<'a>(node: xml::Node<'a, '_>, ty: PrimitiveType) -> io::Result
 
     // When at least one input is present, one input must specify semantic="VERTEX".
     if input_vertex.is_none()
-        && (input_normal.is_some() || input_color.is_some() || !input_texcoord.is_empty())
+        && (input_normal.is_some() || !input_color.is_empty() || !input_texcoord.is_empty())
     {
         bail!(
+            ErrorKind::MissingElement,
             "one <input> in <{}> element must specify semantic=\"VERTEX\" ({})",
             node.tag_name().name(),
             node.node_location()
@@ -524,6 +552,9 @@ fn parse_primitive<'a>(node: xml::Node<'a, '_>, ty: PrimitiveType) -> io::Result
     if !input_texcoord.is_empty() {
         input_texcoord.sort_by_key(|i| i.set);
     }
+    if !input_color.is_empty() {
+        input_color.sort_by_key(|i| i.set);
+    }
 
     Ok(Primitive {
         ty,
@@ -26,8 +26,8 @@ pub(super) struct Image<'a> {
     pub(super) id: &'a str,
     // /// The name of this element.
     // pub(super) name: Option<&'a str>,
-    // /// The image format.
-    // pub(super) format: Option<&'a str>,
+    /// The image format.
+    pub(super) format: Option<&'a str>,
     // /// The height of the image in pixels.
     // pub(super) height: Option<u32>,
     // /// The width of the image in pixels.
@@ -80,8 +80,9 @@ fn parse_image<'a>(cx: &Context<'a>, node: xml::Node<'a, '_>) -> io::Result<Imag
     let id = node.required_attribute("id")?;
     // let name = node.attribute("name");
     let is_1_4 = cx.version.is_1_4();
+    let mut format = None;
     if is_1_4 {
-        // let mut format = node.attribute("format");
+        format = node.attribute("format");
         let _height: Option<u32> = node.parse_attribute("height")?;
         let _width: Option<u32> = node.parse_attribute("width")?;
         let _depth: u32 = node.parse_attribute("depth")?.unwrap_or(1);
@@ -104,7 +105,7 @@ fn parse_image<'a>(cx: &Context<'a>, node: xml::Node<'a, '_>) -> io::Result<Imag
                             source = Some(ImageSource::InitFrom(node.trimmed_text()));
                         }
                         "hex" => {
-                            // format = node.attribute("format");
+                            format = node.attribute("format");
                             let data = hex::decode(node.trimmed_text().as_bytes())?;
                             source = Some(ImageSource::Data(data));
                         }
@@ -127,6 +128,7 @@ fn parse_image<'a>(cx: &Context<'a>, node: xml::Node<'a, '_>) -> io::Result<Imag
         None => {
             if is_1_4 {
                 bail!(
+                    ErrorKind::MissingElement,
                     "<{}> element must be contain <data> or <init_from> element ({})",
                     node.tag_name().name(),
                     node.node_location()
@@ -140,7 +142,7 @@ fn parse_image<'a>(cx: &Context<'a>, node: xml::Node<'a, '_>) -> io::Result<Imag
     Ok(Image {
         id,
         // name,
-        // format,
+        format,
         // height,
         // width,
         // depth,
@@ -1,12 +1,20 @@
-use std::path::PathBuf;
-
 use super::*;
-use crate::ShadingModel;
+use crate::{utils::triangulate::triangulate, ShadingModel, Vec2, Vec3, Warning, WarningKind};
 
-pub(super) fn build(doc: &mut Document<'_>, dir: Option<&Path>) -> common::Scene {
+pub(super) fn build(
+    doc: &mut Document<'_>,
+    dir: Option<&Path>,
+    pre_transform: bool,
+    coordinate_system: Option<common::CoordinateSystem>,
+    keep_polygons: bool,
+    textures: &TextureResolver,
+) -> common::Scene {
     let mut meshes = Vec::with_capacity(doc.library_geometries.geometries.len());
     let mut materials = Vec::with_capacity(doc.library_geometries.geometries.len());
     let mut instance_geometry_map = HashMap::new();
+    let mut scene_coordinate_system = doc.asset.up_axis;
+    let mut warnings = vec![];
+    let mut embedded_textures = vec![];
 
     if let Some(root) = &doc.scene.instance_visual_scene {
         if let Some(root) = doc
@@ -33,63 +41,316 @@ pub(super) fn build(doc: &mut Document<'_>, dir: Option<&Path>) -> common::Scene
                 0.,
                 1.,
             );
+            // Like the unit, the coordinate system conversion is applied to the
+            // root node, so that it is also applied to non-pre-transformed meshes.
+            if let Some(to) = coordinate_system {
+                if to != doc.asset.up_axis {
+                    let r = doc.asset.up_axis.rotation_to(to);
+                    let mut transform = Matrix4x4::new(
+                        r[0][0], r[0][1], r[0][2], 0., r[1][0], r[1][1], r[1][2], 0., r[2][0],
+                        r[2][1], r[2][2], 0., 0., 0., 0., 1.,
+                    );
+                    transform *= root.transform;
+                    root.transform = transform;
+                }
+                scene_coordinate_system = to;
+            }
         }
     }
 
-    for node in &doc.library_visual_scenes.nodes {
+    let nodes = &doc.library_visual_scenes.nodes;
+    for node in nodes {
         for instance_geometry in &node.instance_geometry {
-            let mut transform = node.transform;
-            let mut parent = node.parent;
-            while let Some(p) = parent {
-                let node = &doc.library_visual_scenes.nodes[p];
-                transform *= node.transform;
-                parent = node.parent;
-            }
-            let transform = if transform.is_identity() {
-                None
+            let transform = if pre_transform {
+                Some(world_transform(nodes, node)).filter(|t| !t.is_identity())
             } else {
-                Some(transform)
+                None
             };
             instance_geometry_map.insert(
                 instance_geometry.url.as_str(),
-                (instance_geometry, transform),
+                (&instance_geometry.materials, transform, None),
+            );
+        }
+        for instance_controller in &node.instance_controller {
+            let skin = match doc.get(&instance_controller.url) {
+                Some(controller) => &controller.skin,
+                None => continue,
+            };
+            // Skinned meshes are placed by their skeleton instead of the node
+            // that instantiates them, so only the transform of the root node
+            // (the visual scene) is applied in addition to the bind shape matrix.
+            let mut transform = skin.bind_shape_matrix;
+            let mut root_transform = None;
+            if pre_transform {
+                let mut root = node;
+                while let Some(p) = root.parent {
+                    root = &nodes[p];
+                }
+                let mut t = root.transform;
+                t *= transform;
+                transform = t;
+                root_transform = Some(root.transform);
+            }
+            instance_geometry_map.insert(
+                skin.source.as_str(),
+                (
+                    &instance_controller.materials,
+                    Some(transform).filter(|t| !t.is_identity()),
+                    Some((skin, root_transform)),
+                ),
             );
         }
     }
+    let mut mesh_indices = HashMap::new();
     for geometry in doc.library_geometries.geometries.values() {
-        if let Some(&(instance_geometry, transform)) = instance_geometry_map.get(geometry.id) {
-            meshes.push(build_mesh(doc, geometry, transform));
-            materials.push(build_material(doc, instance_geometry, dir));
+        mesh_indices.insert(geometry.id, meshes.len());
+        if let Some(&(instance_materials, transform, skin)) = instance_geometry_map.get(geometry.id)
+        {
+            meshes.push(build_mesh(doc, geometry, transform, skin, keep_polygons));
+            materials.push(build_material(
+                doc,
+                instance_materials,
+                dir,
+                textures,
+                &mut embedded_textures,
+                &mut warnings,
+            ));
         } else {
-            meshes.push(build_mesh(doc, geometry, None));
+            meshes.push(build_mesh(doc, geometry, None, None, keep_polygons));
             materials.push(common::Material::default());
         }
     }
 
-    common::Scene { materials, meshes }
+    let nodes = doc
+        .library_visual_scenes
+        .nodes
+        .iter()
+        .map(|node| {
+            let mut n = common::Node {
+                name: node.name.or(node.id).unwrap_or_default().to_owned(),
+                parent: node.parent,
+                transform: node.transform.to_cols_array_2d(),
+                transforms: node
+                    .transforms
+                    .iter()
+                    .map(|&(sid, ref t)| build_node_transform(sid, t))
+                    .collect(),
+                ..Default::default()
+            };
+            // The unit and coordinate system conversion of the root node
+            // are not part of the transform elements in the file.
+            if n.transforms.is_empty() && !node.transform.is_identity() {
+                n.transforms.push(common::NodeTransform {
+                    sid: None,
+                    kind: common::NodeTransformKind::Matrix,
+                    values: n.transform.iter().flatten().copied().collect(),
+                });
+            }
+            n.meshes = node
+                .instance_geometry
+                .iter()
+                .map(|i| i.url.as_str())
+                .chain(
+                    node.instance_controller
+                        .iter()
+                        .filter_map(|i| doc.get(&i.url))
+                        .map(|c| c.skin.source.as_str()),
+                )
+                .filter_map(|id| mesh_indices.get(id).copied())
+                .collect();
+            n
+        })
+        .collect();
+
+    common::Scene {
+        materials,
+        meshes,
+        nodes,
+        coordinate_system: Some(scene_coordinate_system),
+        animations: build_animations(doc),
+        embedded_textures,
+        warnings,
+    }
+}
+
+fn build_node_transform(sid: Option<&str>, transform: &Transform) -> common::NodeTransform {
+    let (kind, values) = match transform {
+        Transform::Lookat(f) => (common::NodeTransformKind::LookAt, f.to_vec()),
+        // Convert row-major matrices to column-major, like animation values.
+        Transform::Matrix(f) => (
+            common::NodeTransformKind::Matrix,
+            (0..16).map(|i| f[(i % 4) * 4 + i / 4]).collect(),
+        ),
+        Transform::Rotate(f) => (common::NodeTransformKind::Rotate, f.to_vec()),
+        Transform::Scale(f) => (common::NodeTransformKind::Scale, f.to_vec()),
+        Transform::Skew(f) => (common::NodeTransformKind::Skew, f.to_vec()),
+        Transform::Translate(f) => (common::NodeTransformKind::Translate, f.to_vec()),
+    };
+    common::NodeTransform {
+        sid: sid.map(str::to_owned),
+        kind,
+        values,
+    }
+}
+
+fn build_animations(doc: &Document<'_>) -> Vec<common::Animation> {
+    let nodes = &doc.library_visual_scenes.nodes;
+    let mut animations = vec![];
+    for animation in &doc.library_animations.animations {
+        let mut channels = vec![];
+        for channel in &animation.channels {
+            let sampler = match animation.samplers.get(channel.source) {
+                Some(sampler) => sampler,
+                None => continue,
+            };
+            // The first segment of the target is the ID of the node, and the
+            // rest is the SID path of the transform element of the node.
+            let (id, target) = match channel.target.split_once('/') {
+                Some(target) => target,
+                None => continue,
+            };
+            let node = match nodes.iter().position(|n| n.id == Some(id)) {
+                Some(node) => node,
+                None => continue,
+            };
+            let (times, _) = accessor_floats(doc, &sampler.input);
+            let (values, stride) = accessor_floats(doc, &sampler.output);
+            let mut values = values.to_vec();
+            if stride == 16 {
+                // Convert row-major matrices to column-major.
+                for m in values.chunks_exact_mut(16) {
+                    let mut t = [0.; 16];
+                    for (i, x) in t.iter_mut().enumerate() {
+                        *x = m[(i % 4) * 4 + i / 4];
+                    }
+                    m.copy_from_slice(&t);
+                }
+            }
+            let interpolations = sampler
+                .interpolation
+                .as_ref()
+                .and_then(|i| doc.get(&i.source))
+                .and_then(|acc| doc.get(&acc.source))
+                .and_then(ArrayData::as_string)
+                .unwrap_or_default();
+            let interpolations = (0..times.len())
+                .map(|i| match interpolations.get(i) {
+                    Some(&"STEP") => common::Interpolation::Step,
+                    Some(&"BEZIER") => common::Interpolation::Bezier,
+                    // HERMITE, BSPLINE, and CARDINAL are not supported yet,
+                    // so fall back to linear interpolation.
+                    _ => common::Interpolation::Linear,
+                })
+                .collect();
+            let tangents = |input: &Option<UnsharedInput<'_>>| match input {
+                Some(input) => accessor_floats(doc, input).0.to_vec(),
+                None => vec![],
+            };
+            channels.push(common::AnimationChannel {
+                node,
+                target: target.to_owned(),
+                times: times.to_vec(),
+                values,
+                stride,
+                interpolations,
+                in_tangents: tangents(&sampler.in_tangent),
+                out_tangents: tangents(&sampler.out_tangent),
+            });
+        }
+        if channels.is_empty() {
+            continue;
+        }
+        animations.push(common::Animation {
+            name: animation
+                .name
+                .or(animation.id)
+                .unwrap_or_default()
+                .to_owned(),
+            channels,
+        });
+    }
+    animations
+}
+
+/// Returns the float values referenced by the input and the number of values
+/// per element.
+fn accessor_floats<'a>(doc: &'a Document<'a>, input: &UnsharedInput<'a>) -> (&'a [f32], usize) {
+    let acc = match doc.get(&input.source) {
+        Some(acc) => acc,
+        None => return (&[], 1),
+    };
+    let stride = cmp::max(acc.stride as usize, 1);
+    let data = doc
+        .get(&acc.source)
+        .and_then(ArrayData::as_float)
+        .unwrap_or_default();
+    let len = cmp::min(data.len(), acc.count as usize * stride);
+    (&data[..len], stride)
+}
+
+/// Returns the transform of the node in world space.
+fn world_transform(nodes: &[Node<'_>], node: &Node<'_>) -> Matrix4x4 {
+    let mut transform = node.transform;
+    let mut parent = node.parent;
+    while let Some(p) = parent {
+        let node = &nodes[p];
+        // The parent transform is applied after the child transform.
+        let mut parent_transform = node.transform;
+        parent_transform *= transform;
+        transform = parent_transform;
+        parent = node.parent;
+    }
+    transform
 }
 
 fn build_mesh(
     doc: &Document<'_>,
     geometry: &Geometry<'_>,
     transform: Option<Matrix4x4>,
+    skin: Option<(&Skin<'_>, Option<Matrix4x4>)>,
+    keep_polygons: bool,
 ) -> common::Mesh {
+    let normal_transform = transform.map(Matrix4x4::normal_matrix);
     let mut mesh = common::Mesh {
         name: geometry.id.to_owned(),
         ..Default::default()
     };
+    // The index of the position in the base mesh for each vertex, used to
+    // look up the vertex weights of the skin.
+    let mut position_indices = vec![];
 
     for prim in (iter::Mesh { doc, xml: geometry }).primitives() {
+        if matches!(
+            prim.xml.ty,
+            PrimitiveType::Polylist | PrimitiveType::Polygons
+        ) {
+            if let Some(prim) = PrimitiveVertices::new(&prim) {
+                push_polygons(
+                    &mut mesh,
+                    &prim,
+                    keep_polygons,
+                    transform,
+                    normal_transform,
+                    skin.map(|_| &mut position_indices),
+                );
+            }
+            continue;
+        }
         #[allow(clippy::cast_possible_truncation)]
         let prev_positions_len = mesh.vertices.len() as u32;
         let p: Vec<_> = prim.positions().collect();
         let n: Vec<_> = prim.normals().collect();
-        let t: Vec<_> = prim.texcoords(0).collect();
-        let c: Vec<_> = prim.colors().collect();
+        let t: Vec<Vec<_>> = (0..prim.num_texcoord_sets())
+            .map(|set| prim.texcoords(set).collect())
+            .collect();
+        let c: Vec<Vec<_>> = (0..prim.num_color_sets())
+            .map(|set| prim.colors(set).collect())
+            .collect();
         let positions_indices = prim.vertex_indices();
         let mut normal_indices = prim.normal_indices();
-        let mut texcoord_indices = prim.texcoord_indices(0);
-        let mut color_indices = prim.color_indices();
+        let mut texcoord_indices: Vec<_> =
+            (0..t.len()).map(|set| prim.texcoord_indices(set)).collect();
+        let mut color_indices: Vec<_> = (0..c.len()).map(|set| prim.color_indices(set)).collect();
         let mut idx = 0;
 
         for vertex_idx in positions_indices {
@@ -103,34 +364,48 @@ fn build_mesh(
                     v *= transform;
                 }
                 mesh.vertices.push(v);
+                if skin.is_some() {
+                    position_indices.push(vertex_idx);
+                }
             }
             if !n.is_empty() {
                 if let Some(normal_idx) = normal_indices.next() {
                     for normal_idx in normal_idx {
-                        mesh.normals.push([
+                        let mut v = [
                             n[normal_idx as usize][0],
                             n[normal_idx as usize][1],
                             n[normal_idx as usize][2],
-                        ]);
+                        ];
+                        if let Some(normal_transform) = normal_transform {
+                            v *= normal_transform;
+                            v = normalize(v);
+                        }
+                        mesh.normals.push(v);
                     }
                 } else {
                     panic!()
                 }
             }
-            if !t.is_empty() {
-                if let Some(texcoord_idx) = texcoord_indices.next() {
+            for (set, t) in t.iter().enumerate() {
+                if t.is_empty() {
+                    continue;
+                }
+                if let Some(texcoord_idx) = texcoord_indices[set].next() {
                     for texcoord_idx in texcoord_idx {
-                        mesh.texcoords[0]
+                        mesh.texcoords_mut(set)
                             .push([t[texcoord_idx as usize][0], t[texcoord_idx as usize][1]]);
                     }
                 } else {
                     panic!()
                 }
             }
-            if !c.is_empty() {
-                if let Some(rgb_idx) = color_indices.next() {
+            for (set, c) in c.iter().enumerate() {
+                if c.is_empty() {
+                    continue;
+                }
+                if let Some(rgb_idx) = color_indices[set].next() {
                     for rgb_idx in rgb_idx {
-                        mesh.colors[0].push([
+                        mesh.colors_mut(set).push([
                             c[rgb_idx as usize][0],
                             c[rgb_idx as usize][1],
                             c[rgb_idx as usize][2],
@@ -149,23 +424,386 @@ fn build_mesh(
             idx += 3;
         }
     }
+    push_lines(
+        &mut mesh,
+        (iter::Mesh { doc, xml: geometry }).primitives(),
+        transform,
+        normal_transform,
+        skin.map(|_| &mut position_indices),
+    );
+
+    if let Some((skin, root_transform)) = skin {
+        mesh.bones = build_bones(doc, skin, root_transform, &position_indices);
+    }
 
     mesh
 }
 
+/// The vertex data of a primitive, indexed by the position of each vertex in
+/// `<p>`.
+///
+/// Each attribute is a pair of the data and the offset of its input in `<p>`.
+struct PrimitiveVertices<'a> {
+    xml: &'a Primitive<'a>,
+    positions: Vec<Vec3>,
+    vertex_offset: usize,
+    normals: Option<(Vec<Vec3>, usize)>,
+    texcoords: Vec<Option<(Vec<Vec2>, usize)>>,
+    colors: Vec<Option<(Vec<Vec3>, usize)>>,
+}
+
+impl<'a> PrimitiveVertices<'a> {
+    fn new(prim: &iter::Primitive<'a>) -> Option<Self> {
+        fn attribute<T>(data: Vec<T>, offset: Option<u32>) -> Option<(Vec<T>, usize)> {
+            match offset {
+                Some(offset) if !data.is_empty() => Some((data, offset as usize)),
+                _ => None,
+            }
+        }
+        Some(Self {
+            xml: prim.xml,
+            positions: prim.positions().collect(),
+            vertex_offset: prim.vertex_offset()? as usize,
+            normals: attribute(prim.normals().collect(), prim.normal_offset()),
+            texcoords: (0..prim.num_texcoord_sets())
+                .map(|set| attribute(prim.texcoords(set).collect(), prim.texcoord_offset(set)))
+                .collect(),
+            colors: (0..prim.num_color_sets())
+                .map(|set| attribute(prim.colors(set).collect(), prim.color_offset(set)))
+                .collect(),
+        })
+    }
+
+    fn input(&self, i: usize, offset: usize) -> usize {
+        self.xml.p[i * self.xml.stride as usize + offset] as usize
+    }
+
+    fn attributes(&self) -> Attributes {
+        Attributes {
+            normals: self.normals.is_some(),
+            texcoords: self.texcoords.iter().map(Option::is_some).collect(),
+            colors: self.colors.iter().map(Option::is_some).collect(),
+        }
+    }
+}
+
+/// Which vertex attributes to push.
+///
+/// `texcoords` and `colors` have an element for each set.
+struct Attributes {
+    normals: bool,
+    texcoords: Vec<bool>,
+    colors: Vec<bool>,
+}
+
+impl Attributes {
+    fn of_mesh(mesh: &common::Mesh) -> Self {
+        Self {
+            normals: !mesh.normals.is_empty(),
+            texcoords: mesh.texcoords.iter().map(|t| !t.is_empty()).collect(),
+            colors: mesh.colors.iter().map(|c| !c.is_empty()).collect(),
+        }
+    }
+
+    /// Keeps only the attributes that both have.
+    fn intersect(&mut self, other: &Self) {
+        self.normals &= other.normals;
+        self.texcoords.truncate(other.texcoords.len());
+        for (a, b) in self.texcoords.iter_mut().zip(&other.texcoords) {
+            *a &= b;
+        }
+        self.colors.truncate(other.colors.len());
+        for (a, b) in self.colors.iter_mut().zip(&other.colors) {
+            *a &= b;
+        }
+    }
+}
+
+/// Pushes the `i`-th vertex in `<p>` of the primitive.
+///
+/// Attributes that the primitive does not have are filled with zeros.
+fn push_vertex(
+    mesh: &mut common::Mesh,
+    prim: &PrimitiveVertices<'_>,
+    i: usize,
+    attributes: &Attributes,
+    transform: Option<Matrix4x4>,
+    normal_transform: Option<Matrix4x4>,
+    position_indices: &mut Option<&mut Vec<u32>>,
+) {
+    let vertex_idx = prim.input(i, prim.vertex_offset);
+    let mut v = prim.positions[vertex_idx];
+    if let Some(transform) = transform {
+        v *= transform;
+    }
+    mesh.vertices.push(v);
+    if let Some(position_indices) = position_indices {
+        #[allow(clippy::cast_possible_truncation)]
+        position_indices.push(vertex_idx as u32);
+    }
+    if attributes.normals {
+        let mut v = prim
+            .normals
+            .as_ref()
+            .map_or([0.; 3], |(n, offset)| n[prim.input(i, *offset)]);
+        if let Some(normal_transform) = normal_transform {
+            v *= normal_transform;
+            v = normalize(v);
+        }
+        mesh.normals.push(v);
+    }
+    for (set, _) in attributes.texcoords.iter().enumerate().filter(|(_, &a)| a) {
+        let v = prim
+            .texcoords
+            .get(set)
+            .and_then(Option::as_ref)
+            .map_or([0.; 2], |(t, offset)| t[prim.input(i, *offset)]);
+        mesh.texcoords_mut(set).push(v);
+    }
+    for (set, _) in attributes.colors.iter().enumerate().filter(|(_, &a)| a) {
+        let rgb = prim
+            .colors
+            .get(set)
+            .and_then(Option::as_ref)
+            .map_or([0.; 3], |(c, offset)| c[prim.input(i, *offset)]);
+        mesh.colors_mut(set).push([rgb[0], rgb[1], rgb[2], 1.]);
+    }
+}
+
+/// Pushes the polygons of `<polylist>` and `<polygons>`.
+///
+/// Polygons with more than three vertices are kept as polygons if
+/// `keep_polygons` is `true`, and are triangulated otherwise.
+fn push_polygons(
+    mesh: &mut common::Mesh,
+    prim: &PrimitiveVertices<'_>,
+    keep_polygons: bool,
+    transform: Option<Matrix4x4>,
+    normal_transform: Option<Matrix4x4>,
+    mut position_indices: Option<&mut Vec<u32>>,
+) {
+    let attributes = prim.attributes();
+    let mut push = |mesh: &mut common::Mesh, i| {
+        #[allow(clippy::cast_possible_truncation)]
+        let index = mesh.vertices.len() as u32;
+        push_vertex(
+            mesh,
+            prim,
+            i,
+            &attributes,
+            transform,
+            normal_transform,
+            &mut position_indices,
+        );
+        index
+    };
+    let mut positions = vec![];
+    let mut triangles = vec![];
+    let mut start = 0;
+    for &vc in &prim.xml.vcount {
+        let polygon = start..start + vc as usize;
+        start = polygon.end;
+        if vc < 3 {
+            continue;
+        }
+        if keep_polygons && vc > 3 {
+            #[allow(clippy::cast_possible_truncation)]
+            let offset = mesh.polygon_indices.len() as u32;
+            mesh.polygon_offsets.push(offset);
+            for i in polygon {
+                let index = push(mesh, i);
+                mesh.polygon_indices.push(index);
+            }
+            continue;
+        }
+        positions.clear();
+        positions.extend(
+            polygon
+                .clone()
+                .map(|i| prim.positions[prim.input(i, prim.vertex_offset)]),
+        );
+        triangles.clear();
+        triangulate(&positions, &mut triangles);
+        for t in &triangles {
+            let face = t.map(|k| push(mesh, polygon.start + k));
+            mesh.faces.push(face);
+        }
+    }
+}
+
+/// Pushes the line segments of `<lines>` and `<linestrips>` after triangles,
+/// so that their vertices can have the same attributes as the vertices of
+/// triangles.
+fn push_lines(
+    mesh: &mut common::Mesh,
+    prims: iter::Primitives<'_>,
+    transform: Option<Matrix4x4>,
+    normal_transform: Option<Matrix4x4>,
+    mut position_indices: Option<&mut Vec<u32>>,
+) {
+    let prims: Vec<_> = prims
+        .filter(|prim| {
+            matches!(
+                prim.xml.ty,
+                PrimitiveType::Lines | PrimitiveType::LineStrips
+            )
+        })
+        .filter_map(|prim| Some((PrimitiveVertices::new(&prim)?, prim.lines())))
+        .collect();
+    if prims.is_empty() {
+        return;
+    }
+    // Without triangles, use attributes only if all lines have them.
+    let attributes = if mesh.vertices.is_empty() {
+        let mut attributes = prims[0].0.attributes();
+        for (prim, _) in &prims[1..] {
+            attributes.intersect(&prim.attributes());
+        }
+        attributes
+    } else {
+        Attributes::of_mesh(mesh)
+    };
+
+    for (prim, lines) in &prims {
+        for line in lines {
+            #[allow(clippy::cast_possible_truncation)]
+            let first = mesh.vertices.len() as u32;
+            for &i in line {
+                push_vertex(
+                    mesh,
+                    prim,
+                    i,
+                    &attributes,
+                    transform,
+                    normal_transform,
+                    &mut position_indices,
+                );
+            }
+            mesh.lines.push([first, first + 1]);
+        }
+    }
+}
+
+fn build_bones(
+    doc: &Document<'_>,
+    skin: &Skin<'_>,
+    root_transform: Option<Matrix4x4>,
+    position_indices: &[u32],
+) -> Vec<common::Bone> {
+    let joints = match doc.get(&skin.joints.joint.source) {
+        Some(acc) => match doc.get(&acc.source).and_then(ArrayData::as_string) {
+            Some(joints) => joints,
+            None => return vec![],
+        },
+        None => return vec![],
+    };
+    let (inv_bind_matrices, inv_bind_matrix_stride) =
+        match doc.get(&skin.joints.inv_bind_matrix.source) {
+            Some(acc) => (
+                doc.get(&acc.source)
+                    .and_then(ArrayData::as_float)
+                    .unwrap_or_default(),
+                cmp::max(acc.stride as usize, 16),
+            ),
+            None => (&[][..], 16),
+        };
+    let weights = doc
+        .get(&skin.vertex_weights.weight.source)
+        .and_then(|acc| doc.get(&acc.source))
+        .and_then(ArrayData::as_float)
+        .unwrap_or_default();
+    // The root transform has been applied to the vertices, so the offset
+    // matrices must undo it.
+    let root_inverse = root_transform.and_then(Matrix4x4::inverse_affine);
+
+    let nodes = &doc.library_visual_scenes.nodes;
+    let mut bones: Vec<_> = joints
+        .iter()
+        .enumerate()
+        .map(|(i, &name)| {
+            let start = i * inv_bind_matrix_stride;
+            let mut offset_matrix = match inv_bind_matrices.get(start..start + 16) {
+                Some(m) => Matrix4x4::new(
+                    m[0], m[1], m[2], m[3], m[4], m[5], m[6], m[7], m[8], m[9], m[10], m[11],
+                    m[12], m[13], m[14], m[15],
+                ),
+                None => Matrix4x4::default(),
+            };
+            if let Some(root_inverse) = root_inverse {
+                offset_matrix *= root_inverse;
+            }
+            // <Name_array> refers to joints by sid, and <IDREF_array> refers to joints by id.
+            let node = nodes
+                .iter()
+                .position(|n| n.sid == Some(name))
+                .or_else(|| nodes.iter().position(|n| n.id == Some(name)))
+                .or_else(|| nodes.iter().position(|n| n.name == Some(name)));
+            common::Bone {
+                name: name.to_owned(),
+                node,
+                offset_matrix: offset_matrix.to_cols_array_2d(),
+                weights: vec![],
+            }
+        })
+        .collect();
+
+    let vertex_weights = &skin.vertex_weights;
+    let stride = vertex_weights.stride as usize;
+    let joint_offset = vertex_weights.joint.offset as usize;
+    let weight_offset = vertex_weights.weight.offset as usize;
+    // The range of influences in <v> for each position in the base mesh.
+    let mut starts = Vec::with_capacity(vertex_weights.vcount.len() + 1);
+    let mut start = 0;
+    starts.push(start);
+    for &n in &vertex_weights.vcount {
+        start += n as usize * stride;
+        starts.push(start);
+    }
+    for (vertex, &position) in position_indices.iter().enumerate() {
+        let position = position as usize;
+        let range = match (starts.get(position), starts.get(position + 1)) {
+            (Some(&start), Some(&end)) => start..end,
+            _ => continue,
+        };
+        for influence in vertex_weights.v[range].chunks_exact(stride) {
+            // The joint index -1 refers to the bind shape, not to a bone.
+            let joint = usize::try_from(influence[joint_offset]).ok();
+            let weight = usize::try_from(influence[weight_offset]).ok();
+            if let (Some(bone), Some(&weight)) = (
+                joint.and_then(|j| bones.get_mut(j)),
+                weight.and_then(|w| weights.get(w)),
+            ) {
+                #[allow(clippy::cast_possible_truncation)]
+                bone.weights.push(common::VertexWeight {
+                    vertex: vertex as u32,
+                    weight,
+                });
+            }
+        }
+    }
+
+    bones
+}
+
 fn build_material(
     doc: &Document<'_>,
-    instance_geometry: &InstanceGeometry<'_>,
+    instance_materials: &BTreeMap<&str, SemanticMappingTable<'_>>,
     dir: Option<&Path>,
+    textures: &TextureResolver,
+    embedded_textures: &mut Vec<common::EmbeddedTexture>,
+    warnings: &mut Vec<Warning>,
 ) -> common::Material {
     let mut mat = common::Material::default();
-    for instance_material in instance_geometry.materials.values() {
+    for instance_material in instance_materials.values() {
         fn texture(
             doc: &Document<'_>,
             effect: &Effect<'_>,
             texture: &Texture<'_>,
             dir: Option<&Path>,
-        ) -> Option<PathBuf> {
+            textures: &TextureResolver,
+            embedded_textures: &mut Vec<common::EmbeddedTexture>,
+            warnings: &mut Vec<Warning>,
+        ) -> Option<common::TextureSource> {
             if texture.texture.is_empty() {
                 return None;
             }
@@ -184,31 +822,24 @@ fn build_material(
             }
             if let Some(image) = image {
                 match &image.source {
-                    ImageSource::Data(_data) => {} // TODO
-                    ImageSource::InitFrom(mut p) => {
-                        // There is an exporter writes empty <init_from/> tag
-                        if p.is_empty() {
-                            return None;
-                        }
-                        match dir {
-                            Some(dir) => {
-                                // TODO
-                                p = p.strip_prefix("file://").unwrap_or(p);
-                                let tmp: String;
-                                if p.contains('\\') {
-                                    tmp = p.replace('\\', "/");
-                                    p = &*tmp;
-                                }
-                                if p.starts_with("/..") {
-                                    p = p.strip_prefix('/').unwrap_or(p);
-                                }
-                                let p = dir.join(p);
-                                if p.exists() {
-                                    return Some(p);
-                                }
+                    ImageSource::Data(data) => {
+                        // Images shared by multiple materials are embedded only once.
+                        let index = match embedded_textures.iter().position(|t| t.name == image.id)
+                        {
+                            Some(index) => index,
+                            None => {
+                                embedded_textures.push(common::EmbeddedTexture {
+                                    name: image.id.to_owned(),
+                                    data: data.clone(),
+                                    format_hint: image.format.map(str::to_owned),
+                                });
+                                embedded_textures.len() - 1
                             }
-                            None => return Some(p.into()),
-                        }
+                        };
+                        return Some(common::TextureSource::Embedded(index));
+                    }
+                    ImageSource::InitFrom(p) => {
+                        return textures.resolve(p, dir, None, warnings).map(Into::into);
                     }
                     ImageSource::Skip => {}
                 }
@@ -220,18 +851,26 @@ fn build_material(
             Some(material) => match doc.get(&material.instance_effect.url) {
                 Some(effect) => effect,
                 None => {
-                    // debug!(
-                    //     "not found effect instance '{}'",
-                    //     material.instance_effect.url.as_str()
-                    // );
+                    warnings.push(Warning::new(
+                        WarningKind::MissingMaterial,
+                        format!(
+                            "effect {:?} is not found",
+                            material.instance_effect.url.as_str()
+                        ),
+                        None,
+                    ));
                     continue;
                 }
             },
             None => {
-                // debug!(
-                //     "not found material instance '{}'",
-                //     instance_material.target.as_str()
-                // );
+                warnings.push(Warning::new(
+                    WarningKind::MissingMaterial,
+                    format!(
+                        "material {:?} is not found",
+                        instance_material.target.as_str()
+                    ),
+                    None,
+                ));
                 continue;
             }
         };
@@ -278,36 +917,30 @@ fn build_material(
             }
         }
 
-        if let Some(p) = texture(doc, effect, &effect.profile.technique.ambient.texture, dir) {
+        let mut resolve = |t: &Texture<'_>| {
+            texture(doc, effect, t, dir, textures, embedded_textures, warnings)
+                .map(common::Texture::new)
+        };
+        if let Some(p) = resolve(&effect.profile.technique.ambient.texture) {
             // Refs: https://github.com/assimp/assimp/blob/v5.3.1/code/AssetLib/Collada/ColladaLoader.cpp#L1619
             mat.texture.lightmap = Some(p);
         }
-        if let Some(p) = texture(doc, effect, &effect.profile.technique.emission.texture, dir) {
+        if let Some(p) = resolve(&effect.profile.technique.emission.texture) {
             mat.texture.emissive = Some(p);
         }
-        if let Some(p) = texture(doc, effect, &effect.profile.technique.specular.texture, dir) {
+        if let Some(p) = resolve(&effect.profile.technique.specular.texture) {
             mat.texture.specular = Some(p);
         }
-        if let Some(p) = texture(doc, effect, &effect.profile.technique.diffuse.texture, dir) {
+        if let Some(p) = resolve(&effect.profile.technique.diffuse.texture) {
             mat.texture.diffuse = Some(p);
         }
-        if let Some(p) = texture(doc, effect, &effect.profile.technique.bump, dir) {
+        if let Some(p) = resolve(&effect.profile.technique.bump) {
             mat.texture.normal = Some(p);
         }
-        if let Some(p) = texture(
-            doc,
-            effect,
-            &effect.profile.technique.transparent.texture,
-            dir,
-        ) {
+        if let Some(p) = resolve(&effect.profile.technique.transparent.texture) {
             mat.texture.opacity = Some(p);
         }
-        if let Some(p) = texture(
-            doc,
-            effect,
-            &effect.profile.technique.reflective.texture,
-            dir,
-        ) {
+        if let Some(p) = resolve(&effect.profile.technique.reflective.texture) {
             mat.texture.reflection = Some(p);
         }
     }
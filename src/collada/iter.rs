@@ -1,4 +1,5 @@
 use std::{
+    cmp,
     iter::{self, FusedIterator},
     ops::Range,
     slice,
@@ -102,6 +103,24 @@ impl<'a> Primitive<'a> {
         Positions(Some((acc.count, data.chunks(acc.stride as usize))))
     }
 
+    /// Returns the number of texture coordinate sets that can be read by
+    /// [`texcoords`](Self::texcoords).
+    pub(super) fn num_texcoord_sets(&self) -> usize {
+        self.xml
+            .input
+            .as_ref()
+            .map_or(0, |input| cmp::max(input.texcoord.len(), 1))
+    }
+
+    /// Returns the number of color sets that can be read by
+    /// [`colors`](Self::colors).
+    pub(super) fn num_color_sets(&self) -> usize {
+        self.xml
+            .input
+            .as_ref()
+            .map_or(0, |input| cmp::max(input.color.len(), 1))
+    }
+
     pub(super) fn texcoords(&self, set: usize) -> Texcoords<'a> {
         let acc = match &self.xml.input {
             Some(input) => {
@@ -135,11 +154,12 @@ impl<'a> Primitive<'a> {
         }))
     }
 
-    pub(super) fn colors(&self) -> Colors<'a> {
+    pub(super) fn colors(&self, set: usize) -> Colors<'a> {
         let acc = match &self.xml.input {
-            Some(input) => match &input.color {
-                Some(color) => &self.mesh.doc[&color.source],
-                None => {
+            Some(input) => {
+                if let Some(color) = input.color.get(set) {
+                    &self.mesh.doc[&color.source]
+                } else if set == 0 {
                     if self.mesh.xml.mesh.vertices.id == input.vertex.source {
                         match &self.mesh.xml.mesh.vertices.input.color {
                             Some(color) => &self.mesh.doc[&color.source],
@@ -149,8 +169,10 @@ impl<'a> Primitive<'a> {
                         // TODO: search other mesh's vertices
                         todo!()
                     }
+                } else {
+                    return Positions(None);
                 }
-            },
+            }
             None => return Positions(None),
         };
         let data = self.mesh.doc[&acc.source].as_float().unwrap();
@@ -209,88 +231,115 @@ impl<'a> Primitive<'a> {
     }
 
     pub(super) fn vertex_indices(&self) -> VertexIndices<'a> {
-        let offset = match &self.xml.input {
-            Some(input) => input.vertex.offset,
-            None => return VertexIndices::none(),
-        };
-        VertexIndices {
-            remaining: self.vertex_indices_size(),
-            inner: self.vertex_indices_inner(offset),
-        }
+        self.indices(self.vertex_offset())
     }
 
     pub(super) fn normal_indices(&self) -> VertexIndices<'a> {
-        let offset = match &self.xml.input {
-            Some(input) => match &input.normal {
-                Some(normal) => normal.offset,
-                None => {
-                    if self.mesh.xml.mesh.vertices.id == input.vertex.source {
-                        if self.mesh.xml.mesh.vertices.input.normal.is_some() {
-                            input.vertex.offset
-                        } else {
-                            return VertexIndices::none();
-                        }
+        self.indices(self.normal_offset())
+    }
+
+    pub(super) fn texcoord_indices(&self, set: usize) -> VertexIndices<'a> {
+        self.indices(self.texcoord_offset(set))
+    }
+
+    pub(super) fn color_indices(&self, set: usize) -> VertexIndices<'a> {
+        self.indices(self.color_offset(set))
+    }
+
+    fn indices(&self, offset: Option<u32>) -> VertexIndices<'a> {
+        match offset {
+            Some(offset) => VertexIndices {
+                remaining: self.vertex_indices_size(),
+                inner: self.vertex_indices_inner(offset),
+            },
+            None => VertexIndices::none(),
+        }
+    }
+
+    /// Returns the line segments of `<lines>` and `<linestrips>` as pairs of
+    /// the indices of the vertices in `<p>`.
+    ///
+    /// The index of the `k`-th input of the vertex `i` in `<p>` is
+    /// `i * stride + k`.
+    pub(super) fn lines(&self) -> Vec<[usize; 2]> {
+        match self.xml.ty {
+            ast::PrimitiveType::Lines => (0..self.xml.count as usize)
+                .map(|i| [i * 2, i * 2 + 1])
+                .collect(),
+            ast::PrimitiveType::LineStrips => {
+                let mut lines = vec![];
+                let mut start = 0;
+                for &vc in &self.xml.vcount {
+                    let vc = vc as usize;
+                    lines.extend((start..start + vc - 1).map(|i| [i, i + 1]));
+                    start += vc;
+                }
+                lines
+            }
+            _ => vec![],
+        }
+    }
+
+    pub(super) fn vertex_offset(&self) -> Option<u32> {
+        self.xml.input.as_ref().map(|input| input.vertex.offset)
+    }
+
+    pub(super) fn normal_offset(&self) -> Option<u32> {
+        let input = self.xml.input.as_ref()?;
+        match &input.normal {
+            Some(normal) => Some(normal.offset),
+            None => {
+                if self.mesh.xml.mesh.vertices.id == input.vertex.source {
+                    if self.mesh.xml.mesh.vertices.input.normal.is_some() {
+                        Some(input.vertex.offset)
                     } else {
-                        // TODO: search other mesh's vertices
-                        todo!()
+                        None
                     }
+                } else {
+                    // TODO: search other mesh's vertices
+                    todo!()
                 }
-            },
-            None => return VertexIndices::none(),
-        };
-        VertexIndices {
-            remaining: self.vertex_indices_size(),
-            inner: self.vertex_indices_inner(offset),
+            }
         }
     }
 
-    pub(super) fn texcoord_indices(&self, set: usize) -> VertexIndices<'a> {
-        let offset = match &self.xml.input {
-            Some(input) => match input.texcoord.get(set) {
-                Some(texcoord) => texcoord.offset,
-                None => {
-                    if self.mesh.xml.mesh.vertices.id == input.vertex.source {
-                        if self.mesh.xml.mesh.vertices.input.texcoord.is_some() {
-                            input.vertex.offset
-                        } else {
-                            return VertexIndices::none();
-                        }
+    pub(super) fn texcoord_offset(&self, set: usize) -> Option<u32> {
+        let input = self.xml.input.as_ref()?;
+        match input.texcoord.get(set) {
+            Some(texcoord) => Some(texcoord.offset),
+            None if set != 0 => None,
+            None => {
+                if self.mesh.xml.mesh.vertices.id == input.vertex.source {
+                    if self.mesh.xml.mesh.vertices.input.texcoord.is_some() {
+                        Some(input.vertex.offset)
                     } else {
-                        // TODO: search other mesh's vertices
-                        todo!()
+                        None
                     }
+                } else {
+                    // TODO: search other mesh's vertices
+                    todo!()
                 }
-            },
-            None => return VertexIndices::none(),
-        };
-        VertexIndices {
-            remaining: self.vertex_indices_size(),
-            inner: self.vertex_indices_inner(offset),
+            }
         }
     }
 
-    pub(super) fn color_indices(&self) -> VertexIndices<'a> {
-        let offset = match &self.xml.input {
-            Some(input) => match &input.color {
-                Some(color) => color.offset,
-                None => {
-                    if self.mesh.xml.mesh.vertices.id == input.vertex.source {
-                        if self.mesh.xml.mesh.vertices.input.color.is_some() {
-                            input.vertex.offset
-                        } else {
-                            return VertexIndices::none();
-                        }
+    pub(super) fn color_offset(&self, set: usize) -> Option<u32> {
+        let input = self.xml.input.as_ref()?;
+        match input.color.get(set) {
+            Some(color) => Some(color.offset),
+            None if set != 0 => None,
+            None => {
+                if self.mesh.xml.mesh.vertices.id == input.vertex.source {
+                    if self.mesh.xml.mesh.vertices.input.color.is_some() {
+                        Some(input.vertex.offset)
                     } else {
-                        // TODO: search other mesh's vertices
-                        todo!()
+                        None
                     }
+                } else {
+                    // TODO: search other mesh's vertices
+                    todo!()
                 }
-            },
-            None => return VertexIndices::none(),
-        };
-        VertexIndices {
-            remaining: self.vertex_indices_size(),
-            inner: self.vertex_indices_inner(offset),
+            }
         }
     }
 }
@@ -5,6 +5,8 @@
 #![allow(clippy::wildcard_imports)] // TODO
 #![allow(clippy::many_single_char_names)]
 
+mod animation;
+mod controller;
 mod effect;
 mod error;
 mod geometry;
@@ -24,7 +26,7 @@ use std::{
     str::{self, FromStr},
 };
 
-use self::{effect::*, geometry::*, image::*, material::*, scene::*};
+use self::{animation::*, controller::*, effect::*, geometry::*, image::*, material::*, scene::*};
 use crate::{
     common,
     utils::{
@@ -32,32 +34,87 @@ use crate::{
         utf16::decode_string,
         xml::{self, XmlNodeExt},
     },
-    Color4,
+    Color4, TextureResolver,
 };
 
 /// Parses meshes from bytes of COLLADA text.
 #[inline]
 pub fn from_slice(bytes: &[u8]) -> io::Result<common::Scene> {
-    from_slice_internal(bytes, None)
+    from_slice_internal(
+        bytes,
+        None,
+        true,
+        None,
+        false,
+        false,
+        &TextureResolver::default(),
+    )
 }
 
 /// Parses meshes from a string of COLLADA text.
 #[inline]
 pub fn from_str(s: &str) -> io::Result<common::Scene> {
-    from_str_internal(s, None)
+    from_str_internal(
+        s,
+        None,
+        true,
+        None,
+        false,
+        false,
+        &TextureResolver::default(),
+    )
 }
 
 #[inline]
-pub(crate) fn from_slice_internal(bytes: &[u8], path: Option<&Path>) -> io::Result<common::Scene> {
-    let bytes = &decode_string(bytes)?;
-    from_str_internal(bytes, path)
+pub(crate) fn from_slice_internal(
+    bytes: &[u8],
+    path: Option<&Path>,
+    pre_transform: bool,
+    coordinate_system: Option<common::CoordinateSystem>,
+    strict: bool,
+    keep_polygons: bool,
+    textures: &TextureResolver,
+) -> io::Result<common::Scene> {
+    let bytes = &decode_string(bytes)
+        .map_err(|e| crate::error::with_context(e, crate::Format::Collada, path))?;
+    from_str_internal(
+        bytes,
+        path,
+        pre_transform,
+        coordinate_system,
+        strict,
+        keep_polygons,
+        textures,
+    )
 }
 
 #[inline]
-pub(crate) fn from_str_internal(s: &str, path: Option<&Path>) -> io::Result<common::Scene> {
-    let xml = xml::Document::parse(s).map_err(crate::error::invalid_data)?;
-    let mut collada = Document::parse(&xml)?;
-    Ok(instance::build(&mut collada, path.and_then(Path::parent)))
+pub(crate) fn from_str_internal(
+    s: &str,
+    path: Option<&Path>,
+    pre_transform: bool,
+    coordinate_system: Option<common::CoordinateSystem>,
+    strict: bool,
+    keep_polygons: bool,
+    textures: &TextureResolver,
+) -> io::Result<common::Scene> {
+    let with_context = |e| crate::error::with_context(e, crate::Format::Collada, path);
+    let xml = xml::Document::parse(s)
+        .map_err(|e| format_err!(ErrorKind::Syntax, "{e}"))
+        .map_err(with_context)?;
+    let mut collada = Document::parse(&xml, strict).map_err(with_context)?;
+    let mut scene = instance::build(
+        &mut collada,
+        path.and_then(Path::parent),
+        pre_transform,
+        coordinate_system,
+        keep_polygons,
+        textures,
+    );
+    for warning in &mut scene.warnings {
+        warning.file = path.filter(|&p| p != Path::new("")).map(Path::to_path_buf);
+    }
+    Ok(scene)
 }
 
 // Inspired by gltf-json's `Get` trait.
@@ -82,6 +139,7 @@ macro_rules! impl_get_by_uri {
 
 impl_get_by_uri!(Accessor<'a>, library_geometries.accessors);
 impl_get_by_uri!(ArrayData<'a>, library_geometries.array_data);
+impl_get_by_uri!(Controller<'a>, library_controllers.controllers);
 impl_get_by_uri!(Effect<'a>, library_effects.effects);
 impl_get_by_uri!(Geometry<'a>, library_geometries.geometries);
 impl_get_by_uri!(Image<'a>, library_images.images);
@@ -95,7 +153,11 @@ impl<'a, T> Uri<'a, T> {
         if let Some(id) = url.strip_prefix('#') {
             Ok(Self(id, PhantomData))
         } else {
-            Err(format_err!("unknown reference format {:?}", url))
+            Err(format_err!(
+                ErrorKind::Unsupported,
+                "unknown reference format {:?}",
+                url
+            ))
         }
     }
 
@@ -152,12 +214,15 @@ impl<'a, 'input> ColladaXmlNodeExt<'a, 'input> for xml::Node<'a, 'input> {
     fn parse_url<T>(&self, name: &str) -> io::Result<Uri<'a, T>> {
         let url = self.required_attribute(name)?;
         Uri::parse(url).map_err(|e| {
-            format_err!(
-                "{} in {} attribute of <{}> element at {}",
-                e,
-                name,
-                self.tag_name().name(),
-                self.attr_value_location(name),
+            crate::error::with_message(
+                &e,
+                format!(
+                    "{} in {} attribute of <{}> element at {}",
+                    e,
+                    name,
+                    self.tag_name().name(),
+                    self.attr_value_location(name),
+                ),
             )
         })
     }
@@ -210,7 +275,7 @@ impl FromStr for Version {
             let patch = digits.next()?.parse().ok()?;
             Some(Self::new(minor, patch))
         })()
-        .ok_or_else(|| format_err!("unrecognized version format {:?}", s))
+        .ok_or_else(|| format_err!(ErrorKind::Syntax, "unrecognized version format {:?}", s))
     }
 }
 
@@ -222,7 +287,12 @@ impl fmt::Display for Version {
 
 struct Context<'a> {
     version: Version,
+    /// Whether to reject input that does not conform to the specification
+    /// instead of working around it.
+    strict: bool,
     asset: Asset,
+    library_animations: LibraryAnimations<'a>,
+    library_controllers: LibraryControllers<'a>,
     library_effects: LibraryEffects<'a>,
     library_geometries: LibraryGeometries<'a>,
     library_images: LibraryImages<'a>,
@@ -233,6 +303,8 @@ struct Context<'a> {
 
 struct Document<'a> {
     asset: Asset,
+    library_animations: LibraryAnimations<'a>,
+    library_controllers: LibraryControllers<'a>,
     library_effects: LibraryEffects<'a>,
     library_geometries: LibraryGeometries<'a>,
     library_images: LibraryImages<'a>,
@@ -277,23 +349,34 @@ impl<'a> Document<'a> {
     - `<scene>` (0 or 1)
     - `<extra>` (0 or more)
     */
-    fn parse(doc: &'a xml::Document<'_>) -> io::Result<Self> {
+    fn parse(doc: &'a xml::Document<'_>, strict: bool) -> io::Result<Self> {
         let node = doc.root_element();
         if node.tag_name().name() != "COLLADA" {
-            bail!("root element is not <COLLADA>");
+            bail!(
+                ErrorKind::UnexpectedElement,
+                "root element is not <COLLADA>"
+            );
         }
 
         let version: Version = node.required_attribute("version")?.parse()?;
         if version < Version::MIN {
-            bail!("collada schema version {} is not supported", version);
+            bail!(
+                ErrorKind::Unsupported,
+                "collada schema version {} is not supported",
+                version
+            );
         };
         // debug!("collada schema version is {}", version);
 
         let mut cx = Context {
             version,
+            strict,
             asset: Asset {
                 unit: DEFAULT_UNIT_SIZE,
+                up_axis: common::CoordinateSystem::Y_UP,
             },
+            library_animations: LibraryAnimations::default(),
+            library_controllers: LibraryControllers::default(),
             library_effects: LibraryEffects::default(),
             library_geometries: LibraryGeometries::default(),
             library_images: LibraryImages::default(),
@@ -304,6 +387,12 @@ impl<'a> Document<'a> {
 
         for node in node.element_children() {
             match node.tag_name().name() {
+                "library_animations" => {
+                    parse_library_animations(&mut cx, node)?;
+                }
+                "library_controllers" => {
+                    parse_library_controllers(&mut cx, node)?;
+                }
                 "library_effects" => {
                     parse_library_effects(&mut cx, node)?;
                 }
@@ -333,6 +422,8 @@ impl<'a> Document<'a> {
 
         Ok(Self {
             asset: cx.asset,
+            library_animations: cx.library_animations,
+            library_controllers: cx.library_controllers,
             library_effects: cx.library_effects,
             library_geometries: cx.library_geometries,
             library_images: cx.library_images,
@@ -368,6 +459,8 @@ const DEFAULT_UNIT_SIZE: f32 = 1.;
 struct Asset {
     // <unit meter="<float>" name="..."/>
     unit: f32,
+    // <up_axis>X_UP|Y_UP|Z_UP</up_axis>
+    up_axis: common::CoordinateSystem,
 }
 
 impl Asset {
@@ -375,6 +468,7 @@ impl Asset {
         debug_assert_eq!(node.tag_name().name(), "asset");
 
         let mut unit = None;
+        let mut up_axis = None;
         for child in node.element_children() {
             match child.tag_name().name() {
                 "unit" => {
@@ -382,6 +476,7 @@ impl Asset {
                         let v = xml::comma_to_period(v);
                         unit = Some(v.parse().map_err(|e| {
                             format_err!(
+                                ErrorKind::InvalidFloat,
                                 "{} in <{}> element at {}: {:?}",
                                 e,
                                 child.tag_name().name(),
@@ -391,13 +486,27 @@ impl Asset {
                         })?);
                     }
                 }
-                "up_axis" => {} // TODO
+                "up_axis" => {
+                    up_axis = Some(match child.trimmed_text() {
+                        "X_UP" => common::CoordinateSystem::X_UP,
+                        "Y_UP" => common::CoordinateSystem::Y_UP,
+                        "Z_UP" => common::CoordinateSystem::Z_UP,
+                        v => bail!(
+                            ErrorKind::Syntax,
+                            "unknown up axis {:?} in <{}> element at {}",
+                            v,
+                            child.tag_name().name(),
+                            child.text_location(),
+                        ),
+                    });
+                }
                 _ => { /* ignore */ }
             }
         }
 
         Ok(Self {
             unit: unit.unwrap_or(DEFAULT_UNIT_SIZE),
+            up_axis: up_axis.unwrap_or(common::CoordinateSystem::Y_UP),
         })
     }
 }
@@ -514,6 +623,7 @@ fn parse_array_element<'a>(node: xml::Node<'a, '_>) -> io::Result<ArrayElement<'
         for _ in 0..count {
             if content.is_empty() {
                 bail!(
+                    ErrorKind::InvalidSize,
                     "expected more values while reading <{}> contents at {}",
                     node.tag_name().name(),
                     node.node_location()
@@ -523,7 +633,7 @@ fn parse_array_element<'a>(node: xml::Node<'a, '_>) -> io::Result<ArrayElement<'
             let mut n = 0;
             while content
                 .as_bytes()
-                .first()
+                .get(n)
                 .map_or(false, |&b| !xml::is_whitespace(b as char))
             {
                 n += 1;
@@ -544,10 +654,13 @@ fn parse_array_element<'a>(node: xml::Node<'a, '_>) -> io::Result<ArrayElement<'
         let content = xml::comma_to_period(content);
         // TODO: include in parse_float_array_exact?
         let map_err = |e| {
-            format_err!(
-                "{e} in <{}> element ({})",
-                node.tag_name().name(),
-                node.text_location(),
+            crate::error::with_message(
+                &e,
+                format!(
+                    "{e} in <{}> element ({})",
+                    node.tag_name().name(),
+                    node.text_location(),
+                ),
             )
         };
         for res in xml::parse_float_array_exact(&content, count as usize) {
@@ -568,17 +681,14 @@ enum ArrayData<'a> {
     /// <float_array>
     Float(Vec<f32>),
     /// <IDREF_array> or <Name_array>
-    String(
-        #[allow(dead_code)] // TODO
-        Vec<&'a str>,
-    ),
+    String(Vec<&'a str>),
     // /// <int_array>
     // Int(Vec<i32>),
     // /// <bool_array>
     // Bool(Vec<bool>),
 }
 
-impl ArrayData<'_> {
+impl<'a> ArrayData<'a> {
     // fn is_float(&self) -> bool {
     //     matches!(self, Self::Float(..))
     // }
@@ -592,12 +702,12 @@ impl ArrayData<'_> {
             Self::String(..) => None,
         }
     }
-    // fn as_string(&self) -> Option<&[&'a str]> {
-    //     match self {
-    //         Self::String(v) => Some(v),
-    //         _ => None,
-    //     }
-    // }
+    fn as_string(&self) -> Option<&[&'a str]> {
+        match self {
+            Self::String(v) => Some(v),
+            Self::Float(..) => None,
+        }
+    }
 
     // fn len(&self) -> usize {
     //     match self {
@@ -891,7 +1001,586 @@ impl FromStr for InputSemantic {
             "UV" => Self::UV,
             "VERTEX" => Self::VERTEX,
             "WEIGHT" => Self::WEIGHT,
-            _ => bail!("unknown input semantic {:?}", s),
+            _ => bail!(ErrorKind::Syntax, "unknown input semantic {:?}", s),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A node hierarchy with two instantiated geometries, a skinned geometry,
+    // and an animation.
+    const SCENE: &str = r##"<?xml version="1.0" encoding="utf-8"?>
+<COLLADA xmlns="http://www.collada.org/2005/11/COLLADASchema" version="1.4.1">
+  <asset><up_axis>Y_UP</up_axis></asset>
+  <library_geometries>
+    <geometry id="a">
+      <mesh>
+        <source id="a-pos">
+          <float_array id="a-pos-array" count="9">0 0 0 1 0 0 0 1 0</float_array>
+          <technique_common>
+            <accessor source="#a-pos-array" count="3" stride="3">
+              <param name="X" type="float"/><param name="Y" type="float"/><param name="Z" type="float"/>
+            </accessor>
+          </technique_common>
+        </source>
+        <vertices id="a-vtx"><input semantic="POSITION" source="#a-pos"/></vertices>
+        <triangles count="1">
+          <input semantic="VERTEX" source="#a-vtx" offset="0"/>
+          <p>0 1 2</p>
+        </triangles>
+      </mesh>
+    </geometry>
+    <geometry id="b">
+      <mesh>
+        <source id="b-pos">
+          <float_array id="b-pos-array" count="9">0 0 0 0 0 1 1 0 0</float_array>
+          <technique_common>
+            <accessor source="#b-pos-array" count="3" stride="3">
+              <param name="X" type="float"/><param name="Y" type="float"/><param name="Z" type="float"/>
+            </accessor>
+          </technique_common>
+        </source>
+        <vertices id="b-vtx"><input semantic="POSITION" source="#b-pos"/></vertices>
+        <triangles count="1">
+          <input semantic="VERTEX" source="#b-vtx" offset="0"/>
+          <p>0 1 2</p>
+        </triangles>
+      </mesh>
+    </geometry>
+    <geometry id="c">
+      <mesh>
+        <source id="c-pos">
+          <float_array id="c-pos-array" count="9">0 0 0 1 0 0 1 1 0</float_array>
+          <technique_common>
+            <accessor source="#c-pos-array" count="3" stride="3">
+              <param name="X" type="float"/><param name="Y" type="float"/><param name="Z" type="float"/>
+            </accessor>
+          </technique_common>
+        </source>
+        <vertices id="c-vtx"><input semantic="POSITION" source="#c-pos"/></vertices>
+        <triangles count="1">
+          <input semantic="VERTEX" source="#c-vtx" offset="0"/>
+          <p>0 1 2</p>
+        </triangles>
+      </mesh>
+    </geometry>
+  </library_geometries>
+  <library_controllers>
+    <controller id="skin">
+      <skin source="#c">
+        <bind_shape_matrix>1 0 0 0 0 1 0 0 0 0 1 0 0 0 0 1</bind_shape_matrix>
+        <source id="skin-joints">
+          <Name_array id="skin-joints-array" count="1">bone</Name_array>
+          <technique_common>
+            <accessor source="#skin-joints-array" count="1" stride="1">
+              <param name="JOINT" type="name"/>
+            </accessor>
+          </technique_common>
+        </source>
+        <source id="skin-bind">
+          <float_array id="skin-bind-array" count="16">1 0 0 0 0 1 0 0 0 0 1 -3 0 0 0 1</float_array>
+          <technique_common>
+            <accessor source="#skin-bind-array" count="1" stride="16">
+              <param name="TRANSFORM" type="float4x4"/>
+            </accessor>
+          </technique_common>
+        </source>
+        <source id="skin-weights">
+          <float_array id="skin-weights-array" count="2">1 0.5</float_array>
+          <technique_common>
+            <accessor source="#skin-weights-array" count="2" stride="1">
+              <param name="WEIGHT" type="float"/>
+            </accessor>
+          </technique_common>
+        </source>
+        <joints>
+          <input semantic="JOINT" source="#skin-joints"/>
+          <input semantic="INV_BIND_MATRIX" source="#skin-bind"/>
+        </joints>
+        <vertex_weights count="3">
+          <input semantic="JOINT" source="#skin-joints" offset="0"/>
+          <input semantic="WEIGHT" source="#skin-weights" offset="1"/>
+          <vcount>1 1 1</vcount>
+          <v>0 0 0 0 0 1</v>
+        </vertex_weights>
+      </skin>
+    </controller>
+  </library_controllers>
+  <library_animations>
+    <animation id="move" name="move">
+      <source id="move-input">
+        <float_array id="move-input-array" count="2">0 1</float_array>
+        <technique_common>
+          <accessor source="#move-input-array" count="2" stride="1">
+            <param name="TIME" type="float"/>
+          </accessor>
+        </technique_common>
+      </source>
+      <source id="move-output">
+        <float_array id="move-output-array" count="2">1 4</float_array>
+        <technique_common>
+          <accessor source="#move-output-array" count="2" stride="1">
+            <param name="X" type="float"/>
+          </accessor>
+        </technique_common>
+      </source>
+      <source id="move-interpolation">
+        <Name_array id="move-interpolation-array" count="2">LINEAR STEP</Name_array>
+        <technique_common>
+          <accessor source="#move-interpolation-array" count="2" stride="1">
+            <param name="INTERPOLATION" type="name"/>
+          </accessor>
+        </technique_common>
+      </source>
+      <sampler id="move-sampler">
+        <input semantic="INPUT" source="#move-input"/>
+        <input semantic="OUTPUT" source="#move-output"/>
+        <input semantic="INTERPOLATION" source="#move-interpolation"/>
+      </sampler>
+      <sampler>
+        <input semantic="INPUT" source="#move-input"/>
+        <input semantic="OUTPUT" source="#move-output"/>
+      </sampler>
+      <channel source="#move-sampler" target="root/location.X"/>
+    </animation>
+  </library_animations>
+  <library_visual_scenes>
+    <visual_scene id="scene">
+      <node id="root" name="root">
+        <translate sid="location">1 0 0</translate>
+        <rotate sid="rotationZ">0 0 1 0</rotate>
+        <instance_geometry url="#b"/>
+        <node id="child" name="child">
+          <translate sid="location">0 2 0</translate>
+          <instance_geometry url="#a"/>
+        </node>
+      </node>
+      <node id="bone" sid="bone" name="bone" type="JOINT">
+        <translate sid="location">0 0 3</translate>
+      </node>
+      <node id="skinned" name="skinned">
+        <instance_controller url="#skin"><skeleton>#bone</skeleton></instance_controller>
+      </node>
+    </visual_scene>
+  </library_visual_scenes>
+  <scene><instance_visual_scene url="#scene"/></scene>
+</COLLADA>
+"##;
+
+    #[test]
+    fn nodes() {
+        let loader = crate::Loader::default().collada_pre_transform(false);
+        let scene = loader
+            .load_collada_from_slice(SCENE.as_bytes(), "a.dae")
+            .unwrap();
+        let names: Vec<_> = scene.nodes.iter().map(|n| &*n.name).collect();
+        assert_eq!(names, ["scene", "root", "child", "bone", "skinned"]);
+        let parents: Vec<_> = scene.nodes.iter().map(|n| n.parent).collect();
+        assert_eq!(parents, [None, Some(0), Some(1), Some(0), Some(0)]);
+        assert_eq!(scene.nodes[1].transform[3], [1., 0., 0., 1.]);
+        assert_eq!(scene.nodes[2].transform[3], [0., 2., 0., 1.]);
+        assert_eq!(scene.nodes[1].meshes, [1]);
+        assert_eq!(scene.nodes[2].meshes, [0]);
+        assert_eq!(scene.nodes[4].meshes, [2]);
+        assert_eq!(scene.meshes[0].vertices[1], [1., 0., 0.]);
+
+        let pre_transformed = crate::Loader::default()
+            .load_collada_from_slice(SCENE.as_bytes(), "a.dae")
+            .unwrap();
+        assert_eq!(pre_transformed.meshes[0].vertices[1], [2., 2., 0.]);
+        assert_eq!(pre_transformed.meshes[1].vertices[1], [1., 0., 1.]);
+
+        // Merged meshes are always in world space, and have no nodes.
+        let loader = loader.merge_meshes(true);
+        let merged = loader
+            .load_collada_from_slice(SCENE.as_bytes(), "a.dae")
+            .unwrap();
+        assert_eq!(merged.meshes.len(), 1);
+        assert!(merged.nodes.is_empty());
+        let expected: Vec<_> = pre_transformed
+            .meshes
+            .iter()
+            .flat_map(|m| &m.vertices)
+            .copied()
+            .collect();
+        assert_eq!(merged.meshes[0].vertices, expected);
+        // This is also true if there is only one mesh.
+        let start = SCENE.find(r#"<geometry id="b">"#).unwrap();
+        let end = SCENE.find("<library_visual_scenes>").unwrap();
+        let single = SCENE[..start].to_owned()
+            + "</library_geometries>"
+            + &SCENE[end..]
+                .replace(r##"<instance_geometry url="#b"/>"##, "")
+                .replace(r##"<instance_controller url="#skin">"##, "<!--")
+                .replace("</instance_controller>", "-->");
+        let merged = loader
+            .load_collada_from_slice(single.as_bytes(), "a.dae")
+            .unwrap();
+        assert_eq!(merged.meshes.len(), 1);
+        assert!(merged.nodes.is_empty());
+        assert_eq!(merged.meshes[0].vertices[1], [2., 2., 0.]);
+    }
+
+    #[test]
+    fn skin() {
+        let scene = crate::Loader::default()
+            .load_collada_from_slice(SCENE.as_bytes(), "a.dae")
+            .unwrap();
+        let mesh = &scene.meshes[2];
+        assert_eq!(mesh.bones.len(), 1);
+        let bone = &mesh.bones[0];
+        assert_eq!(bone.name, "bone");
+        assert_eq!(bone.node, Some(3));
+        assert_eq!(bone.offset_matrix[3], [0., 0., -3., 1.]);
+        let weights: Vec<_> = bone.weights.iter().map(|w| (w.vertex, w.weight)).collect();
+        assert_eq!(weights, [(0, 1.), (1, 1.), (2, 0.5)]);
+
+        // Bones must not refer to discarded nodes.
+        let merged = crate::Loader::default()
+            .merge_meshes(true)
+            .load_collada_from_slice(SCENE.as_bytes(), "a.dae")
+            .unwrap();
+        let bone = &merged.meshes[0].bones[0];
+        assert_eq!(bone.node, None);
+        assert_eq!(bone.weights[0].vertex, 6);
+    }
+
+    #[test]
+    fn animation() {
+        let scene = crate::Loader::default()
+            .load_collada_from_slice(SCENE.as_bytes(), "a.dae")
+            .unwrap();
+        assert_eq!(scene.animations.len(), 1);
+        let animation = &scene.animations[0];
+        assert_eq!(animation.name, "move");
+        assert_eq!(animation.channels.len(), 1);
+        let channel = &animation.channels[0];
+        assert_eq!(channel.node, 1);
+        assert_eq!(channel.target, "location.X");
+        assert_eq!(channel.times, [0., 1.]);
+        assert_eq!(channel.values, [1., 4.]);
+        assert_eq!(channel.stride, 1);
+        assert_eq!(
+            channel.interpolations,
+            [common::Interpolation::Linear, common::Interpolation::Step]
+        );
+
+        // The channel targets a transform element of the node.
+        let transforms = &scene.nodes[channel.node].transforms;
+        assert_eq!(transforms.len(), 2);
+        assert_eq!(transforms[0].sid.as_deref(), Some("location"));
+        assert_eq!(transforms[0].kind, common::NodeTransformKind::Translate);
+        assert_eq!(transforms[0].values, [1., 0., 0.]);
+        assert_eq!(transforms[1].sid.as_deref(), Some("rotationZ"));
+        assert_eq!(transforms[1].kind, common::NodeTransformKind::Rotate);
+        assert_eq!(transforms[1].values, [0., 0., 1., 0.]);
+    }
+
+    #[test]
+    fn texcoord_sets() {
+        // More than eight texture coordinate sets.
+        let texcoords = SCENE.replace(
+            r##"        <vertices id="a-vtx"><input semantic="POSITION" source="#a-pos"/></vertices>
+        <triangles count="1">
+          <input semantic="VERTEX" source="#a-vtx" offset="0"/>"##,
+            r##"        <source id="uv0">
+          <float_array id="uv0-array" count="6">0 0 1 0 0 1</float_array>
+          <technique_common>
+            <accessor source="#uv0-array" count="3" stride="2">
+              <param name="S" type="float"/><param name="T" type="float"/>
+            </accessor>
+          </technique_common>
+        </source>
+        <source id="uv1">
+          <float_array id="uv1-array" count="6">1 1 0 1 1 0</float_array>
+          <technique_common>
+            <accessor source="#uv1-array" count="3" stride="2">
+              <param name="S" type="float"/><param name="T" type="float"/>
+            </accessor>
+          </technique_common>
+        </source>
+        <vertices id="a-vtx"><input semantic="POSITION" source="#a-pos"/></vertices>
+        <triangles count="1">
+          <input semantic="VERTEX" source="#a-vtx" offset="0"/>
+          <input semantic="TEXCOORD" source="#uv0" offset="0" set="0"/>
+          <input semantic="TEXCOORD" source="#uv1" offset="0" set="1"/>
+          <input semantic="TEXCOORD" source="#uv1" offset="0" set="2"/>
+          <input semantic="TEXCOORD" source="#uv1" offset="0" set="3"/>
+          <input semantic="TEXCOORD" source="#uv1" offset="0" set="4"/>
+          <input semantic="TEXCOORD" source="#uv1" offset="0" set="5"/>
+          <input semantic="TEXCOORD" source="#uv1" offset="0" set="6"/>
+          <input semantic="TEXCOORD" source="#uv1" offset="0" set="7"/>
+          <input semantic="TEXCOORD" source="#uv1" offset="0" set="8"/>
+          <input semantic="TEXCOORD" source="#uv1" offset="0" set="9"/>"##,
+        );
+        assert_ne!(texcoords, SCENE);
+        let scene = crate::Loader::default()
+            .load_collada_from_slice(texcoords.as_bytes(), "a.dae")
+            .unwrap();
+        let mesh = &scene.meshes[0];
+        assert_eq!(mesh.texcoords.len(), 10);
+        assert_eq!(mesh.texcoords[0], [[0., 0.], [1., 0.], [0., 1.]]);
+        for set in &mesh.texcoords[1..] {
+            assert_eq!(*set, [[1., 1.], [0., 1.], [1., 0.]]);
+        }
+        assert!(scene.meshes[1].texcoords.iter().all(Vec::is_empty));
+    }
+
+    #[test]
+    fn warnings() {
+        let loader = crate::Loader::default().collect_warnings(true);
+        let scene = loader
+            .load_collada_from_slice(SCENE.as_bytes(), "a.dae")
+            .unwrap();
+        assert!(scene.warnings.is_empty());
+
+        // A material that does not exist.
+        let missing_material = SCENE.replace(
+            r##"<instance_geometry url="#a"/>"##,
+            r##"<instance_geometry url="#a">
+            <bind_material>
+              <technique_common><instance_material symbol="m" target="#missing"/></technique_common>
+            </bind_material>
+          </instance_geometry>"##,
+        );
+        assert_ne!(missing_material, SCENE);
+        let scene = loader
+            .load_collada_from_slice(missing_material.as_bytes(), "a.dae")
+            .unwrap();
+        assert_eq!(scene.warnings.len(), 1);
+        assert_eq!(scene.warnings[0].kind, crate::WarningKind::MissingMaterial);
+        assert_eq!(scene.warnings[0].file.as_deref(), Some(Path::new("a.dae")));
+        // The mesh is kept with the default material.
+        assert_eq!(scene.meshes.len(), 3);
+
+        // Warnings are not collected by default.
+        let scene = crate::Loader::default()
+            .load_collada_from_slice(missing_material.as_bytes(), "a.dae")
+            .unwrap();
+        assert!(scene.warnings.is_empty());
+    }
+
+    #[test]
+    fn embedded_texture() {
+        // Both geometries use a material with an image embedded as hex data.
+        let bind_material = r##"<bind_material>
+              <technique_common><instance_material symbol="m" target="#mat"/></technique_common>
+            </bind_material>"##;
+        let embedded = SCENE
+            .replace(
+                "  <library_visual_scenes>",
+                r##"  <library_images>
+    <image id="img" format="png"><data>89504E47</data></image>
+  </library_images>
+  <library_effects>
+    <effect id="effect">
+      <profile_COMMON>
+        <newparam sid="surface"><surface type="2D"><init_from>img</init_from></surface></newparam>
+        <newparam sid="sampler"><sampler2D><source>surface</source></sampler2D></newparam>
+        <technique sid="common">
+          <phong><diffuse><texture texture="sampler" texcoord="uv"/></diffuse></phong>
+        </technique>
+      </profile_COMMON>
+    </effect>
+  </library_effects>
+  <library_materials>
+    <material id="mat"><instance_effect url="#effect"/></material>
+  </library_materials>
+  <library_visual_scenes>"##,
+            )
+            .replace(
+                r##"<instance_geometry url="#a"/>"##,
+                &format!(r##"<instance_geometry url="#a">{bind_material}</instance_geometry>"##),
+            )
+            .replace(
+                r##"<instance_geometry url="#b"/>"##,
+                &format!(r##"<instance_geometry url="#b">{bind_material}</instance_geometry>"##),
+            );
+        let scene = crate::Loader::default()
+            .collect_warnings(true)
+            .load_collada_from_slice(embedded.as_bytes(), "a.dae")
+            .unwrap();
+        assert!(scene.warnings.is_empty());
+        // The image is embedded once.
+        assert_eq!(scene.embedded_textures.len(), 1);
+        let texture = &scene.embedded_textures[0];
+        assert_eq!(texture.name, "img");
+        assert_eq!(texture.data, [0x89, 0x50, 0x4E, 0x47]);
+        assert_eq!(texture.format_hint.as_deref(), Some("png"));
+        for material in &scene.materials[..2] {
+            let diffuse = material.texture.diffuse.as_ref().unwrap();
+            assert_eq!(diffuse.source, common::TextureSource::Embedded(0));
+            assert_eq!(diffuse.path(), None);
+        }
+    }
+
+    #[test]
+    fn resource_resolver() {
+        use std::{collections::HashMap, path::PathBuf};
+
+        struct Store(HashMap<PathBuf, Vec<u8>>);
+        impl crate::ResourceResolver for Store {
+            fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+                self.0
+                    .get(path)
+                    .cloned()
+                    .ok_or_else(|| io::ErrorKind::NotFound.into())
+            }
+            fn exists(&self, path: &Path) -> bool {
+                self.0.contains_key(path)
+            }
+        }
+
+        // The file and its texture only exist in the store.
+        let textured = SCENE
+            .replace(
+                "  <library_visual_scenes>",
+                r##"  <library_images>
+    <image id="img"><init_from>tex.png</init_from></image>
+  </library_images>
+  <library_effects>
+    <effect id="effect">
+      <profile_COMMON>
+        <technique sid="common">
+          <phong><diffuse><texture texture="img" texcoord="uv"/></diffuse></phong>
+        </technique>
+      </profile_COMMON>
+    </effect>
+  </library_effects>
+  <library_materials>
+    <material id="mat"><instance_effect url="#effect"/></material>
+  </library_materials>
+  <library_visual_scenes>"##,
+            )
+            .replace(
+                r##"<instance_geometry url="#a"/>"##,
+                r##"<instance_geometry url="#a">
+            <bind_material>
+              <technique_common><instance_material symbol="m" target="#mat"/></technique_common>
+            </bind_material>
+          </instance_geometry>"##,
+            );
+        let dir = Path::new("mesh-loader-resource-resolver");
+        let mut files = HashMap::new();
+        files.insert(dir.join("a.dae"), textured.into_bytes());
+        files.insert(dir.join("tex.png"), vec![]);
+        let scene = crate::Loader::default()
+            .collect_warnings(true)
+            .resource_resolver(Store(files.clone()))
+            .load(dir.join("a.dae"))
+            .unwrap();
+        assert!(scene.warnings.is_empty());
+        let diffuse = scene.materials[0].texture.diffuse.as_ref().unwrap();
+        assert_eq!(diffuse.path(), Some(&*dir.join("tex.png")));
+
+        files.remove(&dir.join("tex.png"));
+        let scene = crate::Loader::default()
+            .collect_warnings(true)
+            .resource_resolver(Store(files))
+            .load(dir.join("a.dae"))
+            .unwrap();
+        assert_eq!(scene.warnings[0].kind, crate::WarningKind::MissingTexture);
+        assert!(scene.materials[0].texture.diffuse.is_none());
+
+        let e = crate::Loader::default()
+            .resource_resolver(Store(HashMap::new()))
+            .load(dir.join("a.dae"))
+            .unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn strictness() {
+        fn kind(e: &io::Error) -> crate::ErrorKind {
+            crate::Error::from_io_error(e).unwrap().kind()
+        }
+
+        // The number of lines does not match `count`, which SketchUp is known
+        // to write.
+        let wrong_lines_count = SCENE.replace(
+            r##"<triangles count="1">
+          <input semantic="VERTEX" source="#a-vtx" offset="0"/>
+          <p>0 1 2</p>
+        </triangles>"##,
+            r##"<lines count="1">
+          <input semantic="VERTEX" source="#a-vtx" offset="0"/>
+          <p>0 1 1 2</p>
+        </lines>"##,
+        );
+        assert_ne!(wrong_lines_count, SCENE);
+        let scene = crate::Loader::default()
+            .load_collada_from_slice(wrong_lines_count.as_bytes(), "a.dae")
+            .unwrap();
+        assert_eq!(scene.meshes[0].lines.len(), 2);
+        let e = crate::Loader::default()
+            .strictness(crate::Strictness::Strict)
+            .load_collada_from_slice(wrong_lines_count.as_bytes(), "a.dae")
+            .unwrap_err();
+        assert_eq!(kind(&e), crate::ErrorKind::InvalidSize);
+
+        // The number of triangles does not match `count`.
+        let wrong_count = SCENE.replace(
+            r##"<triangles count="1">
+          <input semantic="VERTEX" source="#a-vtx" offset="0"/>"##,
+            r##"<triangles count="2">
+          <input semantic="VERTEX" source="#a-vtx" offset="0"/>"##,
+        );
+        assert_ne!(wrong_count, SCENE);
+        for strictness in [crate::Strictness::Lenient, crate::Strictness::Strict] {
+            let e = crate::Loader::default()
+                .strictness(strictness)
+                .load_collada_from_slice(wrong_count.as_bytes(), "a.dae")
+                .unwrap_err();
+            assert_eq!(kind(&e), crate::ErrorKind::InvalidSize);
+        }
+    }
+
+    #[test]
+    fn up_axis() {
+        let loader = crate::Loader::default().coordinate_system(common::CoordinateSystem::Z_UP);
+        let scene = loader
+            .load_collada_from_slice(SCENE.as_bytes(), "a.dae")
+            .unwrap();
+        assert_eq!(
+            scene.coordinate_system,
+            Some(common::CoordinateSystem::Z_UP)
+        );
+        // Y-up, +Z forward -> Z-up, -Y forward: (x, y, z) -> (x, -z, y)
+        assert_eq!(scene.meshes[0].vertices[1], [2., 0., 2.]);
+        assert_eq!(scene.meshes[1].vertices[1], [1., -1., 0.]);
+
+        // Without pre-transform, the rotation is applied to the root node.
+        let scene = loader
+            .collada_pre_transform(false)
+            .load_collada_from_slice(SCENE.as_bytes(), "a.dae")
+            .unwrap();
+        assert_eq!(scene.meshes[0].vertices[1], [1., 0., 0.]);
+        assert_eq!(
+            scene.nodes[0].transform,
+            [
+                [1., 0., 0., 0.],
+                [0., 0., 1., 0.],
+                [0., -1., 0., 0.],
+                [0., 0., 0., 1.]
+            ]
+        );
+
+        // Files in the target coordinate system are not rotated.
+        let z_up = SCENE.replace("<up_axis>Y_UP</up_axis>", "<up_axis>Z_UP</up_axis>");
+        let scene = crate::Loader::default()
+            .coordinate_system(common::CoordinateSystem::Z_UP)
+            .load_collada_from_slice(z_up.as_bytes(), "a.dae")
+            .unwrap();
+        assert_eq!(scene.meshes[0].vertices[1], [2., 2., 0.]);
+        let scene = crate::Loader::default()
+            .load_collada_from_slice(z_up.as_bytes(), "a.dae")
+            .unwrap();
+        assert_eq!(
+            scene.coordinate_system,
+            Some(common::CoordinateSystem::Z_UP)
+        );
+    }
+}
@@ -47,18 +47,19 @@ pub(super) struct LibraryVisualScenes<'a> {
 pub(super) struct Node<'a> {
     /// The unique identifier of this element.
     pub(super) id: Option<&'a str>,
-    // /// The name of this element.
-    // pub(super) name: Option<&'a str>,
-    // /// The scoped identifier of this element.
-    // pub(super) sid: Option<&'a str>,
+    /// The name of this element.
+    pub(super) name: Option<&'a str>,
+    /// The scoped identifier of this element.
+    pub(super) sid: Option<&'a str>,
     // /// The type of this element.
     // pub(super) ty: NodeType,
     pub(super) parent: Option<usize>,
     // pub(super) children: Vec<usize>,
-    // pub(super) transforms: Vec<Transform>,
+    /// Transform elements and their scoped identifiers, in document order.
+    pub(super) transforms: Vec<(Option<&'a str>, Transform)>,
     pub(super) transform: Matrix4x4,
     // pub(super) instance_camera: Vec<InstanceCamera>,
-    // pub(super) instance_controller: Vec<InstanceController>,
+    pub(super) instance_controller: Vec<InstanceController<'a>>,
     pub(super) instance_geometry: Vec<InstanceGeometry<'a>>,
     // pub(super) instance_light: Vec<InstanceLight>,
     // pub(super) instance_node: Vec<InstanceNode>,
@@ -82,13 +83,13 @@ pub(super) enum Transform {
     Rotate([f32; 4]),
     Translate([f32; 3]),
     Scale([f32; 3]),
-    Skew(#[allow(dead_code)] [f32; 7]),
+    Skew([f32; 7]),
     Matrix([f32; 16]),
 }
 
 impl Transform {
     // Based on https://github.com/assimp/assimp/blob/v5.3.1/code/AssetLib/Collada/ColladaParser.cpp#L2318
-    fn calculate_transform(transforms: &[Self]) -> Matrix4x4 {
+    fn calculate_transform(transforms: &[(Option<&str>, Self)]) -> Matrix4x4 {
         // Based on https://github.com/assimp/assimp/blob/v5.3.1/include/assimp/vector3.inl
         fn sub(mut a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
             a[0] -= b[0];
@@ -103,21 +104,9 @@ impl Transform {
             r[2] = a[0] * b[1] - a[1] * b[0];
             r
         }
-        fn normalize(mut v: [f32; 3]) -> [f32; 3] {
-            let square_len = v[0] * v[0] + v[1] * v[1] + v[2] * v[2];
-            let len = square_len.sqrt();
-            if len == 0. {
-                return v;
-            }
-            let inv = 1. / len;
-            v[0] *= inv;
-            v[1] *= inv;
-            v[2] *= inv;
-            v
-        }
 
         let mut out = None;
-        for transform in transforms {
+        for (_, transform) in transforms {
             match transform {
                 Self::Lookat(f) => {
                     let pos = [f[0], f[1], f[2]];
@@ -178,6 +167,20 @@ impl Transform {
     }
 }
 
+// Based on https://github.com/assimp/assimp/blob/v5.3.1/include/assimp/vector3.inl
+pub(super) fn normalize(mut v: [f32; 3]) -> [f32; 3] {
+    let square_len = v[0] * v[0] + v[1] * v[1] + v[2] * v[2];
+    let len = square_len.sqrt();
+    if len == 0. {
+        return v;
+    }
+    let inv = 1. / len;
+    v[0] *= inv;
+    v[1] *= inv;
+    v[2] *= inv;
+    v
+}
+
 // Based on https://github.com/assimp/assimp/blob/v5.3.1/include/assimp/matrix4x4.inl
 #[derive(Clone, Copy)]
 pub(super) struct Matrix4x4 {
@@ -268,6 +271,82 @@ impl Matrix4x4 {
             ..Default::default()
         }
     }
+    /// Converts this matrix to a column-major array.
+    pub(super) const fn to_cols_array_2d(self) -> [[f32; 4]; 4] {
+        [
+            [self.a1, self.b1, self.c1, self.d1],
+            [self.a2, self.b2, self.c2, self.d2],
+            [self.a3, self.b3, self.c3, self.d3],
+            [self.a4, self.b4, self.c4, self.d4],
+        ]
+    }
+    /// Returns the cofactor matrix of the upper-left 3x3 part of this matrix
+    /// and its determinant.
+    ///
+    /// The cofactor matrix is the inverse transpose multiplied by the determinant.
+    fn cofactor3x3(&self) -> ([f32; 9], f32) {
+        let Self {
+            a1,
+            a2,
+            a3,
+            b1,
+            b2,
+            b3,
+            c1,
+            c2,
+            c3,
+            ..
+        } = *self;
+        let co = [
+            b2 * c3 - b3 * c2,
+            b3 * c1 - b1 * c3,
+            b1 * c2 - b2 * c1,
+            a3 * c2 - a2 * c3,
+            a1 * c3 - a3 * c1,
+            a2 * c1 - a1 * c2,
+            a2 * b3 - a3 * b2,
+            a3 * b1 - a1 * b3,
+            a1 * b2 - a2 * b1,
+        ];
+        let det = a1 * co[0] + a2 * co[1] + a3 * co[2];
+        (co, det)
+    }
+    /// Returns the matrix to transform normals, i.e., the inverse transpose of
+    /// the upper-left 3x3 part of this matrix.
+    pub(super) fn normal_matrix(self) -> Self {
+        let (co, det) = self.cofactor3x3();
+        if det == 0. {
+            return Self::new(
+                self.a1, self.a2, self.a3, 0., self.b1, self.b2, self.b3, 0., self.c1, self.c2,
+                self.c3, 0., 0., 0., 0., 1.,
+            );
+        }
+        // Normals are normalized after the transform, so only the sign matters.
+        let sign = det.signum();
+        let co = co.map(|x| x * sign);
+        Self::new(
+            co[0], co[1], co[2], 0., co[3], co[4], co[5], 0., co[6], co[7], co[8], 0., 0., 0., 0.,
+            1.,
+        )
+    }
+    /// Returns the inverse of this matrix, assuming that this is an affine
+    /// transform (i.e., the last row is `0 0 0 1`).
+    ///
+    /// Returns `None` if this matrix is not invertible.
+    pub(super) fn inverse_affine(self) -> Option<Self> {
+        let (co, det) = self.cofactor3x3();
+        if det == 0. {
+            return None;
+        }
+        let inv = 1. / det;
+        // The inverse of the 3x3 part is the transposed cofactor matrix divided by the determinant.
+        let m = [0, 3, 6, 1, 4, 7, 2, 5, 8].map(|i| co[i] * inv);
+        let t = [self.a4, self.b4, self.c4];
+        let [x, y, z] = [0, 3, 6].map(|i| -(m[i] * t[0] + m[i + 1] * t[1] + m[i + 2] * t[2]));
+        Some(Self::new(
+            m[0], m[1], m[2], x, m[3], m[4], m[5], y, m[6], m[7], m[8], z, 0., 0., 0., 1.,
+        ))
+    }
     pub(super) fn is_identity(&self) -> bool {
         // TODO: use f32::EPSILON?
         const EPSILON: f32 = 10e-3;
@@ -366,23 +445,21 @@ pub(super) struct InstanceGeometry<'a> {
     pub(super) materials: BTreeMap<&'a str, SemanticMappingTable<'a>>,
 }
 
-/*
 /// The `<instance_controller>` element.
 ///
 /// See the [specification][1.4] for details.
 ///
 /// [1.4]: https://www.khronos.org/files/collada_spec_1_4.pdf#page=82
 pub(super) struct InstanceController<'a> {
-    /// The scoped identifier of this element.
-    pub(super) sid: Option<&'a str>,
-    /// The name of this element.
-    pub(super) name: Option<&'a str>,
+    // /// The scoped identifier of this element.
+    // pub(super) sid: Option<&'a str>,
+    // /// The name of this element.
+    // pub(super) name: Option<&'a str>,
     /// The URI of the location of the [`Controller`] to instantiate.
-    pub(super) url: Uri<Controller>,
+    pub(super) url: Uri<'a, Controller<'a>>,
 
-    pub(super) materials: IndexMap<&'a str, SemanticMappingTable>,
+    pub(super) materials: BTreeMap<&'a str, SemanticMappingTable<'a>>,
 }
-*/
 
 pub(super) struct SemanticMappingTable<'a> {
     // Required
@@ -519,7 +596,7 @@ fn parse_visual_scene<'a>(node: xml::Node<'a, '_>, nodes: &mut Vec<Node<'a>>) ->
     let mut scene_nodes = vec![];
     let this = Node {
         id,
-        // name: node.attribute("name"),
+        name: node.attribute("name"),
         ..Default::default()
     };
     let this_index = nodes.len();
@@ -584,9 +661,9 @@ fn parse_node<'a>(
     debug_assert_eq!(node.tag_name().name(), "node");
     let _ty: NodeType = node.parse_attribute("type")?.unwrap_or_default();
     let this = Node {
-        // id: node.attribute("id"),
-        // name: node.attribute("name"),
-        // sid: node.attribute("sid"),
+        id: node.attribute("id"),
+        name: node.attribute("name"),
+        sid: node.attribute("sid"),
         // ty,
         parent: Some(parent),
         ..Default::default()
@@ -608,10 +685,13 @@ fn parse_node<'a>(
                 let mut iter = xml::parse_float_array_exact(&content, 9);
                 // TODO: include in parse_float_array_exact?
                 let map_err = |e| {
-                    format_err!(
-                        "{e} in <{}> element ({})",
-                        child.tag_name().name(),
-                        child.text_location(),
+                    crate::error::with_message(
+                        &e,
+                        format!(
+                            "{e} in <{}> element ({})",
+                            child.tag_name().name(),
+                            child.text_location(),
+                        ),
                     )
                 };
                 let t = [
@@ -625,17 +705,20 @@ fn parse_node<'a>(
                     iter.next().unwrap().map_err(map_err)?,
                     iter.next().unwrap().map_err(map_err)?,
                 ];
-                transforms.push(Transform::Lookat(t));
+                transforms.push((child.attribute("sid"), Transform::Lookat(t)));
             }
             "matrix" => {
                 let content = xml::comma_to_period(child.trimmed_text());
                 let mut iter = xml::parse_float_array_exact(&content, 16);
                 // TODO: include in parse_float_array_exact?
                 let map_err = |e| {
-                    format_err!(
-                        "{e} in <{}> element ({})",
-                        child.tag_name().name(),
-                        child.text_location(),
+                    crate::error::with_message(
+                        &e,
+                        format!(
+                            "{e} in <{}> element ({})",
+                            child.tag_name().name(),
+                            child.text_location(),
+                        ),
                     )
                 };
                 let t = [
@@ -656,17 +739,20 @@ fn parse_node<'a>(
                     iter.next().unwrap().map_err(map_err)?,
                     iter.next().unwrap().map_err(map_err)?,
                 ];
-                transforms.push(Transform::Matrix(t));
+                transforms.push((child.attribute("sid"), Transform::Matrix(t)));
             }
             "rotate" => {
                 let content = xml::comma_to_period(child.trimmed_text());
                 let mut iter = xml::parse_float_array_exact(&content, 4);
                 // TODO: include in parse_float_array_exact?
                 let map_err = |e| {
-                    format_err!(
-                        "{e} in <{}> element ({})",
-                        child.tag_name().name(),
-                        child.text_location(),
+                    crate::error::with_message(
+                        &e,
+                        format!(
+                            "{e} in <{}> element ({})",
+                            child.tag_name().name(),
+                            child.text_location(),
+                        ),
                     )
                 };
                 let t = [
@@ -675,17 +761,20 @@ fn parse_node<'a>(
                     iter.next().unwrap().map_err(map_err)?,
                     iter.next().unwrap().map_err(map_err)?,
                 ];
-                transforms.push(Transform::Rotate(t));
+                transforms.push((child.attribute("sid"), Transform::Rotate(t)));
             }
             "scale" => {
                 let content = xml::comma_to_period(child.trimmed_text());
                 let mut iter = xml::parse_float_array_exact(&content, 3);
                 // TODO: include in parse_float_array_exact?
                 let map_err = |e| {
-                    format_err!(
-                        "{e} in <{}> element ({})",
-                        child.tag_name().name(),
-                        child.text_location(),
+                    crate::error::with_message(
+                        &e,
+                        format!(
+                            "{e} in <{}> element ({})",
+                            child.tag_name().name(),
+                            child.text_location(),
+                        ),
                     )
                 };
                 let t = [
@@ -693,17 +782,20 @@ fn parse_node<'a>(
                     iter.next().unwrap().map_err(map_err)?,
                     iter.next().unwrap().map_err(map_err)?,
                 ];
-                transforms.push(Transform::Scale(t));
+                transforms.push((child.attribute("sid"), Transform::Scale(t)));
             }
             "skew" => {
                 let content = xml::comma_to_period(child.trimmed_text());
                 let mut iter = xml::parse_float_array_exact(&content, 7);
                 // TODO: include in parse_float_array_exact?
                 let map_err = |e| {
-                    format_err!(
-                        "{e} in <{}> element ({})",
-                        child.tag_name().name(),
-                        child.text_location(),
+                    crate::error::with_message(
+                        &e,
+                        format!(
+                            "{e} in <{}> element ({})",
+                            child.tag_name().name(),
+                            child.text_location(),
+                        ),
                     )
                 };
                 let t = [
@@ -715,17 +807,20 @@ fn parse_node<'a>(
                     iter.next().unwrap().map_err(map_err)?,
                     iter.next().unwrap().map_err(map_err)?,
                 ];
-                transforms.push(Transform::Skew(t));
+                transforms.push((child.attribute("sid"), Transform::Skew(t)));
             }
             "translate" => {
                 let content = xml::comma_to_period(child.trimmed_text());
                 let mut iter = xml::parse_float_array_exact(&content, 3);
                 // TODO: include in parse_float_array_exact?
                 let map_err = |e| {
-                    format_err!(
-                        "{e} in <{}> element ({})",
-                        child.tag_name().name(),
-                        child.text_location(),
+                    crate::error::with_message(
+                        &e,
+                        format!(
+                            "{e} in <{}> element ({})",
+                            child.tag_name().name(),
+                            child.text_location(),
+                        ),
                     )
                 };
                 let t = [
@@ -733,12 +828,16 @@ fn parse_node<'a>(
                     iter.next().unwrap().map_err(map_err)?,
                     iter.next().unwrap().map_err(map_err)?,
                 ];
-                transforms.push(Transform::Translate(t));
+                transforms.push((child.attribute("sid"), Transform::Translate(t)));
             }
 
             // instances
             "instance_camera" => {}
-            "instance_controller" => {}
+            "instance_controller" => {
+                nodes[this_index]
+                    .instance_controller
+                    .push(parse_instance_controller(child)?);
+            }
             "instance_geometry" => {
                 nodes[this_index]
                     .instance_geometry
@@ -753,6 +852,7 @@ fn parse_node<'a>(
 
     if !transforms.is_empty() {
         nodes[this_index].transform = Transform::calculate_transform(&transforms);
+        nodes[this_index].transforms = transforms;
     }
 
     Ok(this_index)
@@ -765,7 +865,7 @@ impl FromStr for NodeType {
         Ok(match s {
             "NODE" => Self::Node,
             "JOINT" => Self::Joint,
-            _ => bail!("unknown note type {:?}", s),
+            _ => bail!(ErrorKind::Syntax, "unknown note type {:?}", s),
         })
     }
 }
@@ -793,6 +893,29 @@ fn parse_instance_geometry<'a>(node: xml::Node<'a, '_>) -> io::Result<InstanceGe
     })
 }
 
+fn parse_instance_controller<'a>(node: xml::Node<'a, '_>) -> io::Result<InstanceController<'a>> {
+    debug_assert_eq!(node.tag_name().name(), "instance_controller");
+    let url = node.parse_url("url")?;
+    let mut materials = BTreeMap::new();
+
+    for child in node.element_children() {
+        match child.tag_name().name() {
+            "bind_material" => {
+                parse_bind_material(child, &mut materials)?;
+            }
+            "skeleton" | "extra" => { /* skip */ }
+            _ => return Err(error::unexpected_child_elem(child)),
+        }
+    }
+
+    Ok(InstanceController {
+        // sid: node.attribute("sid"),
+        // name: node.attribute("name"),
+        url,
+        materials,
+    })
+}
+
 /*
 The <bind_material> element
 
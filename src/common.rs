@@ -1,33 +1,314 @@
-use std::{fmt, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fmt, iter,
+    path::{Path, PathBuf},
+};
+
+use crate::utils;
 
 pub(crate) type Vec2 = [f32; 2];
 pub(crate) type Vec3 = [f32; 3];
 pub(crate) type Face = [u32; 3];
 pub(crate) type Color4 = [f32; 4];
-
-// TODO: assimp uses 8 here
-pub(crate) const MAX_NUMBER_OF_TEXCOORDS: usize = 2;
-// TODO: assimp uses 8 here
-pub(crate) const MAX_NUMBER_OF_COLOR_SETS: usize = 2;
+pub(crate) type Mat4 = [[f32; 4]; 4];
 
 #[derive(Debug, Clone, Default)]
 #[non_exhaustive]
 pub struct Scene {
     pub materials: Vec<Material>,
     pub meshes: Vec<Mesh>,
+    /// Node hierarchy of the scene.
+    ///
+    /// Parent nodes always appear before their children. This is empty for
+    /// formats that have no node hierarchy.
+    pub nodes: Vec<Node>,
+    /// Coordinate system of the scene, or `None` if the format does not define it.
+    pub coordinate_system: Option<CoordinateSystem>,
+    /// Keyframe animations of nodes in `nodes`.
+    pub animations: Vec<Animation>,
+    /// Texture images embedded in the file.
+    ///
+    /// Materials refer to them by [`TextureSource::Embedded`].
+    pub embedded_textures: Vec<EmbeddedTexture>,
+    /// Non-fatal problems found while parsing, such as unresolved material
+    /// references and ignored primitives.
+    ///
+    /// [`Loader`](crate::Loader) only keeps them if
+    /// [`collect_warnings`](crate::Loader::collect_warnings) is enabled.
+    pub warnings: Vec<crate::Warning>,
+}
+
+impl Scene {
+    /// Converts the scene to the given coordinate system, assuming that
+    /// vertices of meshes are in world space.
+    ///
+    /// This does nothing if the coordinate system of the scene is unknown.
+    pub(crate) fn convert_coordinate_system(&mut self, to: CoordinateSystem) {
+        let r = match self.coordinate_system {
+            Some(from) if from != to => from.rotation_to(to),
+            _ => return,
+        };
+        let rotate = |v: &mut Vec3| {
+            let [x, y, z] = *v;
+            *v = r.map(|row| row[0] * x + row[1] * y + row[2] * z);
+        };
+        for mesh in &mut self.meshes {
+            mesh.vertices.iter_mut().for_each(rotate);
+            mesh.normals.iter_mut().for_each(rotate);
+        }
+        // Offset matrices transform the rotated vertices back before applying
+        // the bone transforms: offset * R^-1 (R^-1 = R^T for rotations).
+        let rotate_offset = |m: &mut Mat4| {
+            let old = *m;
+            for (j, col) in m.iter_mut().enumerate().take(3) {
+                for (i, x) in col.iter_mut().enumerate() {
+                    *x = (0..3).map(|k| old[k][i] * r[j][k]).sum();
+                }
+            }
+        };
+        for mesh in &mut self.meshes {
+            for bone in &mut mesh.bones {
+                rotate_offset(&mut bone.offset_matrix);
+            }
+        }
+        for node in self.nodes.iter_mut().filter(|n| n.parent.is_none()) {
+            for col in &mut node.transform {
+                let mut v = [col[0], col[1], col[2]];
+                rotate(&mut v);
+                col[..3].copy_from_slice(&v);
+            }
+            if !node.transforms.is_empty() {
+                let values = (0..16)
+                    .map(|i| match (i / 4, i % 4) {
+                        (col, row) if col < 3 && row < 3 => r[row][col],
+                        (3, 3) => 1.,
+                        _ => 0.,
+                    })
+                    .collect();
+                node.transforms.insert(
+                    0,
+                    NodeTransform {
+                        sid: None,
+                        kind: NodeTransformKind::Matrix,
+                        values,
+                    },
+                );
+            }
+        }
+        self.coordinate_system = Some(to);
+    }
+}
+
+/// Node in the scene hierarchy.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Node {
+    pub name: String,
+    /// Index of the parent node in `Scene::nodes`, or `None` if this is a root node.
+    pub parent: Option<usize>,
+    /// Transform relative to the parent node, as a column-major 4x4 matrix.
+    pub transform: Mat4,
+    /// Transform elements that `transform` is composed of, in the order they
+    /// appear in the file (i.e., `transform` is the product of them from left
+    /// to right).
+    ///
+    /// Animation channels refer to them by [`NodeTransform::sid`]. This is
+    /// empty if the format has no such elements.
+    pub transforms: Vec<NodeTransform>,
+    /// Indices of meshes in `Scene::meshes` that are instantiated by this node.
+    pub meshes: Vec<usize>,
+}
+
+impl Default for Node {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            parent: None,
+            transform: [
+                [1., 0., 0., 0.],
+                [0., 1., 0., 0.],
+                [0., 0., 1., 0.],
+                [0., 0., 0., 1.],
+            ],
+            transforms: vec![],
+            meshes: vec![],
+        }
+    }
+}
+
+/// Transform element of a node.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct NodeTransform {
+    /// Scoped identifier (SID) of the transform element, e.g., `translate`.
+    pub sid: Option<String>,
+    pub kind: NodeTransformKind,
+    /// Values of the transform element, in the same layout as the values of
+    /// an animation channel that targets the whole element.
+    ///
+    /// For example, this is (x, y, z) for [`NodeTransformKind::Translate`],
+    /// and (axis x, axis y, axis z, angle in degrees) for
+    /// [`NodeTransformKind::Rotate`]. Matrices are stored as column-major 4x4
+    /// matrices.
+    pub values: Vec<f32>,
+}
+
+/// Kind of [`NodeTransform`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NodeTransformKind {
+    /// Eye position, interest position, and up vector.
+    LookAt,
+    Matrix,
+    Rotate,
+    Scale,
+    /// Angle in degrees, rotation axis, and translation axis.
+    Skew,
+    Translate,
+}
+
+/// Direction of a coordinate axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+    NegX,
+    NegY,
+    NegZ,
+}
+
+impl Axis {
+    const fn to_vec3(self) -> Vec3 {
+        match self {
+            Self::X => [1., 0., 0.],
+            Self::Y => [0., 1., 0.],
+            Self::Z => [0., 0., 1.],
+            Self::NegX => [-1., 0., 0.],
+            Self::NegY => [0., -1., 0.],
+            Self::NegZ => [0., 0., -1.],
+        }
+    }
+}
+
+/// Right-handed coordinate system, described by its up and forward axes.
+///
+/// The forward axis is the direction the front of a model faces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoordinateSystem {
+    up: Axis,
+    forward: Axis,
+}
+
+impl CoordinateSystem {
+    /// +Y up and +Z forward, used by glTF and COLLADA (`Y_UP`).
+    pub const Y_UP: Self = Self {
+        up: Axis::Y,
+        forward: Axis::Z,
+    };
+    /// +Z up and -Y forward, used by Blender and COLLADA (`Z_UP`).
+    pub const Z_UP: Self = Self {
+        up: Axis::Z,
+        forward: Axis::NegY,
+    };
+    /// +X up and +Z forward, used by COLLADA (`X_UP`).
+    pub const X_UP: Self = Self {
+        up: Axis::X,
+        forward: Axis::Z,
+    };
+    /// +Z up and +X forward, used by ROS ([REP 103]).
+    ///
+    /// [REP 103]: https://www.ros.org/reps/rep-0103.html
+    pub const ROS: Self = Self {
+        up: Axis::Z,
+        forward: Axis::X,
+    };
+
+    /// Creates a coordinate system with the given up and forward axes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `up` and `forward` are parallel.
+    #[must_use]
+    pub fn new(up: Axis, forward: Axis) -> Self {
+        let [u, f] = [up.to_vec3(), forward.to_vec3()];
+        assert!(
+            u[0] * f[0] + u[1] * f[1] + u[2] * f[2] == 0.,
+            "up axis ({up:?}) and forward axis ({forward:?}) must not be parallel"
+        );
+        Self { up, forward }
+    }
+
+    #[must_use]
+    pub const fn up(self) -> Axis {
+        self.up
+    }
+
+    #[must_use]
+    pub const fn forward(self) -> Axis {
+        self.forward
+    }
+
+    /// Returns the rotation matrix (row-major) that converts vectors in this
+    /// coordinate system to the `to` coordinate system.
+    pub(crate) fn rotation_to(self, to: Self) -> [Vec3; 3] {
+        // Columns of the basis matrices are (up, forward, up x forward).
+        fn basis(c: CoordinateSystem) -> [Vec3; 3] {
+            let [u, f] = [c.up.to_vec3(), c.forward.to_vec3()];
+            let l = [
+                u[1] * f[2] - u[2] * f[1],
+                u[2] * f[0] - u[0] * f[2],
+                u[0] * f[1] - u[1] * f[0],
+            ];
+            [u, f, l]
+        }
+        // R = to * from^T
+        let (from, to) = (basis(self), basis(to));
+        let mut r = [[0.; 3]; 3];
+        for (i, row) in r.iter_mut().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x = (0..3).map(|k| to[k][i] * from[k][j]).sum();
+            }
+        }
+        r
+    }
 }
 
-/// Triangle mesh
+/// Mesh of triangles, lines, and points.
 #[derive(Clone, Default)]
 #[non_exhaustive]
 pub struct Mesh {
     pub name: String,
     pub vertices: Vec<Vec3>,
+    /// Texture coordinate sets, indexed by set number.
+    ///
+    /// Sets that the mesh does not have are empty, and sets after the last
+    /// set that the mesh has may be omitted, so use `texcoords.get(set)`
+    /// rather than indexing.
     // TODO: use Vec3?
-    pub texcoords: [Vec<Vec2>; MAX_NUMBER_OF_TEXCOORDS],
+    pub texcoords: Vec<Vec<Vec2>>,
     pub normals: Vec<Vec3>,
     pub faces: Vec<Face>,
-    pub colors: [Vec<Color4>; MAX_NUMBER_OF_COLOR_SETS],
+    /// Indices into `vertices` of polygons with more than three vertices.
+    ///
+    /// This is empty unless the mesh is loaded with
+    /// [`Loader::keep_polygons`](crate::Loader::keep_polygons). Use
+    /// [`polygons`](Self::polygons) to iterate over the polygons.
+    pub polygon_indices: Vec<u32>,
+    /// The start of each polygon in `polygon_indices`. Each polygon ends at
+    /// the start of the next polygon, or at the end of `polygon_indices`.
+    pub polygon_offsets: Vec<u32>,
+    /// Line segments. Each line is a pair of indices into `vertices`.
+    pub lines: Vec<[u32; 2]>,
+    /// Points. Each point is an index into `vertices`.
+    pub points: Vec<u32>,
+    /// Vertex color sets, indexed by set number.
+    ///
+    /// Like `texcoords`, sets that the mesh does not have are empty or omitted.
+    pub colors: Vec<Vec<Color4>>,
+    /// Bones that deform this mesh. This is empty if the mesh is not skinned.
+    pub bones: Vec<Bone>,
     #[cfg(feature = "obj")]
     pub(crate) material_index: u32,
 }
@@ -43,46 +324,289 @@ impl Mesh {
         let num_vertices = meshes.iter().map(|m| m.vertices.len()).sum();
         let mut vertices = Vec::with_capacity(num_vertices);
         let mut normals = Vec::with_capacity(num_vertices);
-        // TODO: fill with default if one or more meshes has colors
-        let has_colors0 = num_vertices == meshes.iter().map(|m| m.colors[0].len()).sum();
-        let mut colors0 = Vec::with_capacity(if has_colors0 { num_vertices } else { 0 });
-        let has_colors1 = num_vertices == meshes.iter().map(|m| m.colors[1].len()).sum();
-        let mut colors1 = Vec::with_capacity(if has_colors1 { num_vertices } else { 0 });
         for m in &meshes {
             vertices.extend_from_slice(&m.vertices);
             normals.extend_from_slice(&m.normals);
-            if has_colors0 {
-                colors0.extend_from_slice(&m.colors[0]);
+        }
+        // TODO: fill with default if one or more meshes has texcoords/colors
+        let num_texcoords = meshes.iter().map(|m| m.texcoords.len()).max();
+        let mut texcoords: Vec<Vec<Vec2>> = vec![vec![]; num_texcoords.unwrap_or_default()];
+        for (set, texcoords) in texcoords.iter_mut().enumerate() {
+            let set_len = |m: &Self| m.texcoords.get(set).map_or(0, Vec::len);
+            if num_vertices == meshes.iter().map(set_len).sum() {
+                *texcoords = meshes
+                    .iter()
+                    .flat_map(|m| &m.texcoords[set])
+                    .copied()
+                    .collect();
             }
-            if has_colors1 {
-                colors1.extend_from_slice(&m.colors[1]);
+        }
+        let num_colors = meshes.iter().map(|m| m.colors.len()).max();
+        let mut colors: Vec<Vec<Color4>> = vec![vec![]; num_colors.unwrap_or_default()];
+        for (set, colors) in colors.iter_mut().enumerate() {
+            let set_len = |m: &Self| m.colors.get(set).map_or(0, Vec::len);
+            if num_vertices == meshes.iter().map(set_len).sum() {
+                *colors = meshes
+                    .iter()
+                    .flat_map(|m| &m.colors[set])
+                    .copied()
+                    .collect();
             }
         }
         let mut faces = Vec::with_capacity(meshes.iter().map(|m| m.faces.len()).sum());
+        let mut polygon_indices =
+            Vec::with_capacity(meshes.iter().map(|m| m.polygon_indices.len()).sum());
+        let mut polygon_offsets =
+            Vec::with_capacity(meshes.iter().map(|m| m.polygon_offsets.len()).sum());
+        let mut lines = Vec::with_capacity(meshes.iter().map(|m| m.lines.len()).sum());
+        let mut points = Vec::with_capacity(meshes.iter().map(|m| m.points.len()).sum());
+        let mut bones = Vec::with_capacity(meshes.iter().map(|m| m.bones.len()).sum());
         let mut last = 0;
-        for m in &meshes {
-            if m.faces.is_empty() {
-                continue;
-            }
+        for m in &mut meshes {
             faces.extend(
                 m.faces
                     .iter()
                     .map(|f| [f[0] + last, f[1] + last, f[2] + last]),
             );
-            last = m.faces.last().unwrap()[2] + 1;
+            #[allow(clippy::cast_possible_truncation)]
+            let polygon_offset = polygon_indices.len() as u32;
+            polygon_offsets.extend(m.polygon_offsets.iter().map(|o| o + polygon_offset));
+            polygon_indices.extend(m.polygon_indices.iter().map(|i| i + last));
+            lines.extend(m.lines.iter().map(|l| [l[0] + last, l[1] + last]));
+            points.extend(m.points.iter().map(|p| p + last));
+            bones.extend(m.bones.drain(..).map(|mut b| {
+                for w in &mut b.weights {
+                    w.vertex += last;
+                }
+                b
+            }));
+            #[allow(clippy::cast_possible_truncation)]
+            {
+                last += m.vertices.len() as u32;
+            }
         }
 
         Self {
             name: String::new(),
             vertices,
-            texcoords: Default::default(), // TODO
+            texcoords,
             normals,
             faces,
-            colors: [colors0, colors1],
+            polygon_indices,
+            polygon_offsets,
+            lines,
+            points,
+            colors,
+            bones,
             #[cfg(feature = "obj")]
             material_index: u32::MAX,
         }
     }
+
+    /// Returns the given texture coordinate set, adding empty sets if the mesh
+    /// does not have it yet.
+    #[cfg(any(feature = "collada", feature = "obj"))]
+    pub(crate) fn texcoords_mut(&mut self, set: usize) -> &mut Vec<Vec2> {
+        set_mut(&mut self.texcoords, set)
+    }
+
+    /// Returns the given color set, adding empty sets if the mesh does not
+    /// have it yet.
+    #[cfg(any(feature = "collada", feature = "obj"))]
+    pub(crate) fn colors_mut(&mut self, set: usize) -> &mut Vec<Color4> {
+        set_mut(&mut self.colors, set)
+    }
+
+    /// Returns an iterator over the indices into `vertices` of each polygon
+    /// in `polygon_indices`.
+    pub fn polygons(&self) -> impl Iterator<Item = &[u32]> + '_ {
+        let ends = self
+            .polygon_offsets
+            .iter()
+            .skip(1)
+            .map(|&end| end as usize)
+            .chain(iter::once(self.polygon_indices.len()));
+        self.polygon_offsets
+            .iter()
+            .zip(ends)
+            .map(move |(&start, end)| {
+                self.polygon_indices
+                    .get(start as usize..end)
+                    .unwrap_or_default()
+            })
+    }
+
+    /// Triangulates the polygons in `polygon_indices`, and appends the
+    /// triangles to `faces`.
+    ///
+    /// Unlike a triangle fan, this handles concave polygons correctly: the
+    /// polygons are projected onto the plane that they are closest to and
+    /// triangulated by ear clipping. The triangles share the vertices of the
+    /// polygons, and polygons that refer to non-existent vertices are
+    /// discarded.
+    pub fn triangulate_polygons(&mut self) {
+        let mut positions = vec![];
+        let mut triangles = vec![];
+        let mut faces = vec![];
+        for polygon in self.polygons() {
+            positions.clear();
+            positions.extend(
+                polygon
+                    .iter()
+                    .map_while(|&i| self.vertices.get(i as usize).copied()),
+            );
+            if positions.len() != polygon.len() {
+                continue;
+            }
+            triangles.clear();
+            utils::triangulate::triangulate(&positions, &mut triangles);
+            faces.extend(
+                triangles
+                    .iter()
+                    .map(|t| [polygon[t[0]], polygon[t[1]], polygon[t[2]]]),
+            );
+        }
+        self.faces.append(&mut faces);
+        self.polygon_indices.clear();
+        self.polygon_offsets.clear();
+    }
+
+    /// Merges vertices that have the same position, normal, texture
+    /// coordinates, colors, and bone weights, and updates `faces`,
+    /// `polygon_indices`, `lines`, `points`, and the weights of `bones` to
+    /// refer to the merged vertices.
+    ///
+    /// Formats such as OBJ and COLLADA index each attribute separately, and
+    /// vertices of loaded meshes are not shared between faces. This restores
+    /// the shared vertices of the original file.
+    ///
+    /// This does nothing if the lengths of vertex attributes do not match the
+    /// number of vertices, or if primitives refer to non-existent vertices.
+    pub fn weld_vertices(&mut self) {
+        let num_vertices = self.vertices.len();
+        let mut attribute_lens = iter::once(self.normals.len())
+            .chain(self.texcoords.iter().map(Vec::len))
+            .chain(self.colors.iter().map(Vec::len));
+        let in_bounds = u32::try_from(num_vertices).is_ok()
+            && self
+                .faces
+                .iter()
+                .flatten()
+                .chain(&self.polygon_indices)
+                .chain(self.lines.iter().flatten())
+                .chain(&self.points)
+                .all(|&i| (i as usize) < num_vertices)
+            && self
+                .bones
+                .iter()
+                .flat_map(|b| &b.weights)
+                .all(|w| (w.vertex as usize) < num_vertices);
+        if !in_bounds || attribute_lens.any(|n| n != 0 && n != num_vertices) {
+            return;
+        }
+
+        // Use the bit patterns of all attributes of each vertex as the key.
+        let mut bone_weights = vec![
+            vec![];
+            if self.bones.is_empty() {
+                0
+            } else {
+                num_vertices
+            }
+        ];
+        #[allow(clippy::cast_possible_truncation)]
+        for (bone, b) in self.bones.iter().enumerate() {
+            for w in &b.weights {
+                bone_weights[w.vertex as usize]
+                    .extend_from_slice(&[bone as u32, w.weight.to_bits()]);
+            }
+        }
+        let mut keys = vec![];
+        let mut key_ends = Vec::with_capacity(num_vertices);
+        for i in 0..num_vertices {
+            keys.extend(self.vertices[i].iter().map(|f| f.to_bits()));
+            if let Some(normal) = self.normals.get(i) {
+                keys.extend(normal.iter().map(|f| f.to_bits()));
+            }
+            for texcoords in &self.texcoords {
+                if let Some(texcoord) = texcoords.get(i) {
+                    keys.extend(texcoord.iter().map(|f| f.to_bits()));
+                }
+            }
+            for colors in &self.colors {
+                if let Some(color) = colors.get(i) {
+                    keys.extend(color.iter().map(|f| f.to_bits()));
+                }
+            }
+            if let Some(bone_weights) = bone_weights.get(i) {
+                keys.extend_from_slice(bone_weights);
+            }
+            key_ends.push(keys.len());
+        }
+
+        let mut map = HashMap::with_capacity(num_vertices);
+        // The index of the merged vertex for each vertex.
+        let mut remap = Vec::with_capacity(num_vertices);
+        // The index of the original vertex for each merged vertex.
+        let mut kept = vec![];
+        let mut start = 0;
+        for (i, &end) in key_ends.iter().enumerate() {
+            #[allow(clippy::cast_possible_truncation)]
+            let index = *map.entry(&keys[start..end]).or_insert_with(|| {
+                kept.push(i);
+                (kept.len() - 1) as u32
+            });
+            remap.push(index);
+            start = end;
+        }
+        if kept.len() == num_vertices {
+            return;
+        }
+
+        retain_vertices(&mut self.vertices, &kept);
+        retain_vertices(&mut self.normals, &kept);
+        for texcoords in &mut self.texcoords {
+            retain_vertices(texcoords, &kept);
+        }
+        for colors in &mut self.colors {
+            retain_vertices(colors, &kept);
+        }
+        for i in self
+            .faces
+            .iter_mut()
+            .flatten()
+            .chain(&mut self.polygon_indices)
+            .chain(self.lines.iter_mut().flatten())
+            .chain(&mut self.points)
+        {
+            *i = remap[*i as usize];
+        }
+        for bone in &mut self.bones {
+            // Merged vertices have the same weights, so keep only the weights
+            // of the vertices that remain.
+            bone.weights.retain(|w| {
+                let index = remap[w.vertex as usize];
+                kept[index as usize] == w.vertex as usize
+            });
+            for w in &mut bone.weights {
+                w.vertex = remap[w.vertex as usize];
+            }
+        }
+    }
+}
+
+fn retain_vertices<T: Copy>(v: &mut Vec<T>, kept: &[usize]) {
+    if !v.is_empty() {
+        *v = kept.iter().map(|&i| v[i]).collect();
+    }
+}
+
+#[cfg(any(feature = "collada", feature = "obj"))]
+fn set_mut<T>(sets: &mut Vec<Vec<T>>, set: usize) -> &mut Vec<T> {
+    if sets.len() <= set {
+        sets.resize_with(set + 1, Vec::new);
+    }
+    &mut sets[set]
 }
 
 impl fmt::Debug for Mesh {
@@ -90,16 +614,102 @@ impl fmt::Debug for Mesh {
         f.debug_struct("Mesh")
             .field("name", &self.name)
             .field("num_vertices", &self.vertices.len())
-            .field("num_texcoords0", &self.texcoords[0].len())
-            .field("num_texcoords1", &self.texcoords[1].len())
+            .field(
+                "num_texcoords",
+                &self.texcoords.iter().map(Vec::len).collect::<Vec<_>>(),
+            )
             .field("num_normals", &self.normals.len())
             .field("num_faces", &self.faces.len())
-            .field("num_colors0", &self.colors[0].len())
-            .field("num_colors1", &self.colors[1].len())
+            .field("num_polygons", &self.polygon_offsets.len())
+            .field("num_lines", &self.lines.len())
+            .field("num_points", &self.points.len())
+            .field(
+                "num_colors",
+                &self.colors.iter().map(Vec::len).collect::<Vec<_>>(),
+            )
+            .field("num_bones", &self.bones.len())
             .finish_non_exhaustive()
     }
 }
 
+/// Bone of a skinned mesh.
+///
+/// Vertices of a skinned mesh are in the bind pose, and they are not
+/// affected by the transform of the node that instantiates the mesh. The
+/// deformed position of a vertex is the sum of
+/// `weight * (world transform of the bone's node) * offset_matrix * vertex`
+/// for all bones that affect the vertex.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Bone {
+    pub name: String,
+    /// Index of the node in `Scene::nodes` that this bone refers to.
+    pub node: Option<usize>,
+    /// Matrix that transforms from mesh space to bone space in bind pose
+    /// (i.e., the inverse bind matrix), as a column-major 4x4 matrix.
+    pub offset_matrix: Mat4,
+    /// Vertices affected by this bone.
+    pub weights: Vec<VertexWeight>,
+}
+
+/// Influence of a bone on a vertex.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct VertexWeight {
+    /// Index of the vertex in `Mesh::vertices`.
+    pub vertex: u32,
+    pub weight: f32,
+}
+
+/// Keyframe animation.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct Animation {
+    pub name: String,
+    pub channels: Vec<AnimationChannel>,
+}
+
+/// Keyframe track that animates a transform of a node.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct AnimationChannel {
+    /// Index of the animated node in `Scene::nodes`.
+    pub node: usize,
+    /// Path of the animated value relative to the node, e.g., `translate.X`,
+    /// `rotateZ.ANGLE`, or `transform`.
+    ///
+    /// The path refers to a transform element in [`Node::transforms`] by its
+    /// scoped identifier (SID), optionally followed by a member selection.
+    pub target: String,
+    /// Time of each keyframe in seconds.
+    pub times: Vec<f32>,
+    /// Value of each keyframe, `stride` values per keyframe.
+    ///
+    /// Matrices are stored as column-major 4x4 matrices.
+    pub values: Vec<f32>,
+    /// Number of values per keyframe.
+    pub stride: usize,
+    /// Interpolation between each keyframe and the next.
+    pub interpolations: Vec<Interpolation>,
+    /// Control points before each keyframe for [`Interpolation::Bezier`], as
+    /// (time, value) pairs for each value of the keyframe.
+    pub in_tangents: Vec<f32>,
+    /// Control points after each keyframe for [`Interpolation::Bezier`], as
+    /// (time, value) pairs for each value of the keyframe.
+    pub out_tangents: Vec<f32>,
+}
+
+/// Interpolation between keyframes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Interpolation {
+    /// The value of the keyframe is kept until the next keyframe.
+    Step,
+    Linear,
+    /// Cubic Bezier curve defined by the tangents of the keyframes.
+    Bezier,
+}
+
 #[derive(Debug, Clone, Default)]
 #[non_exhaustive]
 pub struct Material {
@@ -131,17 +741,189 @@ pub struct Colors {
 #[derive(Debug, Clone, Default)]
 #[non_exhaustive]
 pub struct Textures {
-    pub diffuse: Option<PathBuf>,
-    pub specular: Option<PathBuf>,
-    pub ambient: Option<PathBuf>,
-    pub emissive: Option<PathBuf>,
-    pub height: Option<PathBuf>,
-    pub normal: Option<PathBuf>,
-    pub shininess: Option<PathBuf>,
-    pub opacity: Option<PathBuf>,
-    pub displacement: Option<PathBuf>,
-    pub lightmap: Option<PathBuf>,
-    pub reflection: Option<PathBuf>,
+    pub diffuse: Option<Texture>,
+    pub specular: Option<Texture>,
+    pub ambient: Option<Texture>,
+    pub emissive: Option<Texture>,
+    pub height: Option<Texture>,
+    pub normal: Option<Texture>,
+    pub shininess: Option<Texture>,
+    pub opacity: Option<Texture>,
+    pub displacement: Option<Texture>,
+    pub lightmap: Option<Texture>,
+    pub reflection: Option<Texture>,
+}
+
+/// Where the image of a texture is stored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TextureSource {
+    /// An external image file.
+    Path(PathBuf),
+    /// An image embedded in the file, as an index into
+    /// [`Scene::embedded_textures`].
+    Embedded(usize),
+}
+
+impl TextureSource {
+    /// Returns the path of the image file, or `None` if the image is embedded.
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Path(path) => Some(path),
+            Self::Embedded(_) => None,
+        }
+    }
+}
+
+impl From<PathBuf> for TextureSource {
+    fn from(path: PathBuf) -> Self {
+        Self::Path(path)
+    }
+}
+
+/// A texture of a material.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Texture {
+    pub source: TextureSource,
+    pub options: TextureOptions,
+}
+
+impl Texture {
+    /// Creates a new texture with the default options.
+    #[must_use]
+    pub fn new(source: TextureSource) -> Self {
+        Self {
+            source,
+            options: TextureOptions::default(),
+        }
+    }
+
+    /// Returns the path of the image file, or `None` if the image is embedded.
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        self.source.path()
+    }
+}
+
+impl From<TextureSource> for Texture {
+    fn from(source: TextureSource) -> Self {
+        Self::new(source)
+    }
+}
+
+/// Options of how a texture is mapped, such as the options of MTL texture
+/// map statements (`map_Kd -o 0.5 0.5 texture.png`).
+///
+/// Formats that do not have texture options use the default options.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct TextureOptions {
+    /// Offset of texture coordinates (`-o` in MTL).
+    ///
+    /// Default: `[0., 0., 0.]`
+    pub offset: [f32; 3],
+    /// Scale of texture coordinates (`-s` in MTL).
+    ///
+    /// Default: `[1., 1., 1.]`
+    pub scale: [f32; 3],
+    /// Turbulence of texture coordinates (`-t` in MTL).
+    ///
+    /// Default: `[0., 0., 0.]`
+    pub turbulence: [f32; 3],
+    /// Multiplier of the values of bump textures (`-bm` in MTL).
+    ///
+    /// Default: `1.`
+    pub bump_multiplier: f32,
+    /// Whether or not texture coordinates are clamped to `0..=1` instead of
+    /// repeating the texture (`-clamp` in MTL).
+    ///
+    /// Default: `false`
+    pub clamp: bool,
+    /// Whether or not to blend the texture horizontally (`-blendu` in MTL).
+    ///
+    /// Default: `true`
+    pub blend_u: bool,
+    /// Whether or not to blend the texture vertically (`-blendv` in MTL).
+    ///
+    /// Default: `true`
+    pub blend_v: bool,
+    /// Whether or not to apply color correction (`-cc` in MTL).
+    ///
+    /// Default: `false`
+    pub color_correction: bool,
+    /// Base value and gain of texture values (`-mm` in MTL).
+    ///
+    /// Default: `[0., 1.]`
+    pub base_gain: [f32; 2],
+    /// The channel of the image used for scalar textures, such as bump and
+    /// opacity textures (`-imfchan` in MTL).
+    ///
+    /// Default: `None` (the default channel of the texture type)
+    pub channel: Option<TextureChannel>,
+    /// Resolution of the texture to create (`-texres` in MTL).
+    ///
+    /// Default: `None`
+    pub resolution: Option<u32>,
+    /// Sharpness boost of mip-mapped textures (`-boost` in MTL).
+    ///
+    /// Default: `None`
+    pub boost: Option<f32>,
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        Self {
+            offset: [0.; 3],
+            scale: [1.; 3],
+            turbulence: [0.; 3],
+            bump_multiplier: 1.,
+            clamp: false,
+            blend_u: true,
+            blend_v: true,
+            color_correction: false,
+            base_gain: [0., 1.],
+            channel: None,
+            resolution: None,
+            boost: None,
+        }
+    }
+}
+
+/// A channel of a texture image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TextureChannel {
+    Red,
+    Green,
+    Blue,
+    Matte,
+    Luminance,
+    Depth,
+}
+
+/// A texture image embedded in the file.
+#[derive(Clone, Default)]
+#[non_exhaustive]
+pub struct EmbeddedTexture {
+    /// The name of the image in the file, or an empty string if it has no name.
+    pub name: String,
+    /// The encoded image data, such as the contents of a PNG or JPEG file.
+    pub data: Vec<u8>,
+    /// The image format, such as a file extension (`png`) or a MIME type
+    /// (`image/png`), or `None` if it is unknown.
+    pub format_hint: Option<String>,
+}
+
+impl fmt::Debug for EmbeddedTexture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EmbeddedTexture")
+            .field("name", &self.name)
+            .field("data_len", &self.data.len())
+            .field("format_hint", &self.format_hint)
+            .finish()
+    }
 }
 
 // Refs: https://github.com/assimp/assimp/blob/v5.3.1/include/assimp/material.h#L355
@@ -154,3 +936,111 @@ pub enum ShadingModel {
     Blinn,
     NoShading,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weld_vertices() {
+        // A quad with two triangles, each with its own vertices.
+        let mut mesh = Mesh {
+            vertices: vec![
+                [0., 0., 0.],
+                [1., 0., 0.],
+                [1., 1., 0.],
+                [0., 0., 0.],
+                [1., 1., 0.],
+                [0., 1., 0.],
+            ],
+            normals: vec![[0., 0., 1.]; 6],
+            faces: vec![[0, 1, 2], [3, 4, 5]],
+            bones: vec![Bone {
+                name: "bone".to_owned(),
+                node: None,
+                offset_matrix: Node::default().transform,
+                weights: [0, 2, 3, 4]
+                    .iter()
+                    .map(|&vertex| VertexWeight { vertex, weight: 1. })
+                    .collect(),
+            }],
+            ..Default::default()
+        };
+        mesh.weld_vertices();
+        assert_eq!(
+            mesh.vertices,
+            [[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 1., 0.]]
+        );
+        assert_eq!(mesh.normals.len(), 4);
+        assert_eq!(mesh.faces, [[0, 1, 2], [0, 2, 3]]);
+        let weights: Vec<_> = mesh.bones[0].weights.iter().map(|w| w.vertex).collect();
+        assert_eq!(weights, [0, 2]);
+
+        // Vertices with different attributes are not merged.
+        mesh.vertices.push([0., 0., 0.]);
+        mesh.normals.push([0., 0., -1.]);
+        mesh.faces.push([4, 1, 2]);
+        mesh.weld_vertices();
+        assert_eq!(mesh.vertices.len(), 5);
+        assert_eq!(mesh.faces, [[0, 1, 2], [0, 2, 3], [4, 1, 2]]);
+    }
+
+    #[test]
+    fn convert_coordinate_system() {
+        let mut scene = Scene {
+            coordinate_system: Some(CoordinateSystem::Z_UP),
+            ..Default::default()
+        };
+        let mut offset_matrix = Node::default().transform;
+        offset_matrix[3] = [1., 2., 3., 1.];
+        scene.meshes.push(Mesh {
+            vertices: vec![[1., 2., 3.]],
+            normals: vec![[0., 0., 1.]],
+            bones: vec![Bone {
+                name: "bone".to_owned(),
+                node: Some(0),
+                offset_matrix,
+                weights: vec![],
+            }],
+            ..Default::default()
+        });
+        let mut node = Node::default();
+        node.transform[3] = [1., 2., 3., 1.];
+        node.transforms.push(NodeTransform {
+            sid: Some("translate".to_owned()),
+            kind: NodeTransformKind::Translate,
+            values: vec![1., 2., 3.],
+        });
+        scene.nodes.push(node);
+
+        // Z-up, -Y forward -> Y-up, +Z forward: (x, y, z) -> (x, z, -y)
+        scene.convert_coordinate_system(CoordinateSystem::Y_UP);
+        assert_eq!(scene.coordinate_system, Some(CoordinateSystem::Y_UP));
+        let mesh = &scene.meshes[0];
+        assert_eq!(mesh.vertices, [[1., 3., -2.]]);
+        assert_eq!(mesh.normals, [[0., 1., 0.]]);
+        // The offset matrix maps the rotated vertex to the same bone space.
+        let m = mesh.bones[0].offset_matrix;
+        let v = mesh.vertices[0];
+        let bone_space: Vec<f32> = (0..3)
+            .map(|i| m[0][i] * v[0] + m[1][i] * v[1] + m[2][i] * v[2] + m[3][i])
+            .collect();
+        assert_eq!(bone_space, [2., 4., 6.]);
+        // The rotation is applied to the root node.
+        let node = &scene.nodes[0];
+        assert_eq!(node.transform[3], [1., 3., -2., 1.]);
+        assert_eq!(node.transforms.len(), 2);
+        assert_eq!(node.transforms[0].kind, NodeTransformKind::Matrix);
+        assert_eq!(node.transforms[1].sid.as_deref(), Some("translate"));
+
+        // Converting back restores the original values.
+        scene.convert_coordinate_system(CoordinateSystem::Z_UP);
+        assert_eq!(scene.meshes[0].vertices, [[1., 2., 3.]]);
+
+        // Scenes with unknown coordinate system are not converted.
+        scene.coordinate_system = None;
+        scene.convert_coordinate_system(CoordinateSystem::Y_UP);
+        assert_eq!(scene.meshes[0].vertices, [[1., 2., 3.]]);
+        assert_eq!(scene.coordinate_system, None);
+    }
+}
@@ -0,0 +1,357 @@
+use std::{ffi::OsStr, path::Path};
+
+use crate::Format;
+
+// Scores of evidence. The format with the highest total score is detected.
+/// The file starts with the magic number of the format.
+const MAGIC: u32 = 100;
+/// The contents are very likely in the format.
+const STRONG: u32 = 80;
+/// The contents are consistent with the format, but this can happen by chance.
+const WEAK: u32 = 40;
+/// The extension matches the format. This is enough on its own, but contents
+/// in another format take precedence over wrong extensions.
+const EXTENSION: u32 = 30;
+
+/// The number of bytes to look at when detecting formats from contents.
+const SNIFF_LEN: usize = 4096;
+
+const FORMATS: [Format; 7] = [
+    Format::Stl,
+    Format::Collada,
+    Format::Obj,
+    Format::Ply,
+    Format::Gltf,
+    Format::Zip,
+    Format::Gzip,
+];
+
+/// Detects the format of a file from its path and contents.
+///
+/// Each format is scored by how well the contents match it (magic numbers,
+/// the size of binary STL, statements of OBJ, the root element of XML, etc.)
+/// and by whether the extension matches it, so files with wrong or missing
+/// extensions are also detected. Pass an empty path if the path is unknown.
+///
+/// Returns `None` if the format is not recognized.
+///
+/// # Example
+///
+/// ```
+/// use mesh_loader::{detect_format, Format};
+///
+/// let obj = b"v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n";
+/// assert_eq!(detect_format("", obj), Some(Format::Obj));
+/// assert_eq!(detect_format("mesh.stl", obj), Some(Format::Obj));
+/// assert_eq!(detect_format("mesh.stl", b""), Some(Format::Stl));
+/// assert_eq!(detect_format("mesh", b""), None);
+/// ```
+pub fn detect_format<P: AsRef<Path>>(path: P, bytes: &[u8]) -> Option<Format> {
+    let extension = path.as_ref().extension().and_then(OsStr::to_str);
+    let mut best = None;
+    for format in FORMATS {
+        let mut score = content_score(format, bytes);
+        if extension.map_or(false, |ext| matches_extension(format, ext)) {
+            score += EXTENSION;
+        }
+        // Earlier formats win ties.
+        if score > 0 && best.map_or(true, |(_, best_score)| score > best_score) {
+            best = Some((format, score));
+        }
+    }
+    best.map(|(format, _)| format)
+}
+
+fn matches_extension(format: Format, ext: &str) -> bool {
+    let extensions: &[&str] = match format {
+        Format::Stl => &["stl"],
+        Format::Collada => &["dae"],
+        Format::Obj => &["obj"],
+        Format::Ply => &["ply"],
+        Format::Gltf => &["gltf", "glb"],
+        Format::Zip => &["zip", "zae", "3mf"],
+        Format::Gzip => &["gz"],
+    };
+    extensions.iter().any(|e| ext.eq_ignore_ascii_case(e))
+}
+
+fn content_score(format: Format, bytes: &[u8]) -> u32 {
+    match format {
+        Format::Stl => binary_stl_score(bytes).max(ascii_stl_score(bytes)),
+        Format::Collada => match xml_root(bytes) {
+            Some(root) if root == "COLLADA" => STRONG,
+            _ => 0,
+        },
+        Format::Obj => obj_score(bytes),
+        Format::Ply => {
+            if bytes.starts_with(b"ply\n") || bytes.starts_with(b"ply\r\n") {
+                MAGIC
+            } else {
+                0
+            }
+        }
+        Format::Gltf => {
+            if bytes.starts_with(b"glTF") {
+                MAGIC
+            } else if skip_whitespace(skip_bom(bytes)).starts_with(b"{")
+                && contains(&bytes[..bytes.len().min(SNIFF_LEN)], b"\"asset\"")
+            {
+                STRONG
+            } else {
+                0
+            }
+        }
+        Format::Zip => {
+            if bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(b"PK\x05\x06") {
+                MAGIC
+            } else {
+                0
+            }
+        }
+        Format::Gzip => {
+            if bytes.starts_with(b"\x1f\x8b\x08") {
+                MAGIC
+            } else {
+                0
+            }
+        }
+    }
+}
+
+fn binary_stl_score(bytes: &[u8]) -> u32 {
+    const HEADER_SIZE: usize = 80;
+    const TRIANGLE_START: usize = HEADER_SIZE + 4;
+    const TRIANGLE_SIZE: usize = 50;
+
+    if bytes.len() < TRIANGLE_START + TRIANGLE_SIZE {
+        return 0;
+    }
+    let count = u32::from_le_bytes(bytes[HEADER_SIZE..TRIANGLE_START].try_into().unwrap());
+    let mut len = bytes.len() - TRIANGLE_START;
+    // Some buggy STL files have a newline after triangles.
+    if bytes.ends_with(b"\r\n") && len % TRIANGLE_SIZE == 2 {
+        len -= 2;
+    } else if bytes.ends_with(b"\n") && len % TRIANGLE_SIZE == 1 {
+        len -= 1;
+    }
+    if len % TRIANGLE_SIZE != 0 {
+        return 0;
+    }
+    // Triangles are mostly floats, so they are unlikely to be text.
+    let triangles = &bytes[TRIANGLE_START..TRIANGLE_START + len.min(SNIFF_LEN)];
+    if triangles
+        .iter()
+        .all(|&b| b.is_ascii_graphic() || b.is_ascii_whitespace())
+    {
+        return 0;
+    }
+    if count as usize == len / TRIANGLE_SIZE {
+        STRONG
+    } else {
+        // Some STL files contain bogus count.
+        WEAK
+    }
+}
+
+fn ascii_stl_score(bytes: &[u8]) -> u32 {
+    let s = skip_whitespace(bytes);
+    if !s.starts_with(b"solid") {
+        return 0;
+    }
+    let s = &s[..s.len().min(SNIFF_LEN)];
+    if contains(s, b"facet") || contains(s, b"endsolid") {
+        STRONG
+    } else {
+        WEAK
+    }
+}
+
+fn obj_score(bytes: &[u8]) -> u32 {
+    const KEYWORDS: &[&[u8]] = &[
+        b"v", b"vt", b"vn", b"vp", b"f", b"l", b"p", b"o", b"g", b"s", b"mg", b"usemtl", b"mtllib",
+        b"cstype", b"deg", b"curv", b"curv2", b"surf", b"parm", b"trim", b"hole", b"end", b"bmat",
+        b"step",
+    ];
+
+    let s = skip_bom(bytes);
+    let mut s = &s[..s.len().min(SNIFF_LEN)];
+    if s.len() < bytes.len() {
+        // Ignore the last line, which may be cut off.
+        if let Some(end) = s.iter().rposition(|&b| b == b'\n') {
+            s = &s[..end];
+        }
+    }
+    let (mut statements, mut unknown, mut elements) = (0, 0, 0);
+    for line in s.split(|&b| b == b'\n') {
+        let line = skip_whitespace(line);
+        if line.is_empty() || line.starts_with(b"#") {
+            continue;
+        }
+        let keyword = line
+            .split(u8::is_ascii_whitespace)
+            .next()
+            .unwrap_or_default();
+        if KEYWORDS.contains(&keyword) {
+            statements += 1;
+            if matches!(keyword, b"v" | b"f") {
+                elements += 1;
+            }
+        } else {
+            unknown += 1;
+        }
+    }
+    // Most lines must be OBJ statements, and vertices or faces must appear.
+    if elements == 0 || unknown * 10 > statements {
+        0
+    } else if statements >= 3 && unknown == 0 {
+        STRONG
+    } else {
+        WEAK
+    }
+}
+
+/// Returns the name of the root element of XML, without the namespace prefix.
+fn xml_root(bytes: &[u8]) -> Option<String> {
+    let bytes = &bytes[..bytes.len().min(SNIFF_LEN)];
+    // Take the low bytes of UTF-16, which is enough to find ASCII names.
+    let decoded: Vec<u8>;
+    let mut s = match bytes {
+        [0xFF, 0xFE, rest @ ..] => {
+            decoded = rest.chunks(2).map(|c| c[0]).collect();
+            &decoded[..]
+        }
+        [0xFE, 0xFF, rest @ ..] => {
+            decoded = rest.chunks_exact(2).map(|c| c[1]).collect();
+            &decoded[..]
+        }
+        _ => skip_bom(bytes),
+    };
+    loop {
+        s = skip_whitespace(s);
+        let (open, close): (&[u8], &[u8]) = if s.starts_with(b"<?") {
+            (b"<?", b"?>")
+        } else if s.starts_with(b"<!--") {
+            (b"<!--", b"-->")
+        } else if s.starts_with(b"<!") {
+            (b"<!", b">")
+        } else {
+            break;
+        };
+        let end = find(&s[open.len()..], close)?;
+        s = &s[open.len() + end + close.len()..];
+    }
+    let s = s.strip_prefix(b"<")?;
+    let len = s
+        .iter()
+        .position(|&b| b.is_ascii_whitespace() || b == b'>' || b == b'/')?;
+    let name = &s[..len];
+    let name = match name.iter().position(|&b| b == b':') {
+        Some(i) => &name[i + 1..],
+        None => name,
+    };
+    String::from_utf8(name.to_vec()).ok()
+}
+
+fn skip_bom(bytes: &[u8]) -> &[u8] {
+    bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes)
+}
+
+fn skip_whitespace(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+fn find(s: &[u8], needle: &[u8]) -> Option<usize> {
+    s.windows(needle.len()).position(|w| w == needle)
+}
+
+fn contains(s: &[u8], needle: &[u8]) -> bool {
+    find(s, needle).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect() {
+        let mut binary_stl = vec![0; 84 + 50 * 2];
+        binary_stl[..5].copy_from_slice(b"solid");
+        binary_stl[80] = 2;
+        binary_stl[84 + 12..84 + 16].copy_from_slice(&1_f32.to_le_bytes());
+        let mut bogus_count = binary_stl.clone();
+        bogus_count[80] = 0;
+        let mut trailing_newline = binary_stl.clone();
+        trailing_newline.push(b'\n');
+        let mut truncated = bogus_count.clone();
+        truncated.pop();
+        truncated[..5].copy_from_slice(b"\0\0\0\0\0");
+        let cases: &[(&str, &[u8], Option<Format>)] = &[
+            // Extensions
+            ("a.STL", b"", Some(Format::Stl)),
+            ("a.dae", b"", Some(Format::Collada)),
+            ("a.obj", b"", Some(Format::Obj)),
+            ("a.ply", b"", Some(Format::Ply)),
+            ("a.glb", b"", Some(Format::Gltf)),
+            ("a.3mf", b"", Some(Format::Zip)),
+            ("a.stl.gz", b"", Some(Format::Gzip)),
+            ("a.txt", b"", None),
+            ("", b"", None),
+            // Magic numbers
+            ("a.stl", b"\x1f\x8b\x08\0", Some(Format::Gzip)),
+            ("", b"PK\x03\x04", Some(Format::Zip)),
+            ("", b"glTF\x02\0\0\0", Some(Format::Gltf)),
+            ("a.obj", b"ply\nformat ascii 1.0\n", Some(Format::Ply)),
+            // STL
+            ("", &binary_stl, Some(Format::Stl)),
+            ("a.obj", &binary_stl, Some(Format::Stl)),
+            ("", &bogus_count, Some(Format::Stl)),
+            ("", &trailing_newline, Some(Format::Stl)),
+            ("", &truncated, None),
+            ("", b"solid a\n  facet normal 0 0 1\n", Some(Format::Stl)),
+            ("a.obj", b"solid a\nendsolid a\n", Some(Format::Stl)),
+            // OBJ
+            (
+                "",
+                b"# comment\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n",
+                Some(Format::Obj),
+            ),
+            (
+                "a.stl",
+                b"o a\r\nv 0 0 0\r\nv 1 0 0\r\nf 1 2 3",
+                Some(Format::Obj),
+            ),
+            ("a.stl", b"foo\nv 0 0 0\n", Some(Format::Stl)),
+            ("", b"# comment only\n", None),
+            // XML and JSON
+            (
+                "",
+                b"\xEF\xBB\xBF<?xml version=\"1.0\"?>\n<!-- x -->\n<COLLADA version=\"1.4.1\">",
+                Some(Format::Collada),
+            ),
+            (
+                "",
+                b"\xFF\xFE<\0C\0O\0L\0L\0A\0D\0A\0 \0/\0>\0",
+                Some(Format::Collada),
+            ),
+            ("", b"<model unit=\"millimeter\">", None),
+            (
+                "",
+                b" {\"asset\":{\"version\":\"2.0\"}}",
+                Some(Format::Gltf),
+            ),
+            ("", b"{\"foo\": 1}", None),
+        ];
+        for &(path, bytes, expected) in cases {
+            assert_eq!(
+                detect_format(path, bytes),
+                expected,
+                "{path:?} {:?}",
+                String::from_utf8_lossy(bytes)
+            );
+        }
+    }
+}
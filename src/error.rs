@@ -1,12 +1,19 @@
-use std::io;
-#[cfg(any(feature = "obj", feature = "stl"))]
-use std::{fmt, path::Path};
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
 
-#[cfg(any(feature = "obj", feature = "stl"))]
+#[cfg(any(feature = "obj", feature = "ply", feature = "stl"))]
 use crate::utils::bytes::{bytecount_naive, memrchr_naive};
 
-#[cfg(feature = "collada")]
+#[cfg(any(feature = "collada", feature = "gltf", feature = "zip"))]
 macro_rules! format_err {
+    (ErrorKind::$kind:ident, $($tt:tt)*) => {
+        std::io::Error::from(crate::Error::new(crate::ErrorKind::$kind, format!($($tt)*)))
+    };
+    ($msg:literal $(,)?) => {
+        crate::error::invalid_data(format!($msg))
+    };
     ($msg:expr $(,)?) => {
         crate::error::invalid_data($msg)
     };
@@ -15,36 +22,331 @@ macro_rules! format_err {
     };
 }
 
-#[cfg(feature = "collada")]
+#[cfg(any(feature = "collada", feature = "gltf", feature = "zip"))]
 macro_rules! bail {
     ($($tt:tt)*) => {
         return Err(format_err!($($tt)*))
     };
 }
 
+/// A file format supported by this crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Format {
+    /// STL (.stl)
+    Stl,
+    /// COLLADA (.dae)
+    Collada,
+    /// Wavefront OBJ (.obj) and MTL (.mtl)
+    Obj,
+    /// PLY (.ply)
+    Ply,
+    /// glTF (.gltf) and binary glTF (.glb)
+    Gltf,
+    /// Zip archive (.zip), COLLADA archive (.zae), and 3MF (.3mf)
+    Zip,
+    /// gzip-compressed file (.gz)
+    Gzip,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Stl => "STL",
+            Self::Collada => "COLLADA",
+            Self::Obj => "OBJ",
+            Self::Ply => "PLY",
+            Self::Gltf => "glTF",
+            Self::Zip => "zip",
+            Self::Gzip => "gzip",
+        })
+    }
+}
+
+/// The kind of an [`Error`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A keyword, separator, or newline was expected but something else was
+    /// found.
+    Syntax,
+    /// Failed to parse a floating-point number.
+    InvalidFloat,
+    /// Failed to parse an integer.
+    InvalidInt,
+    /// A face refers to a vertex that does not exist.
+    FaceIndexOutOfBounds,
+    /// A face index is malformed or is inconsistent with other indices of
+    /// the face.
+    InvalidFaceIndex,
+    /// A required element, attribute, or property is missing.
+    MissingElement,
+    /// An element appeared where it is not allowed.
+    UnexpectedElement,
+    /// The input ended unexpectedly.
+    UnexpectedEof,
+    /// The size of the input or of a list is inconsistent with its contents.
+    InvalidSize,
+    /// The input contains more vertices or triangles than can be represented.
+    TooLarge,
+    /// The input uses a format version or feature that is not supported.
+    Unsupported,
+    /// Any other error.
+    Other,
+}
+
+/// An error that occurred while parsing a file.
+///
+/// Functions in this crate return [`io::Error`], and errors caused by invalid
+/// input wrap this type. Use [`Error::from_io_error`] to inspect them.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "ply")] {
+/// use mesh_loader::{Error, ErrorKind, Format};
+///
+/// let ply = b"ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nend_header\nz\n";
+/// let e = mesh_loader::ply::from_slice(ply).unwrap_err();
+/// let e = Error::from_io_error(&e).unwrap();
+/// assert_eq!(e.kind(), ErrorKind::InvalidFloat);
+/// assert_eq!(e.format(), Some(Format::Ply));
+/// assert_eq!(e.line(), Some(6));
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Error {
+    kind: ErrorKind,
+    message: String,
+    format: Option<Format>,
+    file: Option<PathBuf>,
+    position: Option<Position>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Position {
+    line: usize,
+    column: usize,
+    offset: usize,
+}
+
+impl Error {
+    #[cfg(any(
+        feature = "collada",
+        feature = "gltf",
+        feature = "obj",
+        feature = "ply",
+        feature = "gzip",
+        feature = "stl",
+        feature = "zip",
+    ))]
+    #[cold]
+    pub(crate) fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            format: None,
+            file: None,
+            position: None,
+        }
+    }
+
+    #[cfg(any(feature = "obj", feature = "ply", feature = "stl"))]
+    pub(crate) fn with_format(mut self, format: Format) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    #[cfg(any(feature = "obj", feature = "ply", feature = "stl"))]
+    pub(crate) fn with_file(mut self, file: Option<&Path>) -> Self {
+        self.file = file.filter(|&p| p != Path::new("")).map(Path::to_path_buf);
+        self
+    }
+
+    /// Sets the 1-based line and column and the 0-based byte offset.
+    #[cfg(any(
+        feature = "collada",
+        feature = "gltf",
+        feature = "obj",
+        feature = "ply",
+        feature = "stl",
+    ))]
+    pub(crate) fn with_position(mut self, line: usize, column: usize, offset: usize) -> Self {
+        self.position = Some(Position {
+            line,
+            column,
+            offset,
+        });
+        self
+    }
+
+    /// Returns the `Error` wrapped in the given [`io::Error`], if any.
+    pub fn from_io_error(e: &io::Error) -> Option<&Self> {
+        e.get_ref()?.downcast_ref()
+    }
+
+    /// Returns the kind of this error.
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Returns the error message without location.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the format of the file where this error occurred, if known.
+    pub fn format(&self) -> Option<Format> {
+        self.format
+    }
+
+    /// Returns the path of the file where this error occurred, if known.
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// Returns the 1-based line number where this error occurred, if known.
+    pub fn line(&self) -> Option<usize> {
+        self.position.map(|p| p.line)
+    }
+
+    /// Returns the 1-based column number where this error occurred, if known.
+    pub fn column(&self) -> Option<usize> {
+        self.position.map(|p| p.column)
+    }
+
+    /// Returns the 0-based byte offset where this error occurred, if known.
+    pub fn offset(&self) -> Option<usize> {
+        self.position.map(|p| p.offset)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        match (&self.file, self.position) {
+            (Some(file), Some(p)) => write!(f, " ({}:{}:{})", file.display(), p.line, p.column),
+            (None, Some(p)) => write!(f, " ({}:{})", p.line, p.column),
+            (Some(file), None) => write!(f, " ({})", file.display()),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Error> for io::Error {
+    #[cold]
+    fn from(e: Error) -> Self {
+        Self::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+/// The kind of a [`Warning`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum WarningKind {
+    /// A material library (e.g., `mtllib` in OBJ) could not be read.
+    MissingMaterialLibrary,
+    /// A referenced material or effect was not found.
+    MissingMaterial,
+    /// Primitives that are not supported were ignored.
+    IgnoredPrimitive,
+    /// A texture image file was not found.
+    ///
+    /// See [`TextureResolver`](crate::TextureResolver).
+    MissingTexture,
+}
+
+/// A non-fatal problem found while parsing a file.
+///
+/// Warnings are collected into [`Scene::warnings`](crate::Scene::warnings).
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub message: String,
+    /// Path of the file where this warning occurred, if known.
+    pub file: Option<PathBuf>,
+}
+
+impl Warning {
+    #[cfg(any(feature = "collada", feature = "gltf", feature = "obj"))]
+    #[cold]
+    pub(crate) fn new(kind: WarningKind, message: String, file: Option<&Path>) -> Self {
+        Self {
+            kind,
+            message,
+            file: file.filter(|&p| p != Path::new("")).map(Path::to_path_buf),
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        if let Some(file) = &self.file {
+            write!(f, " ({})", file.display())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(any(
+    feature = "collada",
+    feature = "gzip",
+    feature = "obj",
+    feature = "zip"
+))]
 #[cold]
 pub(crate) fn invalid_data(e: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
     let e = e.into();
-    let kind = e
-        .downcast_ref::<io::Error>()
-        .map_or(io::ErrorKind::InvalidData, io::Error::kind);
-    io::Error::new(kind, e)
+    match e.downcast::<io::Error>() {
+        Ok(e) => io::Error::new(e.kind(), e),
+        Err(e) => Error::new(ErrorKind::Other, e.to_string()).into(),
+    }
+}
+
+/// Returns the kind of the [`Error`] wrapped in `e`, or [`ErrorKind::Other`].
+#[cfg(feature = "collada")]
+pub(crate) fn kind(e: &io::Error) -> ErrorKind {
+    Error::from_io_error(e).map_or(ErrorKind::Other, Error::kind)
 }
 
-#[cfg(any(feature = "obj", feature = "stl"))]
+/// Returns an error that has the given message and the same kind as `e`.
+///
+/// This is used to add the location to an error returned by a helper.
+#[cfg(feature = "collada")]
 #[cold]
-pub(crate) fn with_location(e: &io::Error, location: &Location<'_>) -> io::Error {
-    io::Error::new(e.kind(), format!("{e} ({location})"))
+pub(crate) fn with_message(e: &io::Error, message: String) -> io::Error {
+    Error::new(kind(e), message).into()
 }
 
-#[cfg(any(feature = "obj", feature = "stl"))]
+/// Sets the format and the file of the [`Error`] wrapped in `e`, if they are
+/// not set yet.
+#[cfg(any(feature = "collada", feature = "gltf"))]
+#[cold]
+pub(crate) fn with_context(mut e: io::Error, format: Format, file: Option<&Path>) -> io::Error {
+    if let Some(inner) = e.get_mut().and_then(|e| e.downcast_mut::<Error>()) {
+        if inner.format.is_none() {
+            inner.format = Some(format);
+        }
+        if inner.file.is_none() {
+            inner.file = file.filter(|&p| p != Path::new("")).map(Path::to_path_buf);
+        }
+    }
+    e
+}
+
+#[cfg(any(feature = "obj", feature = "ply", feature = "stl"))]
 pub(crate) struct Location<'a> {
     file: Option<&'a Path>,
     line: usize,
     column: usize,
+    offset: usize,
 }
 
-#[cfg(any(feature = "obj", feature = "stl"))]
+#[cfg(any(feature = "obj", feature = "ply", feature = "stl"))]
 impl<'a> Location<'a> {
     #[cold]
     #[inline(never)]
@@ -53,20 +355,127 @@ impl<'a> Location<'a> {
         let line = bytecount_naive(b'\n', &start[..pos]) + 1;
         let column = memrchr_naive(b'\n', &start[..pos]).unwrap_or(pos) + 1;
         Self {
-            file: file.filter(|&p| p != Path::new("")),
+            file,
             line,
             column,
+            offset: pos,
         }
     }
+
+    /// Attaches this location to `e`.
+    #[cold]
+    pub(crate) fn attach(&self, e: Error) -> Error {
+        e.with_file(self.file)
+            .with_position(self.line, self.column, self.offset)
+    }
 }
 
-#[cfg(any(feature = "obj", feature = "stl"))]
-impl fmt::Display for Location<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if let Some(file) = self.file {
-            write!(f, "{}:{}:{}", file.display(), self.line, self.column)
-        } else {
-            write!(f, "{}:{}", self.line, self.column)
-        }
+#[cfg(all(
+    test,
+    any(
+        feature = "collada",
+        feature = "gltf",
+        feature = "obj",
+        feature = "ply"
+    ),
+))]
+mod tests {
+    use super::*;
+
+    #[cfg(any(
+        feature = "collada",
+        feature = "gltf",
+        feature = "obj",
+        feature = "ply"
+    ))]
+    #[track_caller]
+    fn error(e: &io::Error) -> &Error {
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        Error::from_io_error(e).unwrap()
+    }
+
+    #[cfg(feature = "obj")]
+    #[test]
+    fn obj() {
+        let e = crate::obj::from_slice::<Vec<u8>, _>(
+            b"v 0 0 0\nv 1 x 0\n",
+            Some(Path::new("a.obj")),
+            |_| unreachable!(),
+        )
+        .unwrap_err();
+        let e = error(&e);
+        assert_eq!(e.kind(), ErrorKind::InvalidFloat);
+        assert_eq!(e.format(), Some(Format::Obj));
+        assert_eq!(e.file(), Some(Path::new("a.obj")));
+        assert_eq!(
+            (e.line(), e.column(), e.offset()),
+            (Some(2), Some(5), Some(12))
+        );
+        assert_eq!(e.to_string(), format!("{} (a.obj:2:5)", e.message()));
+    }
+
+    #[cfg(feature = "ply")]
+    #[test]
+    fn ply() {
+        let ply = b"ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nproperty float y\n\
+            property float z\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n\
+            0 0 0\n3 0 1 2\n";
+        let e = crate::ply::from_slice(ply).unwrap_err();
+        let e = error(&e);
+        assert_eq!(e.kind(), ErrorKind::FaceIndexOutOfBounds);
+        assert_eq!(e.format(), Some(Format::Ply));
+        // The position of errors in the body is unknown.
+        assert_eq!((e.line(), e.column(), e.offset()), (None, None, None));
+        assert_eq!(e.to_string(), e.message());
+    }
+
+    #[cfg(feature = "collada")]
+    #[test]
+    fn collada() {
+        let dae = r#"<COLLADA version="1.4.1"><library_geometries><geometry id="g"><mesh>
+            <source id="p"><float_array id="a" count="3">0 x 0</float_array></source>
+            </mesh></geometry></library_geometries></COLLADA>"#;
+        let e = crate::collada::from_str(dae).unwrap_err();
+        let e = error(&e);
+        assert_eq!(e.kind(), ErrorKind::InvalidFloat);
+        assert_eq!(e.format(), Some(Format::Collada));
+        assert_eq!(e.file(), None);
+
+        let e =
+            crate::collada::from_str("<COLLADA version=\"1.4.1\"><a></b></COLLADA>").unwrap_err();
+        assert_eq!(error(&e).kind(), ErrorKind::Syntax);
+    }
+
+    #[cfg(feature = "gltf")]
+    #[test]
+    fn gltf() {
+        // A triangle that refers to a vertex that does not exist.
+        let gltf = br#"{
+            "asset": {"version": "2.0"},
+            "buffers": [{"byteLength": 48, "uri": "data:,%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00%00"}],
+            "bufferViews": [{"buffer": 0, "byteLength": 36}, {"buffer": 0, "byteOffset": 36, "byteLength": 12}],
+            "accessors": [
+                {"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"},
+                {"bufferView": 1, "componentType": 5125, "count": 3, "type": "SCALAR"}
+            ],
+            "meshes": [{"primitives": [{"attributes": {"POSITION": 0}, "indices": 1}]}]
+        }"#;
+        let mut gltf = gltf.to_vec();
+        let pos = gltf.windows(3).rposition(|w| w == b"%00").unwrap();
+        gltf[pos + 2] = b'3';
+        let e = crate::gltf::from_slice::<Vec<u8>, _>(
+            &gltf,
+            Some(Path::new("a.gltf")),
+            |_| unreachable!(),
+        )
+        .unwrap_err();
+        let e = error(&e);
+        assert_eq!(e.kind(), ErrorKind::FaceIndexOutOfBounds);
+        assert_eq!(e.format(), Some(Format::Gltf));
+        assert_eq!(e.file(), Some(Path::new("a.gltf")));
+
+        let e = crate::gltf::from_slice::<Vec<u8>, _>(b"{\"asset\": 1}", None, |_| unreachable!())
+            .unwrap_err();
+        assert_eq!(error(&e).kind(), ErrorKind::Syntax);
     }
 }
@@ -0,0 +1,305 @@
+// Refs: https://registry.khronos.org/glTF/specs/2.0/glTF-2.0.html#accessors
+
+use std::io;
+
+use super::{object, Document};
+use crate::{
+    utils::json::{self, Value},
+    Color4,
+};
+
+const BYTE: usize = 5120;
+const UNSIGNED_BYTE: usize = 5121;
+const SHORT: usize = 5122;
+const UNSIGNED_SHORT: usize = 5123;
+const UNSIGNED_INT: usize = 5125;
+const FLOAT: usize = 5126;
+
+/// The maximum number of components of an accessor without `bufferView`.
+///
+/// Such an accessor is initialized with zeros, so its `count` is not bounded
+/// by the size of buffers.
+const MAX_ZEROED_COMPONENTS: usize = 1 << 24;
+
+fn component_size(component_type: usize) -> io::Result<usize> {
+    Ok(match component_type {
+        BYTE | UNSIGNED_BYTE => 1,
+        SHORT | UNSIGNED_SHORT => 2,
+        UNSIGNED_INT | FLOAT => 4,
+        _ => bail!(
+            ErrorKind::Unsupported,
+            "unknown componentType {component_type}"
+        ),
+    })
+}
+
+fn num_components(ty: &str) -> io::Result<usize> {
+    Ok(match ty {
+        "SCALAR" => 1,
+        "VEC2" => 2,
+        "VEC3" => 3,
+        "VEC4" | "MAT2" => 4,
+        "MAT3" => 9,
+        "MAT4" => 16,
+        _ => bail!(ErrorKind::Unsupported, "unknown accessor type {ty:?}"),
+    })
+}
+
+/// Strided view of the elements of an accessor.
+struct Elements<'a> {
+    data: &'a [u8],
+    stride: usize,
+    count: usize,
+}
+
+impl<'a> Elements<'a> {
+    fn get(&self, i: usize) -> &'a [u8] {
+        &self.data[i * self.stride..]
+    }
+}
+
+fn buffer_view<'a>(
+    doc: &Document<'a>,
+    index: usize,
+    byte_offset: usize,
+    element_size: usize,
+    count: usize,
+) -> io::Result<Elements<'a>> {
+    let view = doc.get("bufferViews", index)?;
+    let buffer = json::req(view, "buffer", "integer", Value::as_usize)?;
+    let view_offset = json::opt(view, "byteOffset", "integer", Value::as_usize)?.unwrap_or(0);
+    let view_length = json::req(view, "byteLength", "integer", Value::as_usize)?;
+    let stride = json::opt(view, "byteStride", "integer", Value::as_usize)?.unwrap_or(element_size);
+    let data = match doc.buffers.get(buffer) {
+        Some(data) => *data,
+        None => bail!(ErrorKind::MissingElement, "buffers[{buffer}] not found"),
+    };
+    let data = view_offset
+        .checked_add(view_length)
+        .and_then(|end| data.get(view_offset..end))
+        .ok_or_else(|| {
+            format_err!(
+                ErrorKind::InvalidSize,
+                "bufferViews[{index}] is out of bounds of buffers[{buffer}]"
+            )
+        })?;
+    if count == 0 {
+        return Ok(Elements {
+            data,
+            stride,
+            count,
+        });
+    }
+    let needed = stride
+        .checked_mul(count - 1)
+        .and_then(|n| n.checked_add(element_size))
+        .and_then(|n| n.checked_add(byte_offset));
+    match needed {
+        Some(n) if n <= data.len() && stride >= element_size => Ok(Elements {
+            data: &data[byte_offset..],
+            stride,
+            count,
+        }),
+        _ => bail!(
+            ErrorKind::InvalidSize,
+            "accessor is out of bounds of bufferViews[{index}]"
+        ),
+    }
+}
+
+/// Reads an accessor as a flat list of components, converted by `f`.
+fn read<T: Copy + Default>(
+    doc: &Document<'_>,
+    index: usize,
+    f: &dyn Fn(usize, bool, &[u8]) -> io::Result<T>,
+) -> io::Result<(usize, Vec<T>)> {
+    let accessor = doc.get("accessors", index)?;
+    let component_type = json::req(accessor, "componentType", "integer", Value::as_usize)?;
+    let normalized = json::opt(accessor, "normalized", "boolean", Value::as_bool)?.unwrap_or(false);
+    let count = json::req(accessor, "count", "integer", Value::as_usize)?;
+    let components = num_components(json::req(accessor, "type", "string", Value::as_str)?)?;
+    let size = component_size(component_type)?;
+
+    let len = count
+        .checked_mul(components)
+        .ok_or_else(|| format_err!(ErrorKind::TooLarge, "accessors[{index}] is too large"))?;
+    // Check the size before allocating, as count is not trusted.
+    let byte_offset = json::opt(accessor, "byteOffset", "integer", Value::as_usize)?.unwrap_or(0);
+    let elements = match json::opt(accessor, "bufferView", "integer", Value::as_usize)? {
+        Some(view) => Some(buffer_view(
+            doc,
+            view,
+            byte_offset,
+            size * components,
+            count,
+        )?),
+        None => {
+            // Initialized with zeros. Only sparse values are stored in the buffers.
+            if len > MAX_ZEROED_COMPONENTS {
+                bail!(
+                    ErrorKind::InvalidSize,
+                    "accessors[{index}] has no bufferView, but count is too large ({count})"
+                );
+            }
+            None
+        }
+    };
+    let mut out = vec![T::default(); len];
+    if let Some(elements) = elements {
+        for (i, out) in out.chunks_exact_mut(components).enumerate() {
+            let e = elements.get(i);
+            for (j, out) in out.iter_mut().enumerate() {
+                *out = f(component_type, normalized, &e[j * size..])?;
+            }
+        }
+    }
+
+    if let Some(sparse) = json::opt(accessor, "sparse", "object", object)? {
+        let sparse_count = json::req(sparse, "count", "integer", Value::as_usize)?;
+        let indices = json::req(sparse, "indices", "object", object)?;
+        let values = json::req(sparse, "values", "object", object)?;
+        let index_type = json::req(indices, "componentType", "integer", Value::as_usize)?;
+        let index_size = component_size(index_type)?;
+        let indices = buffer_view(
+            doc,
+            json::req(indices, "bufferView", "integer", Value::as_usize)?,
+            json::opt(indices, "byteOffset", "integer", Value::as_usize)?.unwrap_or(0),
+            index_size,
+            sparse_count,
+        )?;
+        let values = buffer_view(
+            doc,
+            json::req(values, "bufferView", "integer", Value::as_usize)?,
+            json::opt(values, "byteOffset", "integer", Value::as_usize)?.unwrap_or(0),
+            size * components,
+            sparse_count,
+        )?;
+        // Sparse indices and values are tightly packed.
+        let indices = Elements {
+            stride: index_size,
+            ..indices
+        };
+        let values = Elements {
+            stride: size * components,
+            ..values
+        };
+        for i in 0..indices.count {
+            let target = to_u32(index_type, indices.get(i))? as usize;
+            if target >= count {
+                bail!(
+                    ErrorKind::InvalidSize,
+                    "sparse index out of bounds ({target})"
+                );
+            }
+            let e = values.get(i);
+            for (j, out) in out[target * components..(target + 1) * components]
+                .iter_mut()
+                .enumerate()
+            {
+                *out = f(component_type, normalized, &e[j * size..])?;
+            }
+        }
+    }
+
+    Ok((components, out))
+}
+
+fn to_f32(component_type: usize, normalized: bool, b: &[u8]) -> f32 {
+    match component_type {
+        BYTE => {
+            let v = f32::from(i8::from_le_bytes([b[0]]));
+            if normalized {
+                (v / 127.).max(-1.)
+            } else {
+                v
+            }
+        }
+        UNSIGNED_BYTE => {
+            let v = f32::from(b[0]);
+            if normalized {
+                v / 255.
+            } else {
+                v
+            }
+        }
+        SHORT => {
+            let v = f32::from(i16::from_le_bytes([b[0], b[1]]));
+            if normalized {
+                (v / 32767.).max(-1.)
+            } else {
+                v
+            }
+        }
+        UNSIGNED_SHORT => {
+            let v = f32::from(u16::from_le_bytes([b[0], b[1]]));
+            if normalized {
+                v / 65535.
+            } else {
+                v
+            }
+        }
+        #[allow(clippy::cast_precision_loss)]
+        UNSIGNED_INT => u32::from_le_bytes(b[..4].try_into().unwrap()) as f32,
+        FLOAT => f32::from_le_bytes(b[..4].try_into().unwrap()),
+        _ => unreachable!(),
+    }
+}
+
+fn to_u32(component_type: usize, b: &[u8]) -> io::Result<u32> {
+    Ok(match component_type {
+        UNSIGNED_BYTE => u32::from(b[0]),
+        UNSIGNED_SHORT => u32::from(u16::from_le_bytes([b[0], b[1]])),
+        UNSIGNED_INT => u32::from_le_bytes(b[..4].try_into().unwrap()),
+        _ => bail!(
+            ErrorKind::InvalidFaceIndex,
+            "componentType {component_type} is not allowed for indices"
+        ),
+    })
+}
+
+pub(super) fn read_attribute<const N: usize>(
+    doc: &Document<'_>,
+    index: usize,
+) -> io::Result<Vec<[f32; N]>> {
+    let (components, values) = read(doc, index, &|ty, normalized, b| {
+        Ok(to_f32(ty, normalized, b))
+    })?;
+    if components != N {
+        bail!(
+            ErrorKind::InvalidSize,
+            "accessors[{index}] must have {N} components, but has {components}"
+        );
+    }
+    Ok(values
+        .chunks_exact(N)
+        .map(|v| v.try_into().unwrap())
+        .collect())
+}
+
+pub(super) fn read_color(doc: &Document<'_>, index: usize) -> io::Result<Vec<Color4>> {
+    let (components, values) = read(doc, index, &|ty, normalized, b| {
+        Ok(to_f32(ty, normalized, b))
+    })?;
+    match components {
+        3 => Ok(values
+            .chunks_exact(3)
+            .map(|v| [v[0], v[1], v[2], 1.])
+            .collect()),
+        4 => Ok(values
+            .chunks_exact(4)
+            .map(|v| v.try_into().unwrap())
+            .collect()),
+        _ => bail!(
+            ErrorKind::InvalidSize,
+            "accessors[{index}] must have 3 or 4 components, but has {components}"
+        ),
+    }
+}
+
+pub(super) fn read_indices(doc: &Document<'_>, index: usize) -> io::Result<Vec<u32>> {
+    let (components, values) = read(doc, index, &|ty, _, b| to_u32(ty, b))?;
+    if components != 1 {
+        bail!(ErrorKind::InvalidSize, "accessors[{index}] must be SCALAR");
+    }
+    Ok(values)
+}
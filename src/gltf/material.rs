@@ -0,0 +1,85 @@
+// Refs: https://registry.khronos.org/glTF/specs/2.0/glTF-2.0.html#materials
+
+use std::io;
+
+use super::{floats, object, Document};
+use crate::{
+    utils::{
+        json::{self, Value},
+        uri::percent_decode,
+    },
+    Material, ShadingModel, Texture, TextureSource, Warning,
+};
+
+pub(super) fn build_material(
+    doc: &Document<'_>,
+    material: &Value<'_>,
+    warnings: &mut Vec<Warning>,
+) -> io::Result<Material> {
+    let mut mat = Material::default();
+    if let Some(name) = json::opt(material, "name", "string", Value::as_str)? {
+        mat.name = name.to_owned();
+    }
+
+    if let Some(pbr) = json::opt(material, "pbrMetallicRoughness", "object", object)? {
+        let base_color = json::opt(pbr, "baseColorFactor", "array of 4 numbers", floats::<4>)?
+            .unwrap_or([1.; 4]);
+        mat.color.diffuse = Some(base_color);
+        mat.texture.diffuse = texture(doc, pbr, "baseColorTexture", warnings)?;
+        let alpha_mode = json::opt(material, "alphaMode", "string", Value::as_str)?;
+        if alpha_mode == Some("BLEND") {
+            mat.opacity = Some(base_color[3]);
+        }
+    }
+    if let Some([r, g, b]) = json::opt(
+        material,
+        "emissiveFactor",
+        "array of 3 numbers",
+        floats::<3>,
+    )? {
+        mat.color.emissive = Some([r, g, b, 1.]);
+    }
+    mat.texture.emissive = texture(doc, material, "emissiveTexture", warnings)?;
+    mat.texture.normal = texture(doc, material, "normalTexture", warnings)?;
+    mat.texture.lightmap = texture(doc, material, "occlusionTexture", warnings)?;
+
+    if let Some(extensions) = json::opt(material, "extensions", "object", object)? {
+        if extensions.get("KHR_materials_unlit").is_some() {
+            mat.shading_model = Some(ShadingModel::NoShading);
+        }
+    }
+
+    Ok(mat)
+}
+
+/// Resolves a textureInfo to the path of the image.
+fn texture(
+    doc: &Document<'_>,
+    parent: &Value<'_>,
+    key: &str,
+    warnings: &mut Vec<Warning>,
+) -> io::Result<Option<Texture>> {
+    let info = match json::opt(parent, key, "object", object)? {
+        Some(info) => info,
+        None => return Ok(None),
+    };
+    let texture = doc.get(
+        "textures",
+        json::req(info, "index", "integer", Value::as_usize)?,
+    )?;
+    let source = match json::opt(texture, "source", "integer", Value::as_usize)? {
+        Some(source) => source,
+        None => return Ok(None),
+    };
+    let image = doc.get("images", source)?;
+    // TODO: images embedded in a buffer view or data URI are not supported yet.
+    let uri = match json::opt(image, "uri", "string", Value::as_str)? {
+        Some(uri) if !uri.starts_with("data:") => uri,
+        _ => return Ok(None),
+    };
+    let uri = percent_decode(uri);
+    Ok(doc
+        .textures
+        .resolve(&uri, doc.dir, None, warnings)
+        .map(|p| TextureSource::Path(p).into()))
+}
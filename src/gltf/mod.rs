@@ -0,0 +1,720 @@
+//! [glTF] 2.0 (.gltf/.glb) parser.
+//!
+//! [glTF]: https://www.khronos.org/gltf
+
+mod accessor;
+mod material;
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    str,
+};
+
+use self::accessor::{read_attribute, read_indices};
+use crate::{
+    utils::{
+        base64,
+        bytes::starts_with,
+        json::{self, Value},
+        uri::percent_decode,
+    },
+    CoordinateSystem, Material, Mesh, Scene, TextureResolver, Vec3,
+};
+
+/// Parses meshes from bytes of glTF (.gltf) or binary glTF (.glb).
+///
+/// External buffers referenced by the document are read by using `reader`.
+pub fn from_slice<B: AsRef<[u8]>, F: FnMut(&Path) -> io::Result<B>>(
+    bytes: &[u8],
+    path: Option<&Path>,
+    reader: F,
+) -> io::Result<Scene> {
+    from_slice_internal(bytes, path, reader, &TextureResolver::default())
+}
+
+pub(crate) fn from_slice_internal<B: AsRef<[u8]>, F: FnMut(&Path) -> io::Result<B>>(
+    bytes: &[u8],
+    path: Option<&Path>,
+    reader: F,
+    textures: &TextureResolver,
+) -> io::Result<Scene> {
+    read_gltf(bytes, path, reader, textures).map_err(|e| match path {
+        Some(path) if crate::Error::from_io_error(&e).is_none() => {
+            io::Error::new(e.kind(), format!("{e} ({})", path.display()))
+        }
+        _ => crate::error::with_context(e, crate::Format::Gltf, path),
+    })
+}
+
+fn read_gltf<B: AsRef<[u8]>, F: FnMut(&Path) -> io::Result<B>>(
+    bytes: &[u8],
+    path: Option<&Path>,
+    mut reader: F,
+    textures: &TextureResolver,
+) -> io::Result<Scene> {
+    let (json, bin) = if starts_with(bytes, GLB_MAGIC) {
+        read_glb(bytes)?
+    } else {
+        (bytes, None)
+    };
+    let json = json.strip_prefix(UTF8_BOM).unwrap_or(json);
+    let json = str::from_utf8(json).map_err(|e| format_err!(ErrorKind::Syntax, "{e}"))?;
+    let root = json::parse(json)?;
+
+    let asset = json::req(&root, "asset", "object", object)?;
+    let version = json::req(asset, "version", "string", Value::as_str)?;
+    if !version.starts_with("2.") {
+        bail!(
+            ErrorKind::Unsupported,
+            "glTF version {version} is not supported"
+        );
+    }
+
+    let dir = path.and_then(Path::parent);
+    let mut buffers = vec![];
+    for (i, buffer) in array(&root, "buffers")?.iter().enumerate() {
+        buffers.push(read_buffer(buffer, i, bin, dir, &mut reader)?);
+    }
+    let doc = Document {
+        root: &root,
+        buffers: buffers.iter().map(BufferData::as_slice).collect(),
+        dir,
+        textures,
+    };
+    let mut scene = build_scene(&doc)?;
+    for warning in &mut scene.warnings {
+        warning.file = path.filter(|&p| p != Path::new("")).map(Path::to_path_buf);
+    }
+    Ok(scene)
+}
+
+// -----------------------------------------------------------------------------
+// Binary glTF
+
+/*
+https://registry.khronos.org/glTF/specs/2.0/glTF-2.0.html#binary-gltf-layout
+
+UINT32       – magic                  -      4 bytes
+UINT32       – version                -      4 bytes
+UINT32       – length                 -      4 bytes
+
+foreach chunk
+    UINT32    – chunkLength               -  4 bytes
+    UINT32    – chunkType                 -  4 bytes
+    UBYTE[]   – chunkData                 -  chunkLength bytes
+end
+*/
+const GLB_MAGIC: &[u8] = b"glTF";
+const GLB_HEADER_SIZE: usize = 12;
+const GLB_CHUNK_HEADER_SIZE: usize = 8;
+const GLB_CHUNK_JSON: u32 = 0x4E4F_534A;
+const GLB_CHUNK_BIN: u32 = 0x004E_4942;
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+fn read_glb(bytes: &[u8]) -> io::Result<(&[u8], Option<&[u8]>)> {
+    fn u32le(bytes: &[u8], pos: usize) -> u32 {
+        u32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap())
+    }
+
+    if bytes.len() < GLB_HEADER_SIZE {
+        bail!(ErrorKind::UnexpectedEof, "binary glTF is too small");
+    }
+    let version = u32le(bytes, 4);
+    if version != 2 {
+        bail!(
+            ErrorKind::Unsupported,
+            "binary glTF version {version} is not supported"
+        );
+    }
+    let length = u32le(bytes, 8) as usize;
+    if length > bytes.len() || length < GLB_HEADER_SIZE {
+        bail!(
+            ErrorKind::InvalidSize,
+            "binary glTF length field ({length}) is inconsistent with the file size ({})",
+            bytes.len()
+        );
+    }
+    let mut s = &bytes[GLB_HEADER_SIZE..length];
+    let mut json = None;
+    let mut bin = None;
+    while !s.is_empty() {
+        if s.len() < GLB_CHUNK_HEADER_SIZE {
+            bail!(
+                ErrorKind::UnexpectedEof,
+                "unexpected end of binary glTF chunk header"
+            );
+        }
+        let chunk_length = u32le(s, 0) as usize;
+        let chunk_type = u32le(s, 4);
+        s = &s[GLB_CHUNK_HEADER_SIZE..];
+        if chunk_length > s.len() {
+            bail!(
+                ErrorKind::InvalidSize,
+                "binary glTF chunk length ({chunk_length}) exceeds the file size"
+            );
+        }
+        let data = &s[..chunk_length];
+        s = &s[chunk_length..];
+        match chunk_type {
+            GLB_CHUNK_JSON if json.is_none() => json = Some(data),
+            GLB_CHUNK_BIN if json.is_some() && bin.is_none() => bin = Some(data),
+            GLB_CHUNK_JSON | GLB_CHUNK_BIN => bail!(
+                ErrorKind::UnexpectedElement,
+                "unexpected binary glTF chunk order"
+            ),
+            _ => { /* unknown chunks must be ignored */ }
+        }
+    }
+    match json {
+        Some(json) => Ok((json, bin)),
+        None => bail!(ErrorKind::MissingElement, "binary glTF has no JSON chunk"),
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Buffers
+
+enum BufferData<'a, B> {
+    Borrowed(&'a [u8]),
+    Owned(Vec<u8>),
+    External(B),
+}
+
+impl<B: AsRef<[u8]>> BufferData<'_, B> {
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            Self::Borrowed(b) => b,
+            Self::Owned(b) => b,
+            Self::External(b) => b.as_ref(),
+        }
+    }
+}
+
+fn read_buffer<'a, B: AsRef<[u8]>, F: FnMut(&Path) -> io::Result<B>>(
+    buffer: &Value<'_>,
+    index: usize,
+    bin: Option<&'a [u8]>,
+    dir: Option<&Path>,
+    reader: &mut F,
+) -> io::Result<BufferData<'a, B>> {
+    let byte_length = json::req(buffer, "byteLength", "integer", Value::as_usize)?;
+    let data = match json::opt(buffer, "uri", "string", Value::as_str)? {
+        Some(uri) => {
+            if let Some(data) = decode_data_uri(uri)? {
+                BufferData::Owned(data)
+            } else {
+                let p = uri_to_path(uri, dir).ok_or_else(|| {
+                    format_err!(
+                        ErrorKind::MissingElement,
+                        "cannot resolve relative URI {uri:?} of buffers[{index}] without file path"
+                    )
+                })?;
+                BufferData::External(reader(&p)?)
+            }
+        }
+        None => match bin {
+            // Only the first buffer can refer the BIN chunk.
+            Some(bin) if index == 0 => BufferData::Borrowed(bin),
+            _ => bail!(ErrorKind::MissingElement, "buffers[{index}] has no uri"),
+        },
+    };
+    let len = data.as_slice().len();
+    if len < byte_length {
+        bail!(ErrorKind::InvalidSize, "buffers[{index}] is too small: byteLength is {byte_length}, but actual length is {len}");
+    }
+    Ok(data)
+}
+
+/// Decodes `data:[<mediatype>][;base64],<data>` URI.
+///
+/// Returns `Ok(None)` if the given URI is not data URI.
+fn decode_data_uri(uri: &str) -> io::Result<Option<Vec<u8>>> {
+    let rest = match uri.strip_prefix("data:") {
+        Some(rest) => rest,
+        None => return Ok(None),
+    };
+    let (header, data) = match rest.find(',') {
+        Some(n) => (&rest[..n], &rest[n + 1..]),
+        None => bail!(ErrorKind::Syntax, "invalid data URI"),
+    };
+    if header.ends_with(";base64") {
+        base64::decode(data.as_bytes()).map(Some)
+    } else {
+        Ok(Some(percent_decode(data).into_owned().into_bytes()))
+    }
+}
+
+/// Converts a URI reference to a path relative to `dir`.
+fn uri_to_path(uri: &str, dir: Option<&Path>) -> Option<PathBuf> {
+    let uri = percent_decode(uri);
+    let p = Path::new(&*uri);
+    match dir {
+        Some(dir) => Some(dir.join(p)),
+        None if p.is_absolute() => Some(p.to_owned()),
+        None => None,
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Scene
+
+struct Document<'a> {
+    root: &'a Value<'a>,
+    buffers: Vec<&'a [u8]>,
+    dir: Option<&'a Path>,
+    textures: &'a TextureResolver,
+}
+
+impl<'a> Document<'a> {
+    fn get(&self, name: &str, index: usize) -> io::Result<&'a Value<'a>> {
+        match array(self.root, name)?.get(index) {
+            Some(v) => Ok(v),
+            None => bail!(ErrorKind::MissingElement, "{name}[{index}] not found"),
+        }
+    }
+}
+
+fn object<'b, 'a: 'b>(v: &'b Value<'a>) -> Option<&'b Value<'a>> {
+    v.as_object().map(|_| v)
+}
+
+fn array<'b, 'a: 'b>(v: &'b Value<'a>, key: &str) -> io::Result<&'b [Value<'a>]> {
+    Ok(json::opt(v, key, "array", Value::as_array)?.unwrap_or_default())
+}
+
+// Column-major 4x4 matrix, same as glTF's `matrix` property.
+type Mat4 = [f32; 16];
+
+const IDENTITY: Mat4 = [
+    1., 0., 0., 0., 0., 1., 0., 0., 0., 0., 1., 0., 0., 0., 0., 1.,
+];
+
+fn mul(a: &Mat4, b: &Mat4) -> Mat4 {
+    let mut out = [0.; 16];
+    for c in 0..4 {
+        for r in 0..4 {
+            out[c * 4 + r] = a[r] * b[c * 4]
+                + a[4 + r] * b[c * 4 + 1]
+                + a[8 + r] * b[c * 4 + 2]
+                + a[12 + r] * b[c * 4 + 3];
+        }
+    }
+    out
+}
+
+fn node_transform(node: &Value<'_>) -> io::Result<Mat4> {
+    if let Some(m) = json::opt(node, "matrix", "array of 16 numbers", floats::<16>)? {
+        return Ok(m);
+    }
+    let [tx, ty, tz] =
+        json::opt(node, "translation", "array of 3 numbers", floats::<3>)?.unwrap_or([0.; 3]);
+    let [x, y, z, w] =
+        json::opt(node, "rotation", "array of 4 numbers", floats::<4>)?.unwrap_or([0., 0., 0., 1.]);
+    let [sx, sy, sz] =
+        json::opt(node, "scale", "array of 3 numbers", floats::<3>)?.unwrap_or([1.; 3]);
+    // T * R * S
+    Ok([
+        (1. - 2. * (y * y + z * z)) * sx,
+        (2. * (x * y + z * w)) * sx,
+        (2. * (x * z - y * w)) * sx,
+        0.,
+        (2. * (x * y - z * w)) * sy,
+        (1. - 2. * (x * x + z * z)) * sy,
+        (2. * (y * z + x * w)) * sy,
+        0.,
+        (2. * (x * z + y * w)) * sz,
+        (2. * (y * z - x * w)) * sz,
+        (1. - 2. * (x * x + y * y)) * sz,
+        0.,
+        tx,
+        ty,
+        tz,
+        1.,
+    ])
+}
+
+fn floats<const N: usize>(v: &Value<'_>) -> Option<[f32; N]> {
+    let a = v.as_array()?;
+    if a.len() != N {
+        return None;
+    }
+    let mut out = [0.; N];
+    for (out, v) in out.iter_mut().zip(a) {
+        *out = v.as_f32()?;
+    }
+    Some(out)
+}
+
+fn build_scene(doc: &Document<'_>) -> io::Result<Scene> {
+    let mut meshes = vec![];
+    let mut materials = vec![];
+    let mut warnings = vec![];
+    let mut material_cache = vec![None; array(doc.root, "materials")?.len()];
+
+    let mut instances = vec![];
+    let scenes = array(doc.root, "scenes")?;
+    if scenes.is_empty() {
+        // No scene to render: load all meshes as is.
+        for mesh in 0..array(doc.root, "meshes")?.len() {
+            instances.push((mesh, IDENTITY));
+        }
+    } else {
+        let scene = json::opt(doc.root, "scene", "integer", Value::as_usize)?.unwrap_or(0);
+        let scene = doc.get("scenes", scene)?;
+        let mut visited = vec![false; array(doc.root, "nodes")?.len()];
+        let mut stack = vec![];
+        for root in array(scene, "nodes")? {
+            match root.as_usize() {
+                Some(i) => stack.push((i, IDENTITY)),
+                None => bail!(ErrorKind::Syntax, "invalid node index {root:?}"),
+            }
+        }
+        stack.reverse();
+        while let Some((i, parent)) = stack.pop() {
+            // Nodes must form strict trees: a node that is reached twice is
+            // shared by multiple parents or part of a cycle.
+            match visited.get_mut(i) {
+                Some(true) => bail!(
+                    ErrorKind::UnexpectedElement,
+                    "nodes[{i}] is referenced more than once in node hierarchy"
+                ),
+                Some(visited) => *visited = true,
+                None => bail!(ErrorKind::MissingElement, "nodes[{i}] not found"),
+            }
+            let node = doc.get("nodes", i)?;
+            let transform = mul(&parent, &node_transform(node)?);
+            if let Some(mesh) = json::opt(node, "mesh", "integer", Value::as_usize)? {
+                instances.push((mesh, transform));
+            }
+            let first_child = stack.len();
+            for child in array(node, "children")? {
+                match child.as_usize() {
+                    Some(c) => stack.push((c, transform)),
+                    None => bail!(ErrorKind::Syntax, "invalid node index {child:?}"),
+                }
+            }
+            stack[first_child..].reverse();
+        }
+    }
+
+    for (mesh_index, transform) in instances {
+        let mesh = doc.get("meshes", mesh_index)?;
+        let name = json::opt(mesh, "name", "string", Value::as_str)?.unwrap_or_default();
+        let primitives = array(mesh, "primitives")?;
+        for (i, primitive) in primitives.iter().enumerate() {
+            let mut m = match build_mesh(doc, primitive, &transform)? {
+                Some(m) => m,
+                None => continue,
+            };
+            m.name = if primitives.len() == 1 {
+                name.to_owned()
+            } else {
+                format!("{name}-{i}")
+            };
+            let material = match json::opt(primitive, "material", "integer", Value::as_usize)? {
+                Some(i) => match material_cache.get_mut(i) {
+                    Some(cache) => match cache {
+                        Some(mat) => Material::clone(mat),
+                        None => cache
+                            .insert(material::build_material(
+                                doc,
+                                doc.get("materials", i)?,
+                                &mut warnings,
+                            )?)
+                            .clone(),
+                    },
+                    None => bail!(ErrorKind::MissingElement, "materials[{i}] not found"),
+                },
+                None => Material::default(),
+            };
+            meshes.push(m);
+            materials.push(material);
+        }
+    }
+
+    Ok(Scene {
+        materials,
+        meshes,
+        coordinate_system: Some(CoordinateSystem::Y_UP),
+        warnings,
+        ..Default::default()
+    })
+}
+
+// Refs: https://registry.khronos.org/glTF/specs/2.0/glTF-2.0.html#_mesh_primitive_mode
+const MODE_POINTS: usize = 0;
+const MODE_LINES: usize = 1;
+const MODE_LINE_LOOP: usize = 2;
+const MODE_LINE_STRIP: usize = 3;
+const MODE_TRIANGLES: usize = 4;
+const MODE_TRIANGLE_STRIP: usize = 5;
+const MODE_TRIANGLE_FAN: usize = 6;
+
+fn build_mesh(
+    doc: &Document<'_>,
+    primitive: &Value<'_>,
+    transform: &Mat4,
+) -> io::Result<Option<Mesh>> {
+    let mode = json::opt(primitive, "mode", "integer", Value::as_usize)?.unwrap_or(MODE_TRIANGLES);
+    if mode > MODE_TRIANGLE_FAN {
+        // unknown mode
+        return Ok(None);
+    }
+    let attributes = json::req(primitive, "attributes", "object", object)?;
+    let positions = match json::opt(attributes, "POSITION", "integer", Value::as_usize)? {
+        Some(i) => read_attribute::<3>(doc, i)?,
+        None => return Ok(None),
+    };
+    let num_vertices = positions.len();
+    let mut mesh = Mesh::default();
+
+    let is_identity = *transform == IDENTITY;
+    mesh.vertices = positions;
+    if !is_identity {
+        for v in &mut mesh.vertices {
+            *v = transform_point(transform, *v);
+        }
+    }
+    if let Some(i) = json::opt(attributes, "NORMAL", "integer", Value::as_usize)? {
+        mesh.normals = read_attribute::<3>(doc, i)?;
+        if mesh.normals.len() != num_vertices {
+            bail!(
+                ErrorKind::InvalidSize,
+                "NORMAL attribute has different count from POSITION attribute"
+            );
+        }
+        if !is_identity {
+            let normal_matrix = normal_matrix(transform);
+            for n in &mut mesh.normals {
+                *n = transform_normal(&normal_matrix, *n);
+            }
+        }
+    }
+    // Sets are numbered from 0 without gaps.
+    for set in 0.. {
+        let key = format!("TEXCOORD_{set}");
+        let i = match json::opt(attributes, &key, "integer", Value::as_usize)? {
+            Some(i) => i,
+            None => break,
+        };
+        let mut texcoords = read_attribute::<2>(doc, i)?;
+        if texcoords.len() != num_vertices {
+            bail!(
+                ErrorKind::InvalidSize,
+                "{key} attribute has different count from POSITION attribute"
+            );
+        }
+        // glTF uses the top-left corner as the origin of texture coordinates.
+        for t in &mut texcoords {
+            t[1] = 1. - t[1];
+        }
+        mesh.texcoords.push(texcoords);
+    }
+    for set in 0.. {
+        let key = format!("COLOR_{set}");
+        let i = match json::opt(attributes, &key, "integer", Value::as_usize)? {
+            Some(i) => i,
+            None => break,
+        };
+        let colors = accessor::read_color(doc, i)?;
+        if colors.len() != num_vertices {
+            bail!(
+                ErrorKind::InvalidSize,
+                "{key} attribute has different count from POSITION attribute"
+            );
+        }
+        mesh.colors.push(colors);
+    }
+
+    let indices = match json::opt(primitive, "indices", "integer", Value::as_usize)? {
+        Some(i) => read_indices(doc, i)?,
+        None => {
+            if u32::try_from(num_vertices).is_err() {
+                bail!(ErrorKind::TooLarge, "too many vertices");
+            }
+            #[allow(clippy::cast_possible_truncation)]
+            (0..num_vertices as u32).collect()
+        }
+    };
+    if let Some(&i) = indices.iter().find(|&&i| i as usize >= num_vertices) {
+        bail!(
+            ErrorKind::FaceIndexOutOfBounds,
+            "vertex index out of bounds ({i})"
+        );
+    }
+    match mode {
+        MODE_POINTS => mesh.points = indices,
+        MODE_LINES => {
+            mesh.lines = indices.chunks_exact(2).map(|l| [l[0], l[1]]).collect();
+        }
+        MODE_LINE_LOOP | MODE_LINE_STRIP => {
+            mesh.lines = indices.windows(2).map(|l| [l[0], l[1]]).collect();
+            if mode == MODE_LINE_LOOP && indices.len() > 2 {
+                mesh.lines.push([indices[indices.len() - 1], indices[0]]);
+            }
+        }
+        MODE_TRIANGLES => {
+            mesh.faces = indices
+                .chunks_exact(3)
+                .map(|f| [f[0], f[1], f[2]])
+                .collect();
+        }
+        MODE_TRIANGLE_STRIP => {
+            for (i, f) in indices.windows(3).enumerate() {
+                // Keep winding order.
+                if i % 2 == 0 {
+                    mesh.faces.push([f[0], f[1], f[2]]);
+                } else {
+                    mesh.faces.push([f[1], f[0], f[2]]);
+                }
+            }
+        }
+        MODE_TRIANGLE_FAN => {
+            if let Some((&first, rest)) = indices.split_first() {
+                for f in rest.windows(2) {
+                    mesh.faces.push([first, f[0], f[1]]);
+                }
+            }
+        }
+        _ => unreachable!(),
+    }
+
+    Ok(Some(mesh))
+}
+
+fn transform_point(m: &Mat4, v: Vec3) -> Vec3 {
+    [
+        m[0] * v[0] + m[4] * v[1] + m[8] * v[2] + m[12],
+        m[1] * v[0] + m[5] * v[1] + m[9] * v[2] + m[13],
+        m[2] * v[0] + m[6] * v[1] + m[10] * v[2] + m[14],
+    ]
+}
+
+/// Returns the inverse transpose of the upper 3x3 matrix (row-major).
+#[allow(clippy::many_single_char_names)]
+fn normal_matrix(m: &Mat4) -> [f32; 9] {
+    let (a, b, c) = (m[0], m[4], m[8]);
+    let (d, e, f) = (m[1], m[5], m[9]);
+    let (g, h, i) = (m[2], m[6], m[10]);
+    let co = [
+        e * i - f * h,
+        f * g - d * i,
+        d * h - e * g,
+        c * h - b * i,
+        a * i - c * g,
+        b * g - a * h,
+        b * f - c * e,
+        c * d - a * f,
+        a * e - b * d,
+    ];
+    let det = a * co[0] + b * co[1] + c * co[2];
+    if det == 0. {
+        return [a, b, c, d, e, f, g, h, i];
+    }
+    // The cofactor matrix is the inverse transpose multiplied by the determinant.
+    let inv = 1. / det;
+    co.map(|x| x * inv)
+}
+
+fn transform_normal(m: &[f32; 9], n: Vec3) -> Vec3 {
+    let v = [
+        m[0] * n[0] + m[1] * n[1] + m[2] * n[2],
+        m[3] * n[0] + m[4] * n[1] + m[5] * n[2],
+        m[6] * n[0] + m[7] * n[1] + m[8] * n[2],
+    ];
+    let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    if len == 0. {
+        v
+    } else {
+        [v[0] / len, v[1] / len, v[2] / len]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(json: &str) -> io::Result<Scene> {
+        from_slice::<Vec<u8>, _>(json.as_bytes(), None, |_| unreachable!())
+    }
+
+    #[test]
+    fn accessor_size() {
+        // An accessor must not be allocated before its count is checked.
+        for accessor in [
+            r#"{"componentType": 5126, "count": 1000000000, "type": "VEC3"}"#,
+            r#"{"bufferView": 0, "componentType": 5126, "count": 1000000000, "type": "VEC3"}"#,
+        ] {
+            let json = format!(
+                r#"{{
+                    "asset": {{"version": "2.0"}},
+                    "buffers": [{{"byteLength": 4, "uri": "data:,abcd"}}],
+                    "bufferViews": [{{"buffer": 0, "byteLength": 4}}],
+                    "accessors": [{accessor}],
+                    "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0}}}}]}}]
+                }}"#
+            );
+            let e = load(&json).unwrap_err();
+            assert_eq!(
+                crate::Error::from_io_error(&e).unwrap().kind(),
+                crate::ErrorKind::InvalidSize
+            );
+        }
+    }
+
+    #[test]
+    fn node_hierarchy() {
+        let load_nodes = |nodes: &str| {
+            load(&format!(
+                r#"{{"asset": {{"version": "2.0"}}, "scenes": [{{"nodes": [0]}}], "nodes": {nodes}}}"#
+            ))
+        };
+        load_nodes(r#"[{"children": [1, 2]}, {"children": [3]}, {}, {}]"#).unwrap();
+        // cycle
+        load_nodes(r#"[{"children": [1]}, {"children": [0]}]"#).unwrap_err();
+        // shared children: this expands exponentially if not rejected
+        let nodes = (0..64)
+            .map(|i| format!(r#"{{"children": [{0}, {0}]}}"#, i + 1))
+            .chain(["{}".to_owned()])
+            .collect::<Vec<_>>()
+            .join(",");
+        load_nodes(&format!("[{nodes}]")).unwrap_err();
+    }
+
+    #[test]
+    fn primitive_mode() {
+        // (0, 0, 0), (1, 0, 0), (1, 1, 0), (0, 1, 0)
+        let load_mode = |mode: usize| {
+            let json = format!(
+                r#"{{
+                    "asset": {{"version": "2.0"}},
+                    "buffers": [{{
+                        "byteLength": 48,
+                        "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAACAPwAAgD8AAAAAAAAAAAAAgD8AAAAA"
+                    }}],
+                    "bufferViews": [{{"buffer": 0, "byteLength": 48}}],
+                    "accessors": [{{"bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3"}}],
+                    "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0}}, "mode": {mode}}}]}}],
+                    "nodes": [{{"mesh": 0}}],
+                    "scenes": [{{"nodes": [0]}}]
+                }}"#
+            );
+            load(&json).unwrap().meshes.remove(0)
+        };
+
+        let mesh = load_mode(MODE_POINTS);
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.points, [0, 1, 2, 3]);
+        assert!(mesh.lines.is_empty() && mesh.faces.is_empty());
+        assert_eq!(load_mode(MODE_LINES).lines, [[0, 1], [2, 3]]);
+        assert_eq!(
+            load_mode(MODE_LINE_LOOP).lines,
+            [[0, 1], [1, 2], [2, 3], [3, 0]]
+        );
+        assert_eq!(load_mode(MODE_LINE_STRIP).lines, [[0, 1], [1, 2], [2, 3]]);
+        let mesh = load_mode(MODE_TRIANGLES);
+        assert_eq!(mesh.faces, [[0, 1, 2]]);
+        assert!(mesh.points.is_empty() && mesh.lines.is_empty());
+    }
+}
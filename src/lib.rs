@@ -22,29 +22,46 @@
     clippy::must_use_candidate,
 )]
 
-#[cfg(any(feature = "collada", feature = "obj", feature = "stl"))]
 #[macro_use]
 mod error;
+pub use error::{Error, ErrorKind, Format, Warning, WarningKind};
 
 mod utils;
 
+#[cfg(feature = "zip")]
+mod archive;
+
+mod detect;
+pub use detect::detect_format;
 mod loader;
 pub use loader::*;
+mod resolver;
+pub use resolver::{PackagePaths, ResourceResolver, TextureResolver};
 mod common;
 pub use common::*;
 
 #[cfg(feature = "collada")]
 pub mod collada;
+#[cfg(feature = "gltf")]
+pub mod gltf;
 #[cfg(feature = "obj")]
 pub mod obj;
+#[cfg(feature = "ply")]
+pub mod ply;
 #[cfg(feature = "stl")]
 pub mod stl;
 
 // Not public API. (exposed for benchmarks)
 #[doc(hidden)]
-#[cfg(any(feature = "collada", feature = "obj", feature = "stl"))]
+#[cfg(any(
+    feature = "collada",
+    feature = "gltf",
+    feature = "obj",
+    feature = "ply",
+    feature = "stl",
+))]
 pub mod __private {
     pub use crate::utils::float;
-    #[cfg(any(feature = "collada", feature = "obj"))]
+    #[cfg(any(feature = "collada", feature = "obj", feature = "ply"))]
     pub use crate::utils::int;
 }
@@ -1,15 +1,29 @@
-use std::{cmp, ffi::OsStr, fmt, fs, io, path::Path};
+#[cfg(any(feature = "gzip", feature = "zip"))]
+use std::ffi::OsStr;
+use std::{borrow::Cow, fmt, fs, io, path::Path, sync::Arc};
 
-use crate::{utils::bytes::starts_with, Scene};
+use crate::{
+    detect_format, CoordinateSystem, Format, PackagePaths, ResourceResolver, Scene, TextureResolver,
+};
 
-type Reader<B> = fn(&Path) -> io::Result<B>;
+type Reader<B> = Arc<dyn ResourceResolver<B>>;
 
 pub struct Loader<B = Vec<u8>> {
     reader: Reader<B>,
     merge_meshes: bool,
+    weld_vertices: bool,
+    keep_polygons: bool,
+    coordinate_system: Option<CoordinateSystem>,
+    default_coordinate_system: Option<CoordinateSystem>,
+    strictness: Strictness,
+    collect_warnings: bool,
+    texture_resolver: TextureResolver,
     // STL config
     #[cfg(feature = "stl")]
     stl_parse_color: bool,
+    // COLLADA config
+    #[cfg(feature = "collada")]
+    collada_pre_transform: bool,
 }
 
 fn default_reader(path: &Path) -> io::Result<Vec<u8>> {
@@ -19,10 +33,19 @@ fn default_reader(path: &Path) -> io::Result<Vec<u8>> {
 impl Default for Loader<Vec<u8>> {
     fn default() -> Self {
         Self {
-            reader: default_reader,
+            reader: Arc::new(default_reader),
             merge_meshes: false,
+            weld_vertices: false,
+            keep_polygons: false,
+            coordinate_system: None,
+            default_coordinate_system: None,
+            strictness: Strictness::Lenient,
+            collect_warnings: false,
+            texture_resolver: TextureResolver::default(),
             #[cfg(feature = "stl")]
             stl_parse_color: false,
+            #[cfg(feature = "collada")]
+            collada_pre_transform: true,
         }
     }
 }
@@ -31,7 +54,9 @@ impl<B: AsRef<[u8]>> Loader<B> {
     /// Sets whether or not to merge meshes at load time.
     ///
     /// If set to `true`, it is guaranteed that there is exactly one mesh in the
-    /// loaded `Scene` (i.e., `scene.meshes.len() == 1`).
+    /// loaded `Scene` (i.e., `scene.meshes.len() == 1`), and the node hierarchy
+    /// and animations are discarded (i.e., `scene.nodes.is_empty()` and
+    /// `scene.animations.is_empty()`).
     ///
     /// Default: `false`
     #[must_use]
@@ -40,7 +65,162 @@ impl<B: AsRef<[u8]>> Loader<B> {
         self
     }
 
-    /// Use the given function as a file reader of this loader.
+    /// Sets whether or not to share identical vertices between faces at load time.
+    ///
+    /// By default, each face of loaded meshes has its own three vertices. If
+    /// set to `true`, vertices that have the same position, normal, texture
+    /// coordinates, colors, and bone weights are merged into one vertex, and
+    /// `faces` refer to the merged vertices. See [`Mesh::weld_vertices`](crate::Mesh::weld_vertices) for
+    /// details.
+    ///
+    /// Default: `false`
+    #[must_use]
+    pub fn weld_vertices(mut self, enable: bool) -> Self {
+        self.weld_vertices = enable;
+        self
+    }
+
+    /// Sets whether or not to keep faces with more than three vertices as
+    /// polygons at load time.
+    ///
+    /// By default, polygons in OBJ and COLLADA files are triangulated. If set
+    /// to `true`, they are stored in `polygon_indices` and `polygon_offsets`
+    /// of [`Mesh`](crate::Mesh) instead of `faces`, and can be triangulated
+    /// later with [`Mesh::triangulate_polygons`](crate::Mesh::triangulate_polygons).
+    ///
+    /// Default: `false`
+    #[must_use]
+    pub fn keep_polygons(mut self, enable: bool) -> Self {
+        self.keep_polygons = enable;
+        self
+    }
+
+    /// Converts loaded scenes to the given coordinate system.
+    ///
+    /// Scenes loaded from formats that do not define the coordinate system
+    /// (STL, OBJ, PLY, and 3MF) are converted from the coordinate system set
+    /// by [`default_coordinate_system`](Self::default_coordinate_system).
+    /// If it is not set, they are not converted, and `scene.coordinate_system`
+    /// is `None` for them.
+    ///
+    /// Default: not converted
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mesh_loader::{CoordinateSystem, Loader};
+    ///
+    /// // Convert Y-up COLLADA/glTF files to the coordinate system of ROS (Z-up, X-forward).
+    /// let loader = Loader::default().coordinate_system(CoordinateSystem::ROS);
+    /// ```
+    #[must_use]
+    pub fn coordinate_system(mut self, coordinate_system: CoordinateSystem) -> Self {
+        self.coordinate_system = Some(coordinate_system);
+        self
+    }
+
+    /// Sets the coordinate system of files whose format does not define it
+    /// (STL, OBJ, PLY, and 3MF).
+    ///
+    /// `scene.coordinate_system` of such files is set to this, and they are
+    /// converted if [`coordinate_system`](Self::coordinate_system) is also set.
+    ///
+    /// Default: unknown
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mesh_loader::{CoordinateSystem, Loader};
+    ///
+    /// // Assume STL files are Z-up and convert all files to Y-up.
+    /// let loader = Loader::default()
+    ///     .default_coordinate_system(CoordinateSystem::Z_UP)
+    ///     .coordinate_system(CoordinateSystem::Y_UP);
+    /// ```
+    #[must_use]
+    pub fn default_coordinate_system(mut self, coordinate_system: CoordinateSystem) -> Self {
+        self.default_coordinate_system = Some(coordinate_system);
+        self
+    }
+
+    /// Sets how strictly to check that files conform to their format.
+    ///
+    /// See [`Strictness`] for details.
+    ///
+    /// Default: [`Strictness::Lenient`]
+    #[must_use]
+    pub fn strictness(mut self, strictness: Strictness) -> Self {
+        self.strictness = strictness;
+        self
+    }
+
+    /// Sets whether or not to collect non-fatal problems found while loading,
+    /// such as unresolved material references and ignored primitives, into
+    /// `scene.warnings`.
+    ///
+    /// If set to `false`, `scene.warnings` is always empty.
+    ///
+    /// Default: `false`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use mesh_loader::Loader;
+    ///
+    /// let scene = Loader::default().collect_warnings(true).load("a.obj")?;
+    /// for warning in &scene.warnings {
+    ///     eprintln!("warning: {warning}");
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    #[must_use]
+    pub fn collect_warnings(mut self, enable: bool) -> Self {
+        self.collect_warnings = enable;
+        self
+    }
+
+    /// Sets how texture paths referenced by materials are resolved.
+    ///
+    /// See [`TextureResolver`] for details.
+    ///
+    /// Default: [`TextureResolver::default()`]
+    #[must_use]
+    pub fn texture_resolver(mut self, mut resolver: TextureResolver) -> Self {
+        // Keep the resource resolver and package paths set to this loader.
+        resolver.inherit(&mut self.texture_resolver);
+        self.texture_resolver = resolver;
+        self
+    }
+
+    /// Sets the package paths used to resolve ROS `package://` URIs.
+    ///
+    /// `package://` and `file://` URIs are accepted as the path of the file to
+    /// load, and in references to other files, such as OBJ material libraries
+    /// and textures.
+    ///
+    /// See [`PackagePaths`] for details.
+    ///
+    /// Default: [`PackagePaths::new()`] (no packages)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use mesh_loader::{Loader, PackagePaths};
+    ///
+    /// let loader = Loader::default().package_paths(PackagePaths::from_env());
+    /// let scene = loader.load("package://robot_description/meshes/base.dae")?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    #[must_use]
+    pub fn package_paths(mut self, packages: PackagePaths) -> Self {
+        self.texture_resolver.set_packages(packages);
+        self
+    }
+
+    /// Use the given function or closure as a file reader of this loader.
+    ///
+    /// This is a shorthand for [`resource_resolver`](Self::resource_resolver)
+    /// with a resolver that checks the existence of textures on the file system.
     ///
     /// Default: [`std::fs::read`]
     ///
@@ -66,8 +246,32 @@ impl<B: AsRef<[u8]>> Loader<B> {
     /// });
     /// ```
     #[must_use]
-    pub fn custom_reader(mut self, reader: Reader<B>) -> Self {
-        self.reader = reader;
+    pub fn custom_reader<F>(mut self, reader: F) -> Self
+    where
+        F: Fn(&Path) -> io::Result<B> + Send + Sync + 'static,
+    {
+        self.reader = Arc::new(reader);
+        self.texture_resolver.set_exists(None);
+        self
+    }
+
+    /// Use the given resolver to read files and to check the existence of
+    /// textures.
+    ///
+    /// See [`ResourceResolver`] for details.
+    ///
+    /// Default: read files from the file system
+    #[must_use]
+    pub fn resource_resolver<R>(mut self, resolver: R) -> Self
+    where
+        R: ResourceResolver<B> + 'static,
+        B: 'static,
+    {
+        let resolver = Arc::new(resolver);
+        let exists = Arc::clone(&resolver);
+        self.texture_resolver
+            .set_exists(Some(Arc::new(move |path: &Path| exists.exists(path))));
+        self.reader = resolver;
         self
     }
 
@@ -89,74 +293,205 @@ impl<B: AsRef<[u8]>> Loader<B> {
     /// let loader = Loader::with_custom_reader(|path| unsafe { Mmap::map(&File::open(path)?) });
     /// ```
     #[must_use]
-    pub fn with_custom_reader(reader: Reader<B>) -> Self {
+    pub fn with_custom_reader<F>(reader: F) -> Self
+    where
+        F: Fn(&Path) -> io::Result<B> + Send + Sync + 'static,
+    {
         Self {
-            reader,
+            reader: Arc::new(reader),
             merge_meshes: false,
+            weld_vertices: false,
+            keep_polygons: false,
+            coordinate_system: None,
+            default_coordinate_system: None,
+            strictness: Strictness::Lenient,
+            collect_warnings: false,
+            texture_resolver: TextureResolver::default(),
             #[cfg(feature = "stl")]
             stl_parse_color: false,
+            #[cfg(feature = "collada")]
+            collada_pre_transform: true,
         }
     }
 
     pub fn load<P: AsRef<Path>>(&self, path: P) -> io::Result<Scene> {
-        self.load_with_reader(path.as_ref(), self.reader)
+        self.load_with_reader(path.as_ref(), |path| self.reader.read(path))
     }
     pub fn load_with_reader<P: AsRef<Path>, F: FnMut(&Path) -> io::Result<B>>(
         &self,
         path: P,
         mut reader: F,
     ) -> io::Result<Scene> {
-        let path = path.as_ref();
+        let path = &*self.resolve_path(path.as_ref())?;
         self.load_from_slice_with_reader(reader(path)?.as_ref(), path, reader)
     }
     pub fn load_from_slice<P: AsRef<Path>>(&self, bytes: &[u8], path: P) -> io::Result<Scene> {
-        self.load_from_slice_with_reader(bytes, path.as_ref(), self.reader)
+        self.load_from_slice_with_reader(bytes, path.as_ref(), |path| self.reader.read(path))
     }
     pub fn load_from_slice_with_reader<P: AsRef<Path>, F: FnMut(&Path) -> io::Result<B>>(
         &self,
         bytes: &[u8],
         path: P,
+        reader: F,
+    ) -> io::Result<Scene> {
+        let path = &*self.resolve_path(path.as_ref())?;
+        let scene = self.parse_from_slice(bytes, path, reader, &self.texture_resolver)?;
+        Ok(self.post_process(scene))
+    }
+
+    /// Parses a file of any supported format, without post-processing.
+    ///
+    /// Textures are resolved by `textures` instead of `self.texture_resolver`,
+    /// so that files in archives can refer to textures in the same archive.
+    // Depending on the enabled features, `self` may be unused.
+    #[allow(clippy::unused_self)]
+    fn parse_from_slice<R: AsRef<[u8]>, F: FnMut(&Path) -> io::Result<R>>(
+        &self,
+        bytes: &[u8],
+        path: &Path,
         #[allow(unused_variables)] reader: F,
+        #[allow(unused_variables)] textures: &TextureResolver,
     ) -> io::Result<Scene> {
-        let path = path.as_ref();
-        match detect_file_type(path, bytes) {
+        match detect_format(path, bytes) {
             #[cfg(feature = "stl")]
-            FileType::Stl => self.load_stl_from_slice(bytes, path),
+            Some(Format::Stl) => crate::stl::from_slice_internal(
+                bytes,
+                Some(path),
+                self.stl_parse_color,
+                self.is_strict(),
+            ),
             #[cfg(not(feature = "stl"))]
-            FileType::Stl => Err(io::Error::new(
+            Some(Format::Stl) => Err(io::Error::new(
                 io::ErrorKind::Unsupported,
                 "'stl' feature of mesh-loader must be enabled to parse STL file ({path:?})",
             )),
             #[cfg(feature = "collada")]
-            FileType::Collada => self.load_collada_from_slice(bytes, path),
+            Some(Format::Collada) => crate::collada::from_slice_internal(
+                bytes,
+                Some(path),
+                self.is_collada_pre_transform(),
+                self.coordinate_system,
+                self.is_strict(),
+                self.keep_polygons,
+                textures,
+            ),
             #[cfg(not(feature = "collada"))]
-            FileType::Collada => Err(io::Error::new(
+            Some(Format::Collada) => Err(io::Error::new(
                 io::ErrorKind::Unsupported,
                 "'collada' feature of mesh-loader must be enabled to parse COLLADA file ({path:?})",
             )),
             #[cfg(feature = "obj")]
-            FileType::Obj => self.load_obj_from_slice_with_reader(bytes, path, reader),
+            Some(Format::Obj) => crate::obj::from_slice_internal(
+                bytes,
+                Some(path),
+                reader,
+                self.is_strict(),
+                self.keep_polygons,
+                textures,
+            ),
             #[cfg(not(feature = "obj"))]
-            FileType::Obj => Err(io::Error::new(
+            Some(Format::Obj) => Err(io::Error::new(
                 io::ErrorKind::Unsupported,
                 "'obj' feature of mesh-loader must be enabled to parse OBJ file ({path:?})",
             )),
-            FileType::Unknown => Err(io::Error::new(
+            #[cfg(feature = "ply")]
+            Some(Format::Ply) => crate::ply::from_slice_internal(bytes, Some(path)),
+            #[cfg(not(feature = "ply"))]
+            Some(Format::Ply) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "'ply' feature of mesh-loader must be enabled to parse PLY file ({})",
+                    path.display()
+                ),
+            )),
+            #[cfg(feature = "gltf")]
+            Some(Format::Gltf) => {
+                crate::gltf::from_slice_internal(bytes, Some(path), reader, textures)
+            }
+            #[cfg(not(feature = "gltf"))]
+            Some(Format::Gltf) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "'gltf' feature of mesh-loader must be enabled to parse glTF file ({})",
+                    path.display()
+                ),
+            )),
+            #[cfg(feature = "zip")]
+            Some(Format::Zip) => self.parse_zip_from_slice(bytes, path),
+            #[cfg(not(feature = "zip"))]
+            Some(Format::Zip) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "'zip' feature of mesh-loader must be enabled to load zip archive ({})",
+                    path.display()
+                ),
+            )),
+            #[cfg(feature = "gzip")]
+            Some(Format::Gzip) => self.parse_gzip_from_slice(bytes, path, reader, textures),
+            #[cfg(not(feature = "gzip"))]
+            Some(Format::Gzip) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "'gzip' feature of mesh-loader must be enabled to load gzip-compressed file ({})",
+                    path.display()
+                ),
+            )),
+            None => Err(io::Error::new(
                 io::ErrorKind::Unsupported,
                 "unsupported or unrecognized file type {path:?}",
             )),
         }
     }
 
+    #[cfg(feature = "gzip")]
+    fn parse_gzip_from_slice<R: AsRef<[u8]>, F: FnMut(&Path) -> io::Result<R>>(
+        &self,
+        bytes: &[u8],
+        path: &Path,
+        reader: F,
+        textures: &TextureResolver,
+    ) -> io::Result<Scene> {
+        let bytes = crate::utils::gzip::decompress(bytes)?;
+        // Detect the file type from the inner extension (e.g., "a.stl" for
+        // "a.stl.gz") and contents.
+        let path = match path.extension().and_then(OsStr::to_str) {
+            Some("gz" | "GZ") => Cow::Owned(path.with_extension("")),
+            _ => Cow::Borrowed(path),
+        };
+        self.parse_from_slice(&bytes, &path, reader, textures)
+    }
+
     #[cfg(feature = "stl")]
     pub fn load_stl<P: AsRef<Path>>(&self, path: P) -> io::Result<Scene> {
-        let path = path.as_ref();
-        self.load_stl_from_slice((self.reader)(path)?.as_ref(), path)
+        let path = &*self.resolve_path(path.as_ref())?;
+        self.load_stl_from_slice(self.reader.read(path)?.as_ref(), path)
     }
     #[cfg(feature = "stl")]
     pub fn load_stl_from_slice<P: AsRef<Path>>(&self, bytes: &[u8], path: P) -> io::Result<Scene> {
-        let scene =
-            crate::stl::from_slice_internal(bytes, Some(path.as_ref()), self.stl_parse_color)?;
+        let scene = crate::stl::from_slice_internal(
+            bytes,
+            Some(&*self.resolve_path(path.as_ref())?),
+            self.stl_parse_color,
+            self.is_strict(),
+        )?;
+        Ok(self.post_process(scene))
+    }
+    /// Loads binary STL from the given reader.
+    ///
+    /// Unlike [`load_stl`](Self::load_stl), this does not support ASCII STL,
+    /// but builds the mesh incrementally without reading the whole file into
+    /// memory first. This is useful for very large files or for reading from
+    /// stdin.
+    ///
+    /// Note that this reads from `reader` in small chunks, so it is often
+    /// preferable to wrap `reader` with [`io::BufReader`].
+    #[cfg(feature = "stl")]
+    pub fn load_binary_stl_from_reader<R: io::Read>(&self, reader: R) -> io::Result<Scene> {
+        let scene = crate::stl::from_binary_reader_internal(
+            reader,
+            self.stl_parse_color,
+            self.is_strict(),
+        )?;
         Ok(self.post_process(scene))
     }
     #[cfg(feature = "stl")]
@@ -168,8 +503,8 @@ impl<B: AsRef<[u8]>> Loader<B> {
 
     #[cfg(feature = "collada")]
     pub fn load_collada<P: AsRef<Path>>(&self, path: P) -> io::Result<Scene> {
-        let path = path.as_ref();
-        self.load_collada_from_slice((self.reader)(path)?.as_ref(), path)
+        let path = &*self.resolve_path(path.as_ref())?;
+        self.load_collada_from_slice(self.reader.read(path)?.as_ref(), path)
     }
     #[cfg(feature = "collada")]
     pub fn load_collada_from_slice<P: AsRef<Path>>(
@@ -177,17 +512,45 @@ impl<B: AsRef<[u8]>> Loader<B> {
         bytes: &[u8],
         path: P,
     ) -> io::Result<Scene> {
-        let scene = crate::collada::from_slice_internal(bytes, Some(path.as_ref()))?;
+        let scene = crate::collada::from_slice_internal(
+            bytes,
+            Some(&*self.resolve_path(path.as_ref())?),
+            self.is_collada_pre_transform(),
+            self.coordinate_system,
+            self.is_strict(),
+            self.keep_polygons,
+            &self.texture_resolver,
+        )?;
         Ok(self.post_process(scene))
     }
+    /// Sets whether or not to apply node transforms to vertices at load time.
+    ///
+    /// If set to `false`, vertices are kept in the local space of the geometry,
+    /// and the transform of each node is available in `scene.nodes`.
+    ///
+    /// If set to `true`, vertices of each mesh are transformed to the world space
+    /// of the node that instantiates the mesh, so node transforms in `scene.nodes`
+    /// must not be applied again. Skinned meshes are transformed to world space
+    /// in the bind pose, and the offset matrices of their bones are adjusted
+    /// accordingly.
+    ///
+    /// This is always `true` if [`merge_meshes`](Self::merge_meshes) is enabled.
+    ///
+    /// Default: `true`
+    #[cfg(feature = "collada")]
+    #[must_use]
+    pub fn collada_pre_transform(mut self, enable: bool) -> Self {
+        self.collada_pre_transform = enable;
+        self
+    }
 
     #[cfg(feature = "obj")]
     pub fn load_obj<P: AsRef<Path>>(&self, path: P) -> io::Result<Scene> {
-        self.load_obj_with_reader(path.as_ref(), self.reader)
+        self.load_obj_with_reader(path.as_ref(), |path| self.reader.read(path))
     }
     #[cfg(feature = "obj")]
     pub fn load_obj_from_slice<P: AsRef<Path>>(&self, bytes: &[u8], path: P) -> io::Result<Scene> {
-        self.load_obj_from_slice_with_reader(bytes, path.as_ref(), self.reader)
+        self.load_obj_from_slice_with_reader(bytes, path.as_ref(), |path| self.reader.read(path))
     }
     #[cfg(feature = "obj")]
     pub fn load_obj_with_reader<P: AsRef<Path>, F: FnMut(&Path) -> io::Result<B>>(
@@ -195,7 +558,7 @@ impl<B: AsRef<[u8]>> Loader<B> {
         path: P,
         mut reader: F,
     ) -> io::Result<Scene> {
-        let path = path.as_ref();
+        let path = &*self.resolve_path(path.as_ref())?;
         self.load_obj_from_slice_with_reader(reader(path)?.as_ref(), path, reader)
     }
     #[cfg(feature = "obj")]
@@ -205,16 +568,159 @@ impl<B: AsRef<[u8]>> Loader<B> {
         path: P,
         reader: F,
     ) -> io::Result<Scene> {
-        let scene = crate::obj::from_slice(bytes, Some(path.as_ref()), reader)?;
+        let scene = crate::obj::from_slice_internal(
+            bytes,
+            Some(&*self.resolve_path(path.as_ref())?),
+            reader,
+            self.is_strict(),
+            self.keep_polygons,
+            &self.texture_resolver,
+        )?;
+        Ok(self.post_process(scene))
+    }
+
+    #[cfg(feature = "ply")]
+    pub fn load_ply<P: AsRef<Path>>(&self, path: P) -> io::Result<Scene> {
+        let path = &*self.resolve_path(path.as_ref())?;
+        self.load_ply_from_slice(self.reader.read(path)?.as_ref(), path)
+    }
+    #[cfg(feature = "ply")]
+    pub fn load_ply_from_slice<P: AsRef<Path>>(&self, bytes: &[u8], path: P) -> io::Result<Scene> {
+        let scene =
+            crate::ply::from_slice_internal(bytes, Some(&*self.resolve_path(path.as_ref())?))?;
+        Ok(self.post_process(scene))
+    }
+
+    #[cfg(feature = "gltf")]
+    pub fn load_gltf<P: AsRef<Path>>(&self, path: P) -> io::Result<Scene> {
+        self.load_gltf_with_reader(path.as_ref(), |path| self.reader.read(path))
+    }
+    #[cfg(feature = "gltf")]
+    pub fn load_gltf_from_slice<P: AsRef<Path>>(&self, bytes: &[u8], path: P) -> io::Result<Scene> {
+        self.load_gltf_from_slice_with_reader(bytes, path.as_ref(), |path| self.reader.read(path))
+    }
+    #[cfg(feature = "gltf")]
+    pub fn load_gltf_with_reader<P: AsRef<Path>, F: FnMut(&Path) -> io::Result<B>>(
+        &self,
+        path: P,
+        mut reader: F,
+    ) -> io::Result<Scene> {
+        let path = &*self.resolve_path(path.as_ref())?;
+        self.load_gltf_from_slice_with_reader(reader(path)?.as_ref(), path, reader)
+    }
+    #[cfg(feature = "gltf")]
+    pub fn load_gltf_from_slice_with_reader<P: AsRef<Path>, F: FnMut(&Path) -> io::Result<B>>(
+        &self,
+        bytes: &[u8],
+        path: P,
+        reader: F,
+    ) -> io::Result<Scene> {
+        let scene = crate::gltf::from_slice_internal(
+            bytes,
+            Some(&*self.resolve_path(path.as_ref())?),
+            reader,
+            &self.texture_resolver,
+        )?;
+        Ok(self.post_process(scene))
+    }
+
+    /// Loads the model in a zip archive.
+    ///
+    /// The loaded document is the one listed in `manifest.xml` for COLLADA
+    /// archives (.zae), the model listed in `_rels/.rels` for 3MF (.3mf), and
+    /// otherwise the model file closest to the root of the archive. Files it
+    /// refers to, such as OBJ material libraries, glTF buffers, and textures,
+    /// are read from the archive, and textures found in the archive are stored
+    /// in [`Scene::embedded_textures`](crate::Scene::embedded_textures).
+    ///
+    /// Entries must be stored or deflate-compressed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use mesh_loader::Loader;
+    ///
+    /// let bytes = std::fs::read("robot.zip")?;
+    /// let scene = Loader::default().load_zip_from_slice(&bytes, "robot.zip")?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    #[cfg(feature = "zip")]
+    pub fn load_zip<P: AsRef<Path>>(&self, path: P) -> io::Result<Scene> {
+        let path = &*self.resolve_path(path.as_ref())?;
+        self.load_zip_from_slice(self.reader.read(path)?.as_ref(), path)
+    }
+    #[cfg(feature = "zip")]
+    pub fn load_zip_from_slice<P: AsRef<Path>>(&self, bytes: &[u8], path: P) -> io::Result<Scene> {
+        let path = &*self.resolve_path(path.as_ref())?;
+        let scene = self.parse_zip_from_slice(bytes, path)?;
         Ok(self.post_process(scene))
     }
+    #[cfg(feature = "zip")]
+    fn parse_zip_from_slice(&self, bytes: &[u8], path: &Path) -> io::Result<Scene> {
+        let archive = crate::archive::Archive::new(bytes, path)?;
+        let root = &archive.root()?;
+        let bytes = &*archive.read(root)?;
+        let mut scene = match root.extension().and_then(OsStr::to_str) {
+            Some(ext) if ext.eq_ignore_ascii_case("model") => {
+                crate::archive::threemf::from_slice(bytes, Some(root))?
+            }
+            _ => {
+                // Look up textures and other files in the archive.
+                let mut textures = self.texture_resolver.clone();
+                textures.set_exists(Some(archive.exists_fn()));
+                let reader = |path: &Path| archive.read(path).map(Cow::into_owned);
+                self.parse_from_slice(bytes, root, reader, &textures)?
+            }
+        };
+        archive.embed_textures(&mut scene)?;
+        Ok(scene)
+    }
+
+    /// Converts `package://` and `file://` URIs to paths.
+    fn resolve_path<'a>(&self, path: &'a Path) -> io::Result<Cow<'a, Path>> {
+        match self.texture_resolver.uri_to_path(path) {
+            Some(path) => path.map(Cow::Owned),
+            None => Ok(Cow::Borrowed(path)),
+        }
+    }
 
     #[cfg(any(feature = "collada", feature = "obj", feature = "stl"))]
+    fn is_strict(&self) -> bool {
+        self.strictness == Strictness::Strict
+    }
+
+    // Merged meshes lose their nodes, so they must be in world space.
+    #[cfg(feature = "collada")]
+    fn is_collada_pre_transform(&self) -> bool {
+        self.collada_pre_transform || self.merge_meshes
+    }
+
     fn post_process(&self, mut scene: Scene) -> Scene {
-        if self.merge_meshes && scene.meshes.len() != 1 {
-            scene.meshes = vec![crate::Mesh::merge(scene.meshes)];
-            // TODO
-            scene.materials = vec![crate::Material::default()];
+        if !self.collect_warnings {
+            scene.warnings.clear();
+        }
+        if scene.coordinate_system.is_none() {
+            scene.coordinate_system = self.default_coordinate_system;
+        }
+        if let Some(coordinate_system) = self.coordinate_system {
+            scene.convert_coordinate_system(coordinate_system);
+        }
+        if self.merge_meshes {
+            if scene.meshes.len() != 1 {
+                scene.meshes = vec![crate::Mesh::merge(scene.meshes)];
+                // TODO
+                scene.materials = vec![crate::Material::default()];
+            }
+            scene.nodes.clear();
+            scene.animations.clear();
+            for bone in scene.meshes.iter_mut().flat_map(|m| &mut m.bones) {
+                bone.node = None;
+            }
+        }
+        if self.weld_vertices {
+            for mesh in &mut scene.meshes {
+                mesh.weld_vertices();
+            }
         }
         scene
     }
@@ -224,45 +730,115 @@ impl fmt::Debug for Loader {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut d = f.debug_struct("Loader");
         d.field("merge_meshes", &self.merge_meshes);
+        d.field("weld_vertices", &self.weld_vertices);
+        d.field("keep_polygons", &self.keep_polygons);
+        d.field("coordinate_system", &self.coordinate_system);
+        d.field("default_coordinate_system", &self.default_coordinate_system);
+        d.field("strictness", &self.strictness);
+        d.field("collect_warnings", &self.collect_warnings);
+        d.field("texture_resolver", &self.texture_resolver);
         #[cfg(feature = "stl")]
         d.field("stl_parse_color", &self.stl_parse_color);
+        #[cfg(feature = "collada")]
+        d.field("collada_pre_transform", &self.collada_pre_transform);
         d.finish_non_exhaustive()
     }
 }
 
-enum FileType {
-    Stl,
-    Collada,
-    Obj,
-    Unknown,
+/// How strictly to check that files conform to their format.
+///
+/// See [`Loader::strictness`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Strictness {
+    /// Accept common deviations from the specification that can be handled
+    /// unambiguously, such as bogus triangle count in binary STL, stray
+    /// newline after binary STL, unknown statements in OBJ and MTL, and wrong
+    /// `count` of `<lines>` in COLLADA.
+    Lenient,
+    /// Reject files that do not conform to the specification, instead of
+    /// working around them.
+    Strict,
 }
 
-fn detect_file_type(path: &Path, bytes: &[u8]) -> FileType {
-    match path.extension().and_then(OsStr::to_str) {
-        Some("stl" | "STL") => return FileType::Stl,
-        Some("dae" | "DAE") => return FileType::Collada,
-        Some("obj" | "OBJ") => return FileType::Obj,
-        _ => {}
-    }
-    // Fallback: If failed to detect file type from extension,
-    // read the first 1024 bytes to detect the file type.
-    // TODO: rewrite based on what assimp does.
-    let mut s = &bytes[..cmp::min(bytes.len(), 1024)];
-    while let Some((&c, s_next)) = s.split_first() {
-        match c {
-            b's' => {
-                if starts_with(s, b"solid") {
-                    return FileType::Stl;
-                }
-            }
-            b'<' => {
-                if starts_with(s, b"<COLLADA") {
-                    return FileType::Collada;
-                }
-            }
-            _ => {}
-        }
-        s = s_next;
+impl Default for Strictness {
+    fn default() -> Self {
+        Self::Lenient
+    }
+}
+
+#[cfg(all(test, any(feature = "obj", feature = "stl")))]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "stl")]
+    #[test]
+    fn strict_stl() {
+        let mut scene = Scene::default();
+        scene.meshes.push(crate::Mesh {
+            vertices: vec![[0., 0., 0.], [1., 0., 0.], [1., 1., 0.]],
+            faces: vec![[0, 1, 2]],
+            ..Default::default()
+        });
+        let mut bytes = crate::stl::to_vec(&scene, crate::stl::WriteOptions::new()).unwrap();
+        // bogus triangle count
+        bytes[80] = 2;
+        let lenient = Loader::default();
+        let strict = Loader::default().strictness(Strictness::Strict);
+        assert_eq!(
+            lenient.load_stl_from_slice(&bytes, "a.stl").unwrap().meshes[0]
+                .faces
+                .len(),
+            1
+        );
+        strict.load_stl_from_slice(&bytes, "a.stl").unwrap_err();
+        strict.load_binary_stl_from_reader(&bytes[..]).unwrap_err();
+    }
+
+    #[cfg(feature = "obj")]
+    #[test]
+    fn strict_obj() {
+        let lenient = Loader::default();
+        let strict = Loader::default().strictness(Strictness::Strict);
+        let load = |loader: &Loader, obj: &str, mtl: &str| {
+            let mtl = mtl.as_bytes().to_vec();
+            loader.load_obj_from_slice_with_reader(obj.as_bytes(), "a.obj", |_| Ok(mtl.clone()))
+        };
+        let obj = "mtllib a.mtl\nv 0 0 0\nv 1 0 0\nv 1 1 0\nusemtl a\nf 1 2 3\n";
+        let mtl = "newmtl a\nKd 1 0 0\n";
+        load(&strict, obj, mtl).unwrap();
+
+        // unknown OBJ keyword
+        let unknown_obj = format!("{obj}foo 1 2 3\n");
+        load(&lenient, &unknown_obj, mtl).unwrap();
+        load(&strict, &unknown_obj, mtl).unwrap_err();
+        // unknown MTL keyword
+        let unknown_mtl = format!("{mtl}foo 1 2 3\n");
+        let scene = load(&lenient, obj, &unknown_mtl).unwrap();
+        assert_eq!(scene.materials[0].color.diffuse, Some([1., 0., 0., 1.]));
+        load(&strict, obj, &unknown_mtl).unwrap_err();
+    }
+
+    #[cfg(feature = "obj")]
+    #[test]
+    fn default_coordinate_system() {
+        let obj = b"v 1 2 3\nv 1 0 0\nv 0 1 0\nf 1 2 3\n";
+        let scene = Loader::default()
+            .coordinate_system(CoordinateSystem::Y_UP)
+            .load_obj_from_slice(obj, "a.obj")
+            .unwrap();
+        assert_eq!(scene.coordinate_system, None);
+        assert_eq!(scene.meshes[0].vertices[0], [1., 2., 3.]);
+
+        let loader = Loader::default().default_coordinate_system(CoordinateSystem::Z_UP);
+        let scene = loader.load_obj_from_slice(obj, "a.obj").unwrap();
+        assert_eq!(scene.coordinate_system, Some(CoordinateSystem::Z_UP));
+        assert_eq!(scene.meshes[0].vertices[0], [1., 2., 3.]);
+        let scene = loader
+            .coordinate_system(CoordinateSystem::Y_UP)
+            .load_obj_from_slice(obj, "a.obj")
+            .unwrap();
+        assert_eq!(scene.coordinate_system, Some(CoordinateSystem::Y_UP));
+        assert_eq!(scene.meshes[0].vertices[0], [1., 3., -2.]);
     }
-    FileType::Unknown
 }
@@ -1,5 +1,7 @@
 use std::{fmt, io, path::Path, str};
 
+use crate::error::{Error, Format, Location};
+
 #[cfg_attr(test, derive(Debug))]
 pub(super) enum ErrorKind {
     ExpectedSpace(&'static str, usize),
@@ -10,28 +12,46 @@ pub(super) enum ErrorKind {
     InvalidW(usize),
     InvalidFaceIndex(usize),
     Oob(usize, usize),
+    UnknownStatement(usize),
     Io(io::Error),
 }
 
 impl ErrorKind {
+    /// Adds the number of bytes after the part of the text where this error
+    /// occurred to the error position.
+    #[cfg(feature = "parallel")]
+    pub(super) fn add_remaining(&mut self, n: usize) {
+        match self {
+            Self::Expected(.., remaining)
+            | Self::ExpectedNewline(.., remaining)
+            | Self::ExpectedSpace(.., remaining)
+            | Self::Float(remaining)
+            | Self::Int(remaining)
+            | Self::InvalidW(remaining)
+            | Self::InvalidFaceIndex(remaining)
+            | Self::Oob(.., remaining)
+            | Self::UnknownStatement(remaining) => *remaining += n,
+            Self::Io(_) => {}
+        }
+    }
+
     #[cold]
     #[inline(never)]
     pub(super) fn into_io_error(self, start: &[u8], path: Option<&Path>) -> io::Error {
-        let remaining = match self {
-            Self::Expected(.., n)
-            | Self::ExpectedNewline(.., n)
-            | Self::ExpectedSpace(.., n)
-            | Self::Float(n)
-            | Self::Int(n)
-            | Self::InvalidW(n)
-            | Self::InvalidFaceIndex(n)
-            | Self::Oob(.., n) => n,
+        let (kind, remaining) = match self {
+            Self::Expected(.., n) | Self::ExpectedNewline(.., n) | Self::ExpectedSpace(.., n) => {
+                (crate::ErrorKind::Syntax, n)
+            }
+            Self::Float(n) => (crate::ErrorKind::InvalidFloat, n),
+            Self::Int(n) => (crate::ErrorKind::InvalidInt, n),
+            Self::InvalidW(n) => (crate::ErrorKind::Other, n),
+            Self::InvalidFaceIndex(n) => (crate::ErrorKind::InvalidFaceIndex, n),
+            Self::Oob(.., n) => (crate::ErrorKind::FaceIndexOutOfBounds, n),
+            Self::UnknownStatement(n) => (crate::ErrorKind::Unsupported, n),
             Self::Io(e) => return e,
         };
-        crate::error::with_location(
-            &crate::error::invalid_data(self.to_string()),
-            &crate::error::Location::find(remaining, start, path),
-        )
+        let e = Error::new(kind, self.to_string()).with_format(Format::Obj);
+        Location::find(remaining, start, path).attach(e).into()
     }
 }
 
@@ -47,6 +67,7 @@ impl fmt::Display for ErrorKind {
             Self::Float(..) => f.write_str("error while parsing a float"),
             Self::Int(..) => f.write_str("error while parsing an integer"),
             Self::Oob(i, ..) => write!(f, "face index out of bounds ({i})"),
+            Self::UnknownStatement(..) => f.write_str("unknown statement"),
             Self::Io(ref e) => fmt::Display::fmt(e, f),
         }
     }
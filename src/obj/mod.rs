@@ -1,47 +1,107 @@
-//! [Wavefront OBJ] (.obj) parser.
+//! [Wavefront OBJ] (.obj) parser and writer.
 //!
 //! [Wavefront OBJ]: https://en.wikipedia.org/wiki/Wavefront_.obj_file
 
 #![allow(clippy::collapsible_if, clippy::many_single_char_names)]
 
 mod error;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod write;
 
-use std::{
-    collections::HashMap,
-    io, mem,
-    path::{Path, PathBuf},
-    str,
-};
+use std::{collections::HashMap, io, iter, mem, path::Path, str};
 
 use self::error::ErrorKind;
+pub use self::write::{mtl_to_writer, to_writer, write};
 use crate::{
     common,
     utils::{
         bytes::{from_utf8_lossy, memchr_naive, memchr_naive_table, path_from_bytes, starts_with},
         float, int,
+        triangulate::triangulate,
         utf16::decode_bytes,
     },
-    Color4, Mesh, Scene, ShadingModel, Vec2, Vec3,
+    Color4, Mesh, Scene, ShadingModel, TextureResolver, Vec2, Vec3, Warning, WarningKind,
 };
 
 /// Parses meshes from bytes of Wavefront OBJ text.
+#[inline]
 pub fn from_slice<B: AsRef<[u8]>, F: FnMut(&Path) -> io::Result<B>>(
+    bytes: &[u8],
+    path: Option<&Path>,
+    reader: F,
+) -> io::Result<Scene> {
+    from_slice_internal(
+        bytes,
+        path,
+        reader,
+        false,
+        false,
+        &TextureResolver::default(),
+    )
+}
+
+pub(crate) fn from_slice_internal<B: AsRef<[u8]>, F: FnMut(&Path) -> io::Result<B>>(
     bytes: &[u8],
     path: Option<&Path>,
     mut reader: F,
+    strict: bool,
+    keep_polygons: bool,
+    textures: &TextureResolver,
 ) -> io::Result<Scene> {
     // If it is UTF-16 with BOM, it is converted to UTF-8, otherwise it is parsed as bytes.
     // We don't require UTF-8 here, as we want to support files that are partially non-UTF-8 like:
     // https://github.com/assimp/assimp/blob/v5.3.1/test/models/OBJ/regr01.mtl#L67
     let bytes = &decode_bytes(bytes)?;
-    match read_obj(bytes, path, &mut |path, materials, material_map| {
-        match reader(path) {
-            Ok(bytes) => read_mtl(bytes.as_ref(), Some(path), materials, material_map),
-            // ignore reader error for now
-            // TODO: logging?
-            Err(_e) => Ok(()),
+    let mut warnings = vec![];
+    let reader = &mut |mtl_path: &Path,
+                       materials: &mut _,
+                       material_map: &mut _,
+                       warnings: &mut Vec<Warning>| {
+        let resolved;
+        let mtl_path = match textures.uri_to_path(mtl_path) {
+            Some(Ok(p)) => {
+                resolved = p;
+                &*resolved
+            }
+            Some(Err(e)) => {
+                warnings.push(Warning::new(
+                    WarningKind::MissingMaterialLibrary,
+                    format!(
+                        "failed to resolve material library {}: {e}",
+                        mtl_path.display()
+                    ),
+                    path,
+                ));
+                return Ok(());
+            }
+            None => mtl_path,
+        };
+        match reader(mtl_path) {
+            Ok(bytes) => read_mtl_with_options(
+                bytes.as_ref(),
+                Some(mtl_path),
+                materials,
+                material_map,
+                strict,
+                textures,
+                warnings,
+            ),
+            // Missing material library is not fatal; meshes use the default material.
+            Err(e) => {
+                warnings.push(Warning::new(
+                    WarningKind::MissingMaterialLibrary,
+                    format!(
+                        "failed to read material library {}: {e}",
+                        mtl_path.display()
+                    ),
+                    path,
+                ));
+                Ok(())
+            }
         }
-    }) {
+    };
+    match read_obj(bytes, path, reader, &mut warnings, strict, keep_polygons) {
         Ok((meshes, materials)) => {
             let materials = meshes
                 .iter()
@@ -52,7 +112,12 @@ pub fn from_slice<B: AsRef<[u8]>, F: FnMut(&Path) -> io::Result<B>>(
                         .unwrap_or_default()
                 })
                 .collect();
-            Ok(Scene { materials, meshes })
+            Ok(Scene {
+                materials,
+                meshes,
+                warnings,
+                ..Default::default()
+            })
         }
         Err(e) => Err(e.into_io_error(bytes, path)),
     }
@@ -62,36 +127,102 @@ pub fn from_slice<B: AsRef<[u8]>, F: FnMut(&Path) -> io::Result<B>>(
 // OBJ
 
 fn read_obj(
-    mut s: &[u8],
+    s: &[u8],
     obj_path: Option<&Path>,
     reader: &mut dyn FnMut(
         &Path,
         &mut Vec<common::Material>,
         &mut HashMap<Vec<u8>, u32>,
+        &mut Vec<Warning>,
     ) -> io::Result<()>,
+    warnings: &mut Vec<Warning>,
+    strict: bool,
+    keep_polygons: bool,
 ) -> Result<(Vec<Mesh>, Vec<common::Material>), ErrorKind> {
-    let mut meshes = Vec::with_capacity(1); // TODO: right default capacity?
+    #[cfg(feature = "parallel")]
+    let chunks = parallel::read_obj_chunks(s, strict);
+    #[cfg(not(feature = "parallel"))]
+    let chunks = vec![read_obj_chunk(s, [0; 3], strict)];
+    merge_obj_chunks(chunks, obj_path, reader, warnings, keep_polygons)
+}
+
+/// The result of parsing a part of OBJ text.
+///
+/// Statements that depend on the state of the preceding parts (`g`, `usemtl`,
+/// and `mtllib`) are recorded and applied in order by [`merge_obj_chunks`].
+struct ObjChunk<'a> {
+    vertices: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    texcoords: Vec<Vec2>,
+    colors: Vec<Vec3>,
+    /// The number of vertices before the first vertex with color.
+    colors_start: usize,
+    faces: Vec<Face>,
+    /// Statements, the number of faces before each statement, and the number
+    /// of vertices, texcoords, normals, and colors before each statement.
+    statements: Vec<(usize, [usize; 4], Statement<'a>)>,
+    error: Option<ErrorKind>,
+}
+
+enum Statement<'a> {
+    Group(&'a [u8]),
+    Usemtl(&'a [u8]),
+    Mtllib(&'a [u8]),
+}
 
+/// Parses a part of OBJ text that starts at the beginning of a line.
+///
+/// `base` is the number of vertices, texcoords, and normals in the preceding
+/// parts, which is used to resolve relative face indices.
+fn read_obj_chunk(s: &[u8], base: [usize; 3], strict: bool) -> ObjChunk<'_> {
+    let mut chunk = ObjChunk {
+        vertices: vec![],
+        normals: vec![],
+        texcoords: vec![],
+        colors: vec![],
+        colors_start: 0,
+        faces: vec![],
+        statements: vec![],
+        error: None,
+    };
+    if let Err(e) = read_obj_statements(s, base, &mut chunk, strict) {
+        chunk.error = Some(e);
+    }
+    chunk
+}
+
+fn read_obj_statements<'a>(
+    mut s: &'a [u8],
+    base: [usize; 3],
+    chunk: &mut ObjChunk<'a>,
+    strict: bool,
+) -> Result<(), ErrorKind> {
     // TODO: use with_capacity
-    let mut vertices = vec![];
-    let mut normals = vec![];
-    let mut texcoords = vec![];
-    let mut colors = vec![];
+    let ObjChunk {
+        vertices,
+        normals,
+        texcoords,
+        colors,
+        colors_start,
+        faces,
+        statements,
+        ..
+    } = chunk;
     let mut face = Vec::with_capacity(3);
-    let mut faces: Vec<Face> = vec![];
-    let mut current_group: &[u8] = b"default";
-    let mut current_material: &[u8] = &[];
-    let mut materials = vec![];
-    let mut material_map = HashMap::new();
 
     while let Some((&c, s_next)) = s.split_first() {
+        let line = s;
         match c {
             b'v' => {
                 s = s_next;
                 match s.first() {
                     Some(b' ' | b'\t') => {
                         skip_spaces(&mut s);
-                        read_v(&mut s, &mut vertices, &mut colors)?;
+                        let has_colors = !colors.is_empty();
+                        read_v(&mut s, vertices, colors)?;
+                        if !has_colors && !colors.is_empty() {
+                            *colors_start = vertices.len() - 1;
+                        }
                         if !colors.is_empty() && colors.len() < vertices.len() {
                             colors.resize(vertices.len(), [0.; 3]);
                         }
@@ -100,14 +231,14 @@ fn read_obj(
                     Some(b'n') => {
                         s = &s[1..];
                         if skip_spaces(&mut s) {
-                            read_vn(&mut s, &mut normals)?;
+                            read_vn(&mut s, normals)?;
                             continue;
                         }
                     }
                     Some(b't') => {
                         s = &s[1..];
                         if skip_spaces(&mut s) {
-                            read_vt(&mut s, &mut texcoords)?;
+                            read_vt(&mut s, texcoords)?;
                             continue;
                         }
                     }
@@ -119,7 +250,40 @@ fn read_obj(
                 s = s_next;
                 if skip_spaces(&mut s) {
                     read_f(
-                        &mut s, &mut faces, &mut face, &vertices, &texcoords, &normals,
+                        &mut s,
+                        faces,
+                        &mut face,
+                        base[0] + vertices.len(),
+                        base[1] + texcoords.len(),
+                        base[2] + normals.len(),
+                    )?;
+                    continue;
+                }
+            }
+            b'l' => {
+                s = s_next;
+                if skip_spaces(&mut s) {
+                    read_l(
+                        &mut s,
+                        faces,
+                        &mut face,
+                        base[0] + vertices.len(),
+                        base[1] + texcoords.len(),
+                        base[2] + normals.len(),
+                    )?;
+                    continue;
+                }
+            }
+            b'p' => {
+                s = s_next;
+                if skip_spaces(&mut s) {
+                    read_p(
+                        &mut s,
+                        faces,
+                        &mut face,
+                        base[0] + vertices.len(),
+                        base[1] + texcoords.len(),
+                        base[2] + normals.len(),
                     )?;
                     continue;
                 }
@@ -129,20 +293,11 @@ fn read_obj(
                 if token(&mut s, &b"usemtl"[1..]) {
                     if skip_spaces(&mut s) {
                         let (name, s_next) = name(s);
-                        if name != current_material {
-                            let material_index = material_map.get(current_material).copied();
-                            push_mesh(
-                                &mut meshes,
-                                &mut faces,
-                                &vertices,
-                                &texcoords,
-                                &normals,
-                                &colors,
-                                current_group,
-                                material_index,
-                            )?;
-                            current_material = name;
-                        }
+                        statements.push((
+                            faces.len(),
+                            [vertices.len(), texcoords.len(), normals.len(), colors.len()],
+                            Statement::Usemtl(name),
+                        ));
                         s = s_next;
                         continue;
                     }
@@ -153,20 +308,11 @@ fn read_obj(
                 if token(&mut s, &b"mtllib"[1..]) {
                     if skip_spaces(&mut s) {
                         let (path, s_next) = name(s);
-                        let path = if path.is_empty() {
-                            None
-                        } else {
-                            path_from_bytes(path).ok()
-                        };
-                        if let Some(path) = path {
-                            match obj_path.and_then(Path::parent) {
-                                Some(parent) => {
-                                    reader(&parent.join(path), &mut materials, &mut material_map)
-                                        .map_err(ErrorKind::Io)?;
-                                }
-                                None => {} // ignored
-                            }
-                        }
+                        statements.push((
+                            faces.len(),
+                            [vertices.len(), texcoords.len(), normals.len(), colors.len()],
+                            Statement::Mtllib(path),
+                        ));
                         s = s_next;
                         continue;
                     }
@@ -176,36 +322,181 @@ fn read_obj(
             b'g' => {
                 s = s_next;
                 if skip_spaces(&mut s) {
-                    let (mut name, s_next) = name(s);
+                    let (name, s_next) = name(s);
+                    statements.push((
+                        faces.len(),
+                        [vertices.len(), texcoords.len(), normals.len(), colors.len()],
+                        Statement::Group(name),
+                    ));
+                    s = s_next;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        // ignore comment, s, mg, o, or other unknown
+        if strict && !is_known_statement(line, OBJ_KEYWORDS) {
+            return Err(ErrorKind::UnknownStatement(line.len()));
+        }
+        skip_any_until_line(&mut s);
+    }
+
+    Ok(())
+}
+
+/// Applies statements of the chunks in order, and builds meshes.
+fn merge_obj_chunks(
+    mut chunks: Vec<ObjChunk<'_>>,
+    obj_path: Option<&Path>,
+    reader: &mut dyn FnMut(
+        &Path,
+        &mut Vec<common::Material>,
+        &mut HashMap<Vec<u8>, u32>,
+        &mut Vec<Warning>,
+    ) -> io::Result<()>,
+    warnings: &mut Vec<Warning>,
+    keep_polygons: bool,
+) -> Result<(Vec<Mesh>, Vec<common::Material>), ErrorKind> {
+    let mut meshes = Vec::with_capacity(1); // TODO: right default capacity?
+
+    // The number of vertices, texcoords, normals, and colors before each chunk.
+    let mut bases = Vec::with_capacity(chunks.len());
+    let mut base = [0; 4];
+    // Colors are concatenated in the same way as when the file is read in one
+    // chunk: once a vertex with color is found, colors are padded to the
+    // number of vertices.
+    let mut colors: Vec<Vec3> = vec![];
+    for chunk in &mut chunks {
+        base[3] = colors.len();
+        bases.push(base);
+        if !colors.is_empty() {
+            // This chunk was read without the colors of the preceding chunks,
+            // so the padding before its first vertex with color is after it.
+            if let Some(c) = chunk.colors.get_mut(..=chunk.colors_start) {
+                c.rotate_left(1);
+            }
+            chunk.colors.resize(chunk.vertices.len(), [0.; 3]);
+            colors.extend_from_slice(&chunk.colors);
+        } else if !chunk.colors.is_empty() {
+            colors = mem::take(&mut chunk.colors);
+            colors.splice(1..1, iter::repeat([0.; 3]).take(base[0]));
+        }
+        base[0] += chunk.vertices.len();
+        base[1] += chunk.texcoords.len();
+        base[2] += chunk.normals.len();
+    }
+    let vertices = concat(chunks.iter_mut().map(|c| &mut c.vertices));
+    let normals = concat(chunks.iter_mut().map(|c| &mut c.normals));
+    let texcoords = concat(chunks.iter_mut().map(|c| &mut c.texcoords));
+    let mut faces: Vec<Face> = vec![];
+    let mut current_group: &[u8] = b"default";
+    let mut current_material: &[u8] = &[];
+    let mut materials = vec![];
+    let mut material_map = HashMap::new();
+
+    for (chunk, base) in chunks.into_iter().zip(bases) {
+        let mut chunk_faces = chunk.faces.into_iter();
+        let mut num_faces = 0;
+        for (n, counts, statement) in chunk.statements {
+            faces.extend(chunk_faces.by_ref().take(n - num_faces));
+            num_faces = n;
+            // Meshes are built from the vertices before the statement.
+            let num_vertices = base[0] + counts[0];
+            let num_colors = if base[3] == 0 && counts[3] == 0 {
+                0
+            } else {
+                num_vertices
+            };
+            let vertices = &vertices[..num_vertices];
+            let texcoords = &texcoords[..base[1] + counts[1]];
+            let normals = &normals[..base[2] + counts[2]];
+            let colors = &colors[..num_colors];
+            match statement {
+                Statement::Usemtl(name) => {
+                    if name != current_material {
+                        let material_index =
+                            material_index(&material_map, current_material, obj_path, warnings);
+                        push_mesh(
+                            &mut meshes,
+                            &mut faces,
+                            vertices,
+                            texcoords,
+                            normals,
+                            colors,
+                            current_group,
+                            material_index,
+                            keep_polygons,
+                        )?;
+                        current_material = name;
+                    }
+                }
+                Statement::Mtllib(path) => {
+                    let path = if path.is_empty() {
+                        None
+                    } else {
+                        path_from_bytes(path).ok()
+                    };
+                    if let Some(path) = path {
+                        // URIs are resolved by the reader.
+                        let is_uri = path.to_str().map_or(false, |p| {
+                            p.starts_with("package://") || p.starts_with("file://")
+                        });
+                        match obj_path.and_then(Path::parent) {
+                            _ if is_uri => {
+                                reader(path, &mut materials, &mut material_map, warnings)
+                                    .map_err(ErrorKind::Io)?;
+                            }
+                            Some(parent) => {
+                                reader(
+                                    &parent.join(path),
+                                    &mut materials,
+                                    &mut material_map,
+                                    warnings,
+                                )
+                                .map_err(ErrorKind::Io)?;
+                            }
+                            None => warnings.push(Warning::new(
+                                WarningKind::MissingMaterialLibrary,
+                                format!(
+                                    "cannot resolve material library {} without file path",
+                                    path.display()
+                                ),
+                                obj_path,
+                            )),
+                        }
+                    }
+                }
+                Statement::Group(mut name) => {
                     if name.is_empty() {
                         name = b"default";
                     }
                     if name != current_group {
-                        let material_index = material_map.get(current_material).copied();
+                        let material_index =
+                            material_index(&material_map, current_material, obj_path, warnings);
                         push_mesh(
                             &mut meshes,
                             &mut faces,
-                            &vertices,
-                            &texcoords,
-                            &normals,
-                            &colors,
+                            vertices,
+                            texcoords,
+                            normals,
+                            colors,
                             current_group,
                             material_index,
+                            keep_polygons,
                         )?;
                         current_material = &[];
                         current_group = name;
                     }
-                    s = s_next;
-                    continue;
                 }
             }
-            _ => {}
         }
-        // ignore comment, p, l, s, mg, o, or other unknown
-        skip_any_until_line(&mut s);
+        faces.extend(chunk_faces);
+        if let Some(e) = chunk.error {
+            return Err(e);
+        }
     }
 
-    let material_index = material_map.get(current_material).copied();
+    let material_index = material_index(&material_map, current_material, obj_path, warnings);
     push_mesh(
         &mut meshes,
         &mut faces,
@@ -215,11 +506,87 @@ fn read_obj(
         &colors,
         current_group,
         material_index,
+        keep_polygons,
     )?;
 
     Ok((meshes, materials))
 }
 
+/// Returns the index of the material with the given name, or `None` if no
+/// material is used or the material is not found.
+fn material_index(
+    material_map: &HashMap<Vec<u8>, u32>,
+    name: &[u8],
+    obj_path: Option<&Path>,
+    warnings: &mut Vec<Warning>,
+) -> Option<u32> {
+    let index = material_map.get(name).copied();
+    if index.is_none() && !name.is_empty() {
+        let message = format!("material {:?} is not found", from_utf8_lossy(name));
+        if !warnings
+            .iter()
+            .any(|w| w.kind == WarningKind::MissingMaterial && w.message == message)
+        {
+            warnings.push(Warning::new(
+                WarningKind::MissingMaterial,
+                message,
+                obj_path,
+            ));
+        }
+    }
+    index
+}
+
+/// Keywords defined in the OBJ specification.
+#[rustfmt::skip]
+const OBJ_KEYWORDS: &[&[u8]] = &[
+    // vertex data
+    b"v", b"vt", b"vn", b"vp", b"cstype", b"deg", b"bmat", b"step",
+    // elements
+    b"p", b"l", b"f", b"fo", b"curv", b"curv2", b"surf",
+    // free-form curve/surface body statements and connectivity
+    b"parm", b"trim", b"hole", b"scrv", b"sp", b"end", b"con",
+    // grouping
+    b"g", b"s", b"mg", b"o",
+    // display/render attributes
+    b"bevel", b"c_interp", b"d_interp", b"lod", b"usemtl", b"mtllib", b"usemap", b"maplib",
+    b"shadow_obj", b"trace_obj", b"ctech", b"stech",
+    // general statements
+    b"call", b"csh",
+];
+
+/// Keywords defined in the MTL specification and its common PBR extension.
+#[rustfmt::skip]
+const MTL_KEYWORDS: &[&[u8]] = &[
+    b"newmtl", b"Ka", b"Kd", b"Ks", b"Ke", b"Km", b"Tf", b"Tr", b"d", b"Ns", b"Ni", b"illum",
+    b"sharpness", b"Pr", b"Pm", b"Ps", b"Pc", b"Pcr", b"aniso", b"anisor", b"a",
+    // texture maps
+    b"map_Ka", b"map_Kd", b"map_Ks", b"map_Ke", b"map_Ns", b"map_d", b"map_Tr", b"map_aat",
+    b"map_bump", b"map_Bump", b"bump", b"disp", b"decal", b"refl", b"norm", b"map_Pr",
+    b"map_Pm", b"map_Ps",
+];
+
+/// Returns `true` if the line is empty, a comment, or starts with one of the
+/// given keywords.
+#[cold]
+fn is_known_statement(mut s: &[u8], keywords: &[&[u8]]) -> bool {
+    skip_spaces(&mut s);
+    let len = memchr_naive_table(LINE | SPACE, &TABLE, s).unwrap_or(s.len());
+    let keyword = &s[..len];
+    keyword.is_empty() || keyword.starts_with(b"#") || keywords.contains(&keyword)
+}
+
+fn concat<'a, T: Copy + 'a>(mut vecs: impl Iterator<Item = &'a mut Vec<T>>) -> Vec<T> {
+    let mut out = match vecs.next() {
+        Some(v) => mem::take(v),
+        None => return vec![],
+    };
+    for v in vecs {
+        out.extend_from_slice(v);
+    }
+    out
+}
+
 #[inline(always)]
 fn read_v(
     s: &mut &[u8],
@@ -354,11 +721,78 @@ fn read_f(
     s: &mut &[u8],
     faces: &mut Vec<Face>,
     face: &mut Vec<[u32; 3]>,
-    vertices: &[Vec3],
-    texcoords: &[Vec2],
-    normals: &[Vec3],
+    vertices_len: usize,
+    texcoords_len: usize,
+    normals_len: usize,
 ) -> Result<(), ErrorKind> {
     // f <v1>/[vt1]/[vn1] <v2>/[vt2]/[vn2] <v3>/[vt3]/[vn3] ...
+    read_vertex_indices(s, face, vertices_len, texcoords_len, normals_len)?;
+    match face.len() {
+        1 => {
+            faces.push(Face::Point([face[0]]));
+            face.clear();
+        }
+        2 => {
+            faces.push(Face::Line([face[0], face[1]]));
+            face.clear();
+        }
+        3 => {
+            faces.push(Face::Triangle([face[0], face[1], face[2]]));
+            face.clear();
+        }
+        0 => return Err(ErrorKind::Expected("f", s.len())),
+        // TODO: triangulate in place here?
+        _ => faces.push(Face::Polygon(mem::take(face))),
+    }
+    Ok(())
+}
+
+fn read_l(
+    s: &mut &[u8],
+    faces: &mut Vec<Face>,
+    face: &mut Vec<[u32; 3]>,
+    vertices_len: usize,
+    texcoords_len: usize,
+    normals_len: usize,
+) -> Result<(), ErrorKind> {
+    // l <v1>/[vt1] <v2>/[vt2] ...
+    read_vertex_indices(s, face, vertices_len, texcoords_len, normals_len)?;
+    if face.len() < 2 {
+        return Err(ErrorKind::Expected("l", s.len()));
+    }
+    faces.extend(face.windows(2).map(|w| Face::Line([w[0], w[1]])));
+    face.clear();
+    Ok(())
+}
+
+fn read_p(
+    s: &mut &[u8],
+    faces: &mut Vec<Face>,
+    face: &mut Vec<[u32; 3]>,
+    vertices_len: usize,
+    texcoords_len: usize,
+    normals_len: usize,
+) -> Result<(), ErrorKind> {
+    // p <v1> <v2> ...
+    read_vertex_indices(s, face, vertices_len, texcoords_len, normals_len)?;
+    if face.is_empty() {
+        return Err(ErrorKind::Expected("p", s.len()));
+    }
+    faces.extend(face.iter().map(|&v| Face::Point([v])));
+    face.clear();
+    Ok(())
+}
+
+/// Reads `<v>/[vt]/[vn]` triples until the end of the line, and pushes them
+/// to `face`.
+#[inline(always)]
+fn read_vertex_indices(
+    s: &mut &[u8],
+    face: &mut Vec<[u32; 3]>,
+    vertices_len: usize,
+    texcoords_len: usize,
+    normals_len: usize,
+) -> Result<(), ErrorKind> {
     let mut f;
     match memchr_naive_table(LINE, &TABLE, s) {
         Some(n) => {
@@ -402,7 +836,7 @@ fn read_f(
             )]
             Some(i) => {
                 idx[0] = if i < 0 {
-                    (vertices.len() as isize + i as isize) as u32
+                    (vertices_len as isize + i as isize) as u32
                 } else {
                     (i - 1) as u32
                 }
@@ -428,7 +862,7 @@ fn read_f(
                 )]
                 Some(i) => {
                     idx[1] = if i < 0 {
-                        (texcoords.len() as isize + i as isize) as u32
+                        (texcoords_len as isize + i as isize) as u32
                     } else {
                         (i - 1) as u32
                     }
@@ -446,7 +880,7 @@ fn read_f(
                 )]
                 Some(i) => {
                     idx[2] = if i < 0 {
-                        (normals.len() as isize + i as isize) as u32
+                        (normals_len as isize + i as isize) as u32
                     } else {
                         (i - 1) as u32
                     }
@@ -458,23 +892,6 @@ fn read_f(
         skip_spaces(&mut f);
         face.push(idx);
     }
-    match face.len() {
-        1 => {
-            faces.push(Face::Point([face[0]]));
-            face.clear();
-        }
-        2 => {
-            faces.push(Face::Line([face[0], face[1]]));
-            face.clear();
-        }
-        3 => {
-            faces.push(Face::Triangle([face[0], face[1], face[2]]));
-            face.clear();
-        }
-        0 => return Err(ErrorKind::Expected("f", s.len())),
-        // TODO: triangulate in place here?
-        _ => faces.push(Face::Polygon(mem::take(face))),
-    }
     Ok(())
 }
 
@@ -581,7 +998,8 @@ fn push_vertex(
         .push(*vertices.get(v).ok_or(ErrorKind::Oob(v, 0))?);
     if !texcoords.is_empty() && vert[1] != u32::MAX {
         let vt = vert[1] as usize;
-        mesh.texcoords[0].push(*texcoords.get(vt).ok_or(ErrorKind::Oob(vt, 0))?);
+        let texcoord = *texcoords.get(vt).ok_or(ErrorKind::Oob(vt, 0))?;
+        mesh.texcoords_mut(0).push(texcoord);
     }
     if !normals.is_empty() && vert[2] != u32::MAX {
         let vn = vert[2] as usize;
@@ -591,7 +1009,7 @@ fn push_vertex(
     if !colors.is_empty() {
         let rgb = colors.get(v).ok_or(ErrorKind::Oob(v, 0))?;
         // a is 1 by default: https://github.com/assimp/assimp/blob/v5.3.1/code/AssetLib/Obj/ObjFileImporter.cpp#L233
-        mesh.colors[0].push([rgb[0], rgb[1], rgb[2], 1.]);
+        mesh.colors_mut(0).push([rgb[0], rgb[1], rgb[2], 1.]);
     }
     Ok(())
 }
@@ -605,6 +1023,7 @@ fn push_mesh(
     colors: &[Vec3],
     current_group: &[u8],
     material_index: Option<u32>,
+    keep_polygons: bool,
 ) -> Result<(), ErrorKind> {
     if !faces.is_empty() {
         let mut mesh = Mesh {
@@ -624,9 +1043,12 @@ fn push_mesh(
         // if !colors.is_empty() {
         //     mesh.colors[0].reserve(faces.len() * 3);
         // }
+        let mut has_lines_or_points = false;
+        let mut positions = vec![];
+        let mut triangles = vec![];
         for face in &*faces {
             match face {
-                Face::Point(_) | Face::Line(_) => {} // ignored
+                Face::Point(_) | Face::Line(_) => has_lines_or_points = true,
                 Face::Triangle(face) => {
                     #[allow(clippy::cast_possible_truncation)]
                     let vertices_indices = [
@@ -639,30 +1061,49 @@ fn push_mesh(
                     push_vertex(&mut mesh, face[2], vertices, colors, texcoords, normals)?;
                     mesh.faces.push(vertices_indices);
                 }
+                Face::Polygon(face) if keep_polygons => {
+                    #[allow(clippy::cast_possible_truncation)]
+                    let offset = mesh.polygon_indices.len() as u32;
+                    mesh.polygon_offsets.push(offset);
+                    for &v in face {
+                        #[allow(clippy::cast_possible_truncation)]
+                        let index = mesh.vertices.len() as u32;
+                        mesh.polygon_indices.push(index);
+                        push_vertex(&mut mesh, v, vertices, colors, texcoords, normals)?;
+                    }
+                }
                 Face::Polygon(face) => {
-                    let a = face[0];
-                    let mut b = face[1];
-                    for &c in &face[2..] {
+                    positions.clear();
+                    for v in face {
+                        let v = v[0] as usize;
+                        positions.push(*vertices.get(v).ok_or(ErrorKind::Oob(v, 0))?);
+                    }
+                    triangles.clear();
+                    triangulate(&positions, &mut triangles);
+                    for &[a, b, c] in &triangles {
                         #[allow(clippy::cast_possible_truncation)]
                         let vertices_indices = [
                             mesh.vertices.len() as u32,
                             (mesh.vertices.len() + 1) as u32,
                             (mesh.vertices.len() + 2) as u32,
                         ];
-                        push_vertex(&mut mesh, a, vertices, colors, texcoords, normals)?;
-                        push_vertex(&mut mesh, b, vertices, colors, texcoords, normals)?;
-                        push_vertex(&mut mesh, c, vertices, colors, texcoords, normals)?;
+                        push_vertex(&mut mesh, face[a], vertices, colors, texcoords, normals)?;
+                        push_vertex(&mut mesh, face[b], vertices, colors, texcoords, normals)?;
+                        push_vertex(&mut mesh, face[c], vertices, colors, texcoords, normals)?;
                         mesh.faces.push(vertices_indices);
-                        b = c;
                     }
                 }
             }
         }
-        if !mesh.colors[0].is_empty() && mesh.vertices.len() != mesh.colors[0].len() {
+        if has_lines_or_points {
+            push_lines_and_points(&mut mesh, faces, vertices, texcoords, normals, colors)?;
+        }
+        let len_mismatch = |len: usize| len != 0 && len != mesh.vertices.len();
+        if mesh.colors.iter().map(Vec::len).any(len_mismatch) {
             // TODO: do not use (0)
             return Err(ErrorKind::InvalidFaceIndex(0));
         }
-        if !mesh.texcoords[0].is_empty() && mesh.vertices.len() != mesh.texcoords[0].len() {
+        if mesh.texcoords.iter().map(Vec::len).any(len_mismatch) {
             return Err(ErrorKind::InvalidFaceIndex(0));
         }
         if !mesh.normals.is_empty() && mesh.vertices.len() != mesh.normals.len() {
@@ -674,6 +1115,63 @@ fn push_mesh(
     Ok(())
 }
 
+/// Pushes lines and points after triangles, so that their vertices can have
+/// the same attributes as the vertices of triangles.
+fn push_lines_and_points(
+    mesh: &mut Mesh,
+    faces: &[Face],
+    vertices: &[Vec3],
+    texcoords: &[Vec2],
+    normals: &[Vec3],
+    colors: &[Vec3],
+) -> Result<(), ErrorKind> {
+    let line_and_point_vertices = || {
+        faces.iter().flat_map(|face| match face {
+            Face::Point(face) => &face[..],
+            Face::Line(face) => &face[..],
+            Face::Triangle(_) | Face::Polygon(_) => &[],
+        })
+    };
+    // Without triangles, use attributes only if all vertices have them.
+    let (has_texcoords, has_normals) = if mesh.vertices.is_empty() {
+        (
+            !texcoords.is_empty() && line_and_point_vertices().all(|v| v[1] != u32::MAX),
+            !normals.is_empty() && line_and_point_vertices().all(|v| v[2] != u32::MAX),
+        )
+    } else {
+        (!mesh.texcoords.is_empty(), !mesh.normals.is_empty())
+    };
+    let push = |mesh: &mut Mesh, v: [u32; 3]| -> Result<u32, ErrorKind> {
+        #[allow(clippy::cast_possible_truncation)]
+        let index = mesh.vertices.len() as u32;
+        let vt = if has_texcoords { v[1] } else { u32::MAX };
+        let vn = if has_normals { v[2] } else { u32::MAX };
+        push_vertex(mesh, [v[0], vt, vn], vertices, colors, texcoords, normals)?;
+        if has_texcoords && vt == u32::MAX {
+            mesh.texcoords_mut(0).push([0.; 2]);
+        }
+        if has_normals && vn == u32::MAX {
+            mesh.normals.push([0.; 3]);
+        }
+        Ok(index)
+    };
+    for face in faces {
+        match face {
+            Face::Point([a]) => {
+                let a = push(mesh, *a)?;
+                mesh.points.push(a);
+            }
+            Face::Line([a, b]) => {
+                let a = push(mesh, *a)?;
+                let b = push(mesh, *b)?;
+                mesh.lines.push([a, b]);
+            }
+            Face::Triangle(_) | Face::Polygon(_) => {}
+        }
+    }
+    Ok(())
+}
+
 // -----------------------------------------------------------------------------
 // MTL
 
@@ -685,9 +1183,37 @@ pub fn read_mtl(
     path: Option<&Path>,
     materials: &mut Vec<common::Material>,
     material_map: &mut HashMap<Vec<u8>, u32>,
+) -> io::Result<()> {
+    read_mtl_with_options(
+        bytes,
+        path,
+        materials,
+        material_map,
+        false,
+        &TextureResolver::default(),
+        &mut vec![],
+    )
+}
+
+fn read_mtl_with_options(
+    bytes: &[u8],
+    path: Option<&Path>,
+    materials: &mut Vec<common::Material>,
+    material_map: &mut HashMap<Vec<u8>, u32>,
+    strict: bool,
+    textures: &TextureResolver,
+    warnings: &mut Vec<Warning>,
 ) -> io::Result<()> {
     let bytes = &decode_bytes(bytes)?;
-    match read_mtl_internal(bytes, path.and_then(Path::parent), materials, material_map) {
+    match read_mtl_internal(
+        bytes,
+        path,
+        materials,
+        material_map,
+        strict,
+        textures,
+        warnings,
+    ) {
         Ok(()) => Ok(()),
         Err(e) => Err(e.into_io_error(bytes, path)),
     }
@@ -695,14 +1221,18 @@ pub fn read_mtl(
 
 fn read_mtl_internal(
     mut s: &[u8],
-    mtl_dir: Option<&Path>,
+    mtl_path: Option<&Path>,
     materials: &mut Vec<common::Material>,
     material_map: &mut HashMap<Vec<u8>, u32>,
+    strict: bool,
+    textures: &TextureResolver,
+    warnings: &mut Vec<Warning>,
 ) -> Result<(), ErrorKind> {
     let mut mat: Option<Material<'_>> = None;
     let mut current_name: &[u8] = b"";
 
     while let Some((&c, s_next)) = s.split_first() {
+        let line = s;
         match c {
             b'K' | b'k' => {
                 s = s_next;
@@ -788,7 +1318,7 @@ fn read_mtl_internal(
                         continue;
                     }
                     Some(b'i') => {
-                        if read_texture(&mut s, &mut mat) {
+                        if read_texture(&mut s, &mut mat, strict)? {
                             // disp
                             continue;
                         }
@@ -824,7 +1354,15 @@ fn read_mtl_internal(
                         if skip_spaces(&mut s) {
                             let (name, s_next) = name(s);
                             if let Some(mat) = mat.replace(Material::default()) {
-                                push_material(materials, material_map, mtl_dir, current_name, &mat);
+                                push_material(
+                                    materials,
+                                    material_map,
+                                    mtl_path,
+                                    textures,
+                                    warnings,
+                                    current_name,
+                                    &mat,
+                                );
                             }
                             current_name = name;
                             s = s_next;
@@ -833,7 +1371,7 @@ fn read_mtl_internal(
                     }
                 }
                 Some(b'o') => {
-                    if read_texture(&mut s, &mut mat) {
+                    if read_texture(&mut s, &mut mat, strict)? {
                         // norm
                         continue;
                     }
@@ -895,7 +1433,7 @@ fn read_mtl_internal(
                 }
             }
             b'm' | b'b' | b'r' => {
-                if read_texture(&mut s, &mut mat) {
+                if read_texture(&mut s, &mut mat, strict)? {
                     continue;
                 }
             }
@@ -932,142 +1470,205 @@ fn read_mtl_internal(
             _ => {}
         }
         // ignore comment or other unknown
+        if strict && !is_known_statement(line, MTL_KEYWORDS) {
+            return Err(ErrorKind::UnknownStatement(line.len()));
+        }
         skip_any_until_line(&mut s);
     }
 
     if let Some(mat) = &mat {
-        push_material(materials, material_map, mtl_dir, current_name, mat);
+        push_material(
+            materials,
+            material_map,
+            mtl_path,
+            textures,
+            warnings,
+            current_name,
+            mat,
+        );
     }
 
     Ok(())
 }
 
-fn read_texture<'a>(s: &mut &'a [u8], mat: &mut Option<Material<'a>>) -> bool {
-    // Empty name cases are processed later in texture_path.
-    // TODO: handle texture options
-    if token(s, b"map_Kd") {
-        if skip_spaces(s) {
-            let (name, s_next) = name(s);
-            if let Some(mat) = mat {
-                mat.diffuse_texture = Some(name);
-            }
-            *s = s_next;
-            return true;
-        }
+fn read_texture<'a>(
+    s: &mut &'a [u8],
+    mat: &mut Option<Material<'a>>,
+    strict: bool,
+) -> Result<bool, ErrorKind> {
+    // Empty name cases are processed later by the texture resolver.
+    let texture = if token(s, b"map_Kd") {
+        mat.as_mut().map(|m| &mut m.diffuse_texture)
     } else if token(s, b"map_Ka") {
-        if skip_spaces(s) {
-            let (name, s_next) = name(s);
-            if let Some(mat) = mat {
-                mat.ambient_texture = Some(name);
-            }
-            *s = s_next;
-            return true;
-        }
+        mat.as_mut().map(|m| &mut m.ambient_texture)
     } else if token(s, b"map_Ks") {
-        if skip_spaces(s) {
-            let (name, s_next) = name(s);
-            if let Some(mat) = mat {
-                mat.specular_texture = Some(name);
-            }
-            *s = s_next;
-            return true;
-        }
+        mat.as_mut().map(|m| &mut m.specular_texture)
     } else if token(s, b"map_disp") || token(s, b"disp") {
-        if skip_spaces(s) {
-            let (name, s_next) = name(s);
-            if let Some(mat) = mat {
-                mat.displacement_texture = Some(name);
-            }
-            *s = s_next;
-            return true;
-        }
+        mat.as_mut().map(|m| &mut m.displacement_texture)
     } else if token(s, b"map_d") {
-        if skip_spaces(s) {
-            let (name, s_next) = name(s);
-            if let Some(mat) = mat {
-                mat.opacity_texture = Some(name);
-            }
-            *s = s_next;
-            return true;
-        }
+        mat.as_mut().map(|m| &mut m.opacity_texture)
     } else if token(s, b"map_emissive") || token(s, b"map_Ke") {
-        if skip_spaces(s) {
-            let (name, s_next) = name(s);
-            if let Some(mat) = mat {
-                mat.emissive_texture = Some(name);
-            }
-            *s = s_next;
-            return true;
-        }
+        mat.as_mut().map(|m| &mut m.emissive_texture)
     } else if token(s, b"map_Bump") || token(s, b"map_bump") || token(s, b"bump") {
-        if skip_spaces(s) {
-            let (name, s_next) = name(s);
-            if let Some(mat) = mat {
-                mat.bump_texture = Some(name);
-            }
-            *s = s_next;
-            return true;
-        }
+        mat.as_mut().map(|m| &mut m.bump_texture)
     } else if token(s, b"map_Kn") || token(s, b"norm") {
-        if skip_spaces(s) {
-            let (name, s_next) = name(s);
-            if let Some(mat) = mat {
-                mat.normal_texture = Some(name);
-            }
-            *s = s_next;
-            return true;
-        }
+        mat.as_mut().map(|m| &mut m.normal_texture)
     } else if token(s, b"refl") {
-        if skip_spaces(s) {
-            let (_name, s_next) = name(s);
-            // ignore https://github.com/assimp/assimp/blob/v5.3.1/code/AssetLib/Obj/ObjFileMtlImporter.cpp#L415
-            *s = s_next;
-            return true;
-        }
+        // ignore https://github.com/assimp/assimp/blob/v5.3.1/code/AssetLib/Obj/ObjFileMtlImporter.cpp#L415
+        None
     } else if token(s, b"map_Ns") || token(s, b"map_ns") || token(s, b"map_NS") {
-        if skip_spaces(s) {
-            let (name, s_next) = name(s);
-            if let Some(mat) = mat {
-                mat.specularity_texture = Some(name);
-            }
-            *s = s_next;
-            return true;
-        }
+        mat.as_mut().map(|m| &mut m.specularity_texture)
     } else if token(s, b"map_Pr") {
-        if skip_spaces(s) {
-            let (name, s_next) = name(s);
-            if let Some(mat) = mat {
-                mat.roughness_texture = Some(name);
+        mat.as_mut().map(|m| &mut m.roughness_texture)
+    } else if token(s, b"map_Pm") {
+        mat.as_mut().map(|m| &mut m.metallic_texture)
+    } else if token(s, b"map_Ps") {
+        mat.as_mut().map(|m| &mut m.sheen_texture)
+    } else {
+        return Ok(false);
+    };
+    if !skip_spaces(s) {
+        return Ok(false);
+    }
+    let mut s_options = *s;
+    let options = match read_texture_options(&mut s_options) {
+        Ok(options) => {
+            *s = s_options;
+            options
+        }
+        Err(e) if strict => return Err(e),
+        // Treat the rest of the line as the file name.
+        Err(_) => common::TextureOptions::default(),
+    };
+    let (name, s_next) = name(s);
+    if let Some(texture) = texture {
+        *texture = Some((name, options));
+    }
+    *s = s_next;
+    Ok(true)
+}
+
+/// Reads options of a texture map statement, such as `-o 0.5 0.5`.
+///
+/// Anything that does not start with a known option is treated as the start
+/// of the file name. If an option is malformed, an error is returned, and the
+/// caller treats the whole rest of the line as the file name unless in strict
+/// mode.
+fn read_texture_options(s: &mut &[u8]) -> Result<common::TextureOptions, ErrorKind> {
+    fn option(s: &mut &[u8], option: &'static [u8]) -> bool {
+        if starts_with(s, option)
+            && s.get(option.len())
+                .map_or(false, |&b| TABLE[b as usize] & SPACE != 0)
+        {
+            *s = &s[option.len()..];
+            skip_spaces(s);
+            true
+        } else {
+            false
+        }
+    }
+    /// Reads a space-separated word, and skips spaces after it.
+    fn word<'a>(s: &mut &'a [u8]) -> &'a [u8] {
+        let len = memchr_naive_table(LINE | SPACE, &TABLE, s).unwrap_or(s.len());
+        let (word, s_next) = s.split_at(len);
+        *s = s_next;
+        skip_spaces(s);
+        word
+    }
+    fn float(s: &mut &[u8]) -> Option<f32> {
+        let mut s_next = *s;
+        let w = word(&mut s_next);
+        match float::parse_partial::<f32>(w) {
+            Some((f, n)) if n == w.len() => {
+                *s = s_next;
+                Some(f)
             }
-            *s = s_next;
-            return true;
+            _ => None,
         }
-    } else if token(s, b"map_Pm") {
-        if skip_spaces(s) {
-            let (name, s_next) = name(s);
-            if let Some(mat) = mat {
-                mat.metallic_texture = Some(name);
+    }
+    fn req_float(s: &mut &[u8]) -> Result<f32, ErrorKind> {
+        float(s).ok_or(ErrorKind::Float(s.len()))
+    }
+    // u [v [w]]
+    fn float3(s: &mut &[u8], default: [f32; 3]) -> Result<[f32; 3], ErrorKind> {
+        let mut floats = default;
+        floats[0] = req_float(s)?;
+        if let Some(f) = float(s) {
+            floats[1] = f;
+            if let Some(f) = float(s) {
+                floats[2] = f;
             }
-            *s = s_next;
-            return true;
         }
-    } else if token(s, b"map_Ps") {
-        if skip_spaces(s) {
-            let (name, s_next) = name(s);
-            if let Some(mat) = mat {
-                mat.sheen_texture = Some(name);
+        Ok(floats)
+    }
+    fn on_off(s: &mut &[u8], expected: &'static str) -> Result<bool, ErrorKind> {
+        match word(s) {
+            b"on" => Ok(true),
+            b"off" => Ok(false),
+            _ => Err(ErrorKind::Expected(expected, s.len())),
+        }
+    }
+
+    let mut options = common::TextureOptions::default();
+    while s.first() == Some(&b'-') {
+        if option(s, b"-blendu") {
+            options.blend_u = on_off(s, "on or off after -blendu")?;
+        } else if option(s, b"-blendv") {
+            options.blend_v = on_off(s, "on or off after -blendv")?;
+        } else if option(s, b"-boost") {
+            options.boost = Some(req_float(s)?);
+        } else if option(s, b"-bm") {
+            options.bump_multiplier = req_float(s)?;
+        } else if option(s, b"-cc") {
+            options.color_correction = on_off(s, "on or off after -cc")?;
+        } else if option(s, b"-clamp") {
+            options.clamp = on_off(s, "on or off after -clamp")?;
+        } else if option(s, b"-imfchan") {
+            options.channel = Some(match word(s) {
+                b"r" => common::TextureChannel::Red,
+                b"g" => common::TextureChannel::Green,
+                b"b" => common::TextureChannel::Blue,
+                b"m" => common::TextureChannel::Matte,
+                b"l" => common::TextureChannel::Luminance,
+                b"z" => common::TextureChannel::Depth,
+                _ => {
+                    return Err(ErrorKind::Expected(
+                        "r, g, b, m, l, or z after -imfchan",
+                        s.len(),
+                    ))
+                }
+            });
+        } else if option(s, b"-mm") {
+            options.base_gain = [req_float(s)?, req_float(s)?];
+        } else if option(s, b"-o") {
+            options.offset = float3(s, [0.; 3])?;
+        } else if option(s, b"-s") {
+            options.scale = float3(s, [1.; 3])?;
+        } else if option(s, b"-t") {
+            options.turbulence = float3(s, [0.; 3])?;
+        } else if option(s, b"-texres") {
+            let w = word(s);
+            match int::parse_partial::<u32>(w) {
+                Some((n, len)) if len == w.len() => options.resolution = Some(n),
+                _ => return Err(ErrorKind::Int(s.len())),
             }
-            *s = s_next;
-            return true;
+        } else if option(s, b"-type") {
+            // The type of reflection maps (e.g., sphere, cube_top), which is
+            // not supported as reflection maps are ignored.
+            word(s);
+        } else {
+            break;
         }
     }
-    false
+    Ok(options)
 }
 
 fn push_material(
     materials: &mut Vec<common::Material>,
     material_map: &mut HashMap<Vec<u8>, u32>,
-    mtl_dir: Option<&Path>,
+    mtl_path: Option<&Path>,
+    textures: &TextureResolver,
+    warnings: &mut Vec<Warning>,
     current_name: &[u8],
     mat: &Material<'_>,
 ) {
@@ -1076,42 +1677,16 @@ fn push_material(
         // a is 1 by default: https://github.com/assimp/assimp/blob/v5.3.1/code/AssetLib/Obj/ObjFileImporter.cpp#L233
         Some([rgb[0], rgb[1], rgb[2], 1.])
     }
-    fn texture_path(texture: Option<&[u8]>, mtl_dir: Option<&Path>) -> Option<PathBuf> {
-        let mut p = texture?;
-        if p.is_empty() {
-            return None;
-        }
-        match mtl_dir {
-            Some(mtl_dir) => {
-                let tmp: Vec<_>;
-                if p.contains(&b'\\') {
-                    tmp = p
-                        .iter()
-                        .map(|&b| if b == b'\\' { b'/' } else { b })
-                        .collect();
-                    p = &*tmp;
-                }
-                if p.starts_with(b"/..") {
-                    p = p.strip_prefix(b"/").unwrap_or(p);
-                }
-                p = p.strip_prefix(b"./").unwrap_or(p);
-                let p = path_from_bytes(p).ok()?;
-                let p = mtl_dir.join(p);
-                if p.to_str().map_or(false, |s| {
-                    s.starts_with("https://") || p.starts_with("http://")
-                }) || p.exists()
-                {
-                    Some(p)
-                } else {
-                    None
-                }
-            }
-            None => {
-                let p = path_from_bytes(p).ok()?.to_owned();
-                Some(p)
-            }
-        }
-    }
+    let mtl_dir = mtl_path.and_then(Path::parent);
+    let mut texture_path = |texture: &Option<(&[u8], common::TextureOptions)>| {
+        let (p, options) = texture.as_ref()?;
+        let p = str::from_utf8(p).ok()?;
+        let path = textures.resolve(p, mtl_dir, mtl_path, warnings)?;
+        Some(common::Texture {
+            source: common::TextureSource::Path(path),
+            options: options.clone(),
+        })
+    };
     #[allow(clippy::cast_possible_truncation)]
     let material_index = materials.len() as u32;
     materials.push(common::Material {
@@ -1142,16 +1717,16 @@ fn push_material(
             reflective: None,
         },
         texture: common::Textures {
-            diffuse: texture_path(mat.diffuse_texture, mtl_dir),
-            ambient: texture_path(mat.ambient_texture, mtl_dir),
-            emissive: texture_path(mat.emissive_texture, mtl_dir),
-            specular: texture_path(mat.specular_texture, mtl_dir),
-            height: texture_path(mat.bump_texture, mtl_dir),
-            normal: texture_path(mat.normal_texture, mtl_dir),
+            diffuse: texture_path(&mat.diffuse_texture),
+            ambient: texture_path(&mat.ambient_texture),
+            emissive: texture_path(&mat.emissive_texture),
+            specular: texture_path(&mat.specular_texture),
+            height: texture_path(&mat.bump_texture),
+            normal: texture_path(&mat.normal_texture),
             reflection: None, // TODO
-            displacement: texture_path(mat.displacement_texture, mtl_dir),
-            opacity: texture_path(mat.opacity_texture, mtl_dir),
-            shininess: texture_path(mat.specularity_texture, mtl_dir),
+            displacement: texture_path(&mat.displacement_texture),
+            opacity: texture_path(&mat.opacity_texture),
+            shininess: texture_path(&mat.specularity_texture),
             lightmap: None,
         },
     });
@@ -1162,8 +1737,8 @@ fn push_material(
 // Helpers
 
 enum Face {
-    Point(#[allow(dead_code)] [[u32; 3]; 1]),
-    Line(#[allow(dead_code)] [[u32; 3]; 2]),
+    Point([[u32; 3]; 1]),
+    Line([[u32; 3]; 2]),
     Triangle([[u32; 3]; 3]),
     Polygon(Vec<[u32; 3]>),
 }
@@ -1171,19 +1746,19 @@ enum Face {
 #[derive(Default)]
 struct Material<'a> {
     // Textures
-    diffuse_texture: Option<&'a [u8]>,
-    specular_texture: Option<&'a [u8]>,
-    ambient_texture: Option<&'a [u8]>,
-    emissive_texture: Option<&'a [u8]>,
-    bump_texture: Option<&'a [u8]>,
-    normal_texture: Option<&'a [u8]>,
+    diffuse_texture: Option<(&'a [u8], common::TextureOptions)>,
+    specular_texture: Option<(&'a [u8], common::TextureOptions)>,
+    ambient_texture: Option<(&'a [u8], common::TextureOptions)>,
+    emissive_texture: Option<(&'a [u8], common::TextureOptions)>,
+    bump_texture: Option<(&'a [u8], common::TextureOptions)>,
+    normal_texture: Option<(&'a [u8], common::TextureOptions)>,
     // reflection_texture: Option<&'a [u8]>,
-    specularity_texture: Option<&'a [u8]>,
-    opacity_texture: Option<&'a [u8]>,
-    displacement_texture: Option<&'a [u8]>,
-    roughness_texture: Option<&'a [u8]>,
-    metallic_texture: Option<&'a [u8]>,
-    sheen_texture: Option<&'a [u8]>,
+    specularity_texture: Option<(&'a [u8], common::TextureOptions)>,
+    opacity_texture: Option<(&'a [u8], common::TextureOptions)>,
+    displacement_texture: Option<(&'a [u8], common::TextureOptions)>,
+    roughness_texture: Option<(&'a [u8], common::TextureOptions)>,
+    metallic_texture: Option<(&'a [u8], common::TextureOptions)>,
+    sheen_texture: Option<(&'a [u8], common::TextureOptions)>,
     // rma_texture: Option<&'a [u8]>,
 
     // Colors
@@ -1262,6 +1837,84 @@ fn table() {
     }
 }
 
+#[test]
+fn lines_and_points() {
+    let obj = b"v 0 0 0\nv 1 0 0\nv 1 1 0\nvt 0 0\nvt 1 0\nvt 1 1\n\
+        f 1/1 2/2 3/3\nl 1 2/2 3\np 1 3\nf 1 2\n";
+    let scene = from_slice(obj, None, |_| Ok(vec![])).unwrap();
+    let mesh = &scene.meshes[0];
+    assert_eq!(mesh.faces, [[0, 1, 2]]);
+    assert_eq!(mesh.lines, [[3, 4], [5, 6], [9, 10]]);
+    assert_eq!(mesh.points, [7, 8]);
+    assert_eq!(mesh.vertices.len(), 11);
+    assert_eq!(mesh.texcoords[0].len(), 11);
+    assert_eq!(mesh.texcoords[0][4], [1., 0.]);
+    assert_eq!(mesh.texcoords[0][6], [0., 0.]);
+
+    let scene = from_slice(b"v 0 0 0\nv 1 0 0\nl 1 2\nl 1\n", None, |_| Ok(vec![]));
+    assert!(scene.is_err());
+}
+
+#[test]
+fn texture_options() {
+    let mtl = b"newmtl a\nmap_Bump -bm 0.5 normal.png\n\
+        map_Kd -o 0.5 0.25 -s 2 -clamp on -imfchan r -texres 512 my texture.png\n\
+        map_d -blendu off -t 1 2 3 -mm 0.1 0.9 -file.png\n";
+    let mut materials = vec![];
+    read_mtl(mtl, None, &mut materials, &mut HashMap::new()).unwrap();
+    let t = &materials[0].texture;
+    let height = t.height.as_ref().unwrap();
+    assert_eq!(height.path(), Some(Path::new("normal.png")));
+    assert_eq!(height.options.bump_multiplier, 0.5);
+    let diffuse = t.diffuse.as_ref().unwrap();
+    assert_eq!(diffuse.path(), Some(Path::new("my texture.png")));
+    assert_eq!(diffuse.options.offset, [0.5, 0.25, 0.]);
+    assert_eq!(diffuse.options.scale, [2., 1., 1.]);
+    assert!(diffuse.options.clamp);
+    assert_eq!(diffuse.options.channel, Some(common::TextureChannel::Red));
+    assert_eq!(diffuse.options.resolution, Some(512));
+    let opacity = t.opacity.as_ref().unwrap();
+    assert_eq!(opacity.path(), Some(Path::new("-file.png")));
+    assert!(!opacity.options.blend_u);
+    assert_eq!(opacity.options.turbulence, [1., 2., 3.]);
+    assert_eq!(opacity.options.base_gain, [0.1, 0.9]);
+
+    // Options are written back.
+    let scene = Scene {
+        materials: materials.clone(),
+        ..Default::default()
+    };
+    let mut written = vec![];
+    mtl_to_writer(&mut written, &scene, None).unwrap();
+    let mut read = vec![];
+    read_mtl(&written, None, &mut read, &mut HashMap::new()).unwrap();
+    for (a, b) in [
+        (&t.height, &read[0].texture.height),
+        (&t.diffuse, &read[0].texture.diffuse),
+        (&t.opacity, &read[0].texture.opacity),
+    ] {
+        assert_eq!(a, b);
+    }
+
+    // Malformed options are a part of the file name unless in strict mode.
+    let mtl = b"newmtl a\nmap_Kd -clamp yes a.png\n";
+    let mut materials = vec![];
+    read_mtl(mtl, None, &mut materials, &mut HashMap::new()).unwrap();
+    let diffuse = materials[0].texture.diffuse.as_ref().unwrap();
+    assert_eq!(diffuse.path(), Some(Path::new("-clamp yes a.png")));
+    assert_eq!(diffuse.options, common::TextureOptions::default());
+    assert!(read_mtl_with_options(
+        mtl,
+        None,
+        &mut vec![],
+        &mut HashMap::new(),
+        true,
+        &TextureResolver::default(),
+        &mut vec![],
+    )
+    .is_err());
+}
+
 #[inline]
 fn skip_whitespace_until_byte_or_eof(s: &mut &[u8], byte_mask: u8, whitespace_mask: u8) -> bool {
     while let Some((&b, s_next)) = s.split_first() {
@@ -1361,3 +2014,17 @@ fn name(mut s: &[u8]) -> (&[u8], &[u8]) {
     }
     (name, s)
 }
+
+#[test]
+fn vertices_after_faces() {
+    // Meshes are built from the vertices defined before the group changes.
+    let obj = b"v 0 0 0\nf 1 2 3\ng a\nv 1 0 0\nv 0 1 0\nf 1 2 3\n";
+    let e = from_slice(obj, None, |_| Ok(vec![])).unwrap_err();
+    assert_eq!(
+        crate::Error::from_io_error(&e).unwrap().kind(),
+        crate::ErrorKind::FaceIndexOutOfBounds
+    );
+    let obj = b"v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\ng a\nf 1 2 3\n";
+    let scene = from_slice(obj, None, |_| Ok(vec![])).unwrap();
+    assert_eq!(scene.meshes.len(), 2);
+}
@@ -0,0 +1,194 @@
+// The parallel feature requires Rust 1.63+.
+#![allow(clippy::incompatible_msrv)]
+
+use std::{panic, thread};
+
+use super::{read_obj_chunk, skip_any_until_line, skip_spaces, ObjChunk};
+
+/// Files smaller than this are parsed on the current thread.
+const MIN_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Splits OBJ text at line boundaries, and parses the parts in parallel.
+pub(super) fn read_obj_chunks(s: &[u8], strict: bool) -> Vec<ObjChunk<'_>> {
+    let num_threads = thread::available_parallelism().map_or(1, usize::from);
+    read_obj_in_parts(s, num_threads.min(s.len() / MIN_CHUNK_SIZE), strict)
+}
+
+fn read_obj_in_parts(s: &[u8], num_parts: usize, strict: bool) -> Vec<ObjChunk<'_>> {
+    let parts = split_lines(s, num_parts);
+    if parts.len() <= 1 {
+        return vec![read_obj_chunk(s, [0; 3], strict)];
+    }
+
+    // Relative face indices refer to the vertices before the face, so count
+    // the vertices in the preceding parts first.
+    let counts = run_in_parallel(&parts, |&part| count_vertices(part));
+    let mut base = [0; 3];
+    let bases = counts.iter().map(|count| {
+        let b = base;
+        for i in 0..3 {
+            base[i] += count[i];
+        }
+        b
+    });
+    let parts: Vec<_> = parts.iter().copied().zip(bases).collect();
+    let mut chunks = run_in_parallel(&parts, |&(part, base)| read_obj_chunk(part, base, strict));
+
+    // Error positions are relative to the end of each part.
+    let mut remaining = 0;
+    for (chunk, (part, _)) in chunks.iter_mut().zip(&parts).rev() {
+        if let Some(e) = &mut chunk.error {
+            e.add_remaining(remaining);
+        }
+        remaining += part.len();
+    }
+    chunks
+}
+
+fn run_in_parallel<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let f = &f;
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .iter()
+            .map(|item| scope.spawn(move || f(item)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+            .collect()
+    })
+}
+
+/// Splits the text into at most `n` parts at the line boundaries.
+fn split_lines(mut s: &[u8], n: usize) -> Vec<&[u8]> {
+    let mut parts = Vec::with_capacity(n);
+    let size = s.len() / n.max(1);
+    while parts.len() + 1 < n {
+        match find_line_boundary(s, size) {
+            Some(pos) => {
+                parts.push(&s[..pos]);
+                s = &s[pos..];
+            }
+            None => break,
+        }
+    }
+    parts.push(s);
+    parts
+}
+
+/// Returns the position after the first line feed at or after `start` that is
+/// not escaped by a backslash.
+fn find_line_boundary(s: &[u8], start: usize) -> Option<usize> {
+    let mut pos = start;
+    while pos < s.len() {
+        if s[pos] == b'\n' {
+            if !matches!(&s[..pos], [.., b'\\'] | [.., b'\\', b'\r']) {
+                return Some(pos + 1);
+            }
+        }
+        pos += 1;
+    }
+    None
+}
+
+/// Returns the number of `v`, `vt`, and `vn` statements.
+///
+/// This must be consistent with `read_obj_statements`.
+fn count_vertices(mut s: &[u8]) -> [usize; 3] {
+    let mut counts = [0; 3];
+    while let Some((&c, s_next)) = s.split_first() {
+        if c == b'v' {
+            s = s_next;
+            match s.first() {
+                Some(b' ' | b'\t') => counts[0] += 1,
+                Some(b't') => {
+                    s = &s[1..];
+                    if skip_spaces(&mut s) {
+                        counts[1] += 1;
+                    }
+                }
+                Some(b'n') => {
+                    s = &s[1..];
+                    if skip_spaces(&mut s) {
+                        counts[2] += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+        skip_any_until_line(&mut s);
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write as _;
+
+    use super::*;
+    use crate::obj::merge_obj_chunks;
+
+    #[test]
+    fn same_as_single_thread() {
+        let mut obj = String::new();
+        for i in 0..200_u16 {
+            let f = f32::from(i);
+            // The first vertex with color is not the first vertex.
+            if i > 120 && i % 3 == 0 {
+                writeln!(obj, "v {f} {f} 0 1 0.5 0.25").unwrap();
+            } else {
+                writeln!(obj, "v {f} 0 {f}").unwrap();
+            }
+            writeln!(obj, "vt {f} \\\n 1\nvn 0 0 1").unwrap();
+            if i >= 2 {
+                obj += "f -3/-3/-3 -2/-2/-2 -1/-1/-1\n";
+                writeln!(obj, "f {}/1/1 {}/2/2 {}/3/3", i - 1, i, i + 1).unwrap();
+            }
+            if i % 50 == 0 {
+                writeln!(obj, "g group{}\nusemtl mat{}", i / 100, i % 100).unwrap();
+            }
+        }
+
+        let read = |chunks| {
+            merge_obj_chunks(chunks, None, &mut |_, _, _, _| Ok(()), &mut vec![], false).unwrap()
+        };
+        let (expected, _) = read(vec![read_obj_chunk(obj.as_bytes(), [0; 3], false)]);
+        for num_parts in [2, 3, 7] {
+            let (meshes, _) = read(read_obj_in_parts(obj.as_bytes(), num_parts, false));
+            assert_eq!(meshes.len(), expected.len());
+            for (m, e) in meshes.iter().zip(&expected) {
+                assert_eq!(m.name, e.name);
+                assert_eq!(m.material_index, e.material_index);
+                assert_eq!(m.vertices, e.vertices);
+                assert_eq!(m.texcoords, e.texcoords);
+                assert_eq!(m.normals, e.normals);
+                assert_eq!(m.colors, e.colors);
+                assert_eq!(m.faces, e.faces);
+            }
+        }
+
+        // Error positions are the same as in the single-threaded parser.
+        obj += "v 1 2\n";
+        obj += &"v 1 2 3\n".repeat(100);
+        let expected = match merge_obj_chunks(
+            vec![read_obj_chunk(obj.as_bytes(), [0; 3], false)],
+            None,
+            &mut |_, _, _, _| Ok(()),
+            &mut vec![],
+            false,
+        ) {
+            Err(e) => e.into_io_error(obj.as_bytes(), None).to_string(),
+            Ok(_) => panic!(),
+        };
+        match merge_obj_chunks(
+            read_obj_in_parts(obj.as_bytes(), 3, false),
+            None,
+            &mut |_, _, _, _| Ok(()),
+            &mut vec![],
+            false,
+        ) {
+            Err(e) => assert_eq!(e.into_io_error(obj.as_bytes(), None).to_string(), expected),
+            Ok(_) => panic!(),
+        }
+    }
+}
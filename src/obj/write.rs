@@ -0,0 +1,423 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Write},
+    path::Path,
+    slice,
+};
+
+use crate::{Color4, Material, Scene, ShadingModel, TextureChannel, TextureOptions};
+
+/// Writes the scene as OBJ to `path`, and its materials as MTL to the same
+/// path with the `.mtl` extension.
+///
+/// The MTL file is not written if the scene has no materials.
+pub fn write<P: AsRef<Path>>(path: P, scene: &Scene) -> io::Result<()> {
+    let path = path.as_ref();
+    let mut mtllib = None;
+    if !scene.materials.is_empty() {
+        let mtl_path = path.with_extension("mtl");
+        let mut writer = io::BufWriter::new(fs::File::create(&mtl_path)?);
+        mtl_to_writer(&mut writer, scene, mtl_path.parent())?;
+        writer.flush()?;
+        mtllib = mtl_path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .map(str::to_owned);
+    }
+    let mut writer = io::BufWriter::new(fs::File::create(path)?);
+    to_writer(&mut writer, scene, mtllib.as_deref())?;
+    writer.flush()
+}
+
+/// Serializes meshes in the scene as OBJ into the given writer.
+///
+/// Each mesh is written as a `g` group named after the mesh, and refers to
+/// the material at the same index in `scene.materials` by `usemtl`. If
+/// `mtllib` is given, it is written as the name of the MTL file that contains
+/// the materials (see [`mtl_to_writer`]). Polygons in `polygon_indices` are
+/// written as faces with more than three vertices.
+///
+/// Note that this writes to `writer` in small chunks, so it is often
+/// preferable to wrap `writer` with [`io::BufWriter`].
+pub fn to_writer<W: Write>(mut writer: W, scene: &Scene, mtllib: Option<&str>) -> io::Result<()> {
+    if let Some(mtllib) = mtllib {
+        writeln!(writer, "mtllib {mtllib}")?;
+    }
+    let material_names = material_names(&scene.materials);
+    // OBJ indices are 1-based and global across groups.
+    let (mut v_offset, mut vt_offset, mut vn_offset) = (1_u64, 1_u64, 1_u64);
+    let mut prev_group = String::new();
+    for (i, mesh) in scene.meshes.iter().enumerate() {
+        let texcoords = mesh
+            .texcoords
+            .first()
+            .filter(|t| t.len() == mesh.vertices.len());
+        let has_texcoords = texcoords.is_some();
+        let has_normals = mesh.normals.len() == mesh.vertices.len();
+        let colors = mesh
+            .colors
+            .first()
+            .filter(|c| c.len() == mesh.vertices.len());
+
+        // The reader starts a new mesh when the group name changes, so avoid
+        // using the same name as the previous group.
+        let mut group = sanitize_name(&mesh.name, "default");
+        if i != 0 && group == prev_group {
+            group = format!("{group}_{i}");
+        }
+        writeln!(writer, "g {group}")?;
+        for (j, v) in mesh.vertices.iter().enumerate() {
+            if let Some(colors) = colors {
+                let c = colors[j];
+                writeln!(
+                    writer,
+                    "v {} {} {} {} {} {}",
+                    v[0], v[1], v[2], c[0], c[1], c[2]
+                )?;
+            } else {
+                writeln!(writer, "v {} {} {}", v[0], v[1], v[2])?;
+            }
+        }
+        if let Some(texcoords) = texcoords {
+            for vt in texcoords {
+                writeln!(writer, "vt {} {}", vt[0], vt[1])?;
+            }
+        }
+        if has_normals {
+            for vn in &mesh.normals {
+                writeln!(writer, "vn {} {} {}", vn[0], vn[1], vn[2])?;
+            }
+        }
+
+        if let Some(name) = material_names.get(i) {
+            writeln!(writer, "usemtl {name}")?;
+        }
+        // Polygons are written as faces with more than three vertices. Lines
+        // can have only texture coordinates, and points can have neither
+        // texture coordinates nor normals.
+        let primitives = mesh
+            .faces
+            .iter()
+            .map(|f| &f[..])
+            .chain(mesh.polygons())
+            .map(|f| (&b"f"[..], f, has_texcoords, has_normals))
+            .chain(
+                mesh.lines
+                    .iter()
+                    .map(|l| (&b"l"[..], &l[..], has_texcoords, false)),
+            )
+            .chain(
+                mesh.points
+                    .iter()
+                    .map(|p| (&b"p"[..], slice::from_ref(p), false, false)),
+            );
+        for (keyword, indices, has_texcoords, has_normals) in primitives {
+            writer.write_all(keyword)?;
+            for &vi in indices {
+                if vi as usize >= mesh.vertices.len() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("vertex index out of bounds ({vi})"),
+                    ));
+                }
+                let v = u64::from(vi) + v_offset;
+                match (has_texcoords, has_normals) {
+                    (false, false) => write!(writer, " {v}")?,
+                    (true, false) => write!(writer, " {v}/{}", u64::from(vi) + vt_offset)?,
+                    (false, true) => write!(writer, " {v}//{}", u64::from(vi) + vn_offset)?,
+                    (true, true) => write!(
+                        writer,
+                        " {v}/{}/{}",
+                        u64::from(vi) + vt_offset,
+                        u64::from(vi) + vn_offset
+                    )?,
+                }
+            }
+            writer.write_all(b"\n")?;
+        }
+
+        let num_vertices = mesh.vertices.len() as u64;
+        v_offset += num_vertices;
+        if has_texcoords {
+            vt_offset += num_vertices;
+        }
+        if has_normals {
+            vn_offset += num_vertices;
+        }
+        prev_group = group;
+    }
+    Ok(())
+}
+
+/// Serializes materials in the scene as MTL into the given writer.
+///
+/// Texture paths under `mtl_dir` are written as paths relative to `mtl_dir`,
+/// and other paths are written as is. The lightmap texture and embedded
+/// textures are not written, as MTL has no corresponding statement.
+///
+/// Note that this writes to `writer` in small chunks, so it is often
+/// preferable to wrap `writer` with [`io::BufWriter`].
+pub fn mtl_to_writer<W: Write>(
+    mut writer: W,
+    scene: &Scene,
+    mtl_dir: Option<&Path>,
+) -> io::Result<()> {
+    fn color<W: Write>(writer: &mut W, key: &str, color: Option<Color4>) -> io::Result<()> {
+        match color {
+            Some(c) => writeln!(writer, "{key} {} {} {}", c[0], c[1], c[2]),
+            None => Ok(()),
+        }
+    }
+    fn float<W: Write>(writer: &mut W, key: &str, f: Option<f32>) -> io::Result<()> {
+        match f {
+            Some(f) => writeln!(writer, "{key} {f}"),
+            None => Ok(()),
+        }
+    }
+
+    let material_names = material_names(&scene.materials);
+    let mut written = HashSet::new();
+    for (mat, name) in scene.materials.iter().zip(&material_names) {
+        if !written.insert(name) {
+            continue;
+        }
+        writeln!(writer, "newmtl {name}")?;
+        // Refs: https://github.com/assimp/assimp/blob/v5.3.1/code/AssetLib/Obj/ObjFileImporter.cpp#L591
+        match mat.shading_model {
+            Some(ShadingModel::NoShading) => writeln!(writer, "illum 0")?,
+            Some(ShadingModel::Flat | ShadingModel::Gouraud) => writeln!(writer, "illum 1")?,
+            Some(ShadingModel::Phong | ShadingModel::Blinn) => writeln!(writer, "illum 2")?,
+            None => {}
+        }
+        color(&mut writer, "Ka", mat.color.ambient)?;
+        color(&mut writer, "Kd", mat.color.diffuse)?;
+        color(&mut writer, "Ks", mat.color.specular)?;
+        color(&mut writer, "Ke", mat.color.emissive)?;
+        color(&mut writer, "Tf", mat.color.transparent)?;
+        float(&mut writer, "Ns", mat.shininess)?;
+        float(&mut writer, "d", mat.opacity)?;
+        float(&mut writer, "Ni", mat.index_of_refraction)?;
+        let t = &mat.texture;
+        for (key, texture) in [
+            ("map_Kd", &t.diffuse),
+            ("map_Ka", &t.ambient),
+            ("map_Ks", &t.specular),
+            ("map_Ke", &t.emissive),
+            ("map_Ns", &t.shininess),
+            ("map_d", &t.opacity),
+            ("bump", &t.height),
+            ("norm", &t.normal),
+            ("disp", &t.displacement),
+            ("refl", &t.reflection),
+        ] {
+            let texture = match texture {
+                Some(texture) => texture,
+                None => continue,
+            };
+            if let Some(path) = texture.path() {
+                let path = match mtl_dir {
+                    Some(dir) => path.strip_prefix(dir).unwrap_or(path),
+                    None => path,
+                };
+                write!(writer, "{key}")?;
+                texture_options(&mut writer, &texture.options)?;
+                writeln!(writer, " {}", path.to_string_lossy())?;
+            }
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Writes texture options that differ from the default.
+fn texture_options<W: Write>(writer: &mut W, options: &TextureOptions) -> io::Result<()> {
+    fn on_off(b: bool) -> &'static str {
+        if b {
+            "on"
+        } else {
+            "off"
+        }
+    }
+    let default = TextureOptions::default();
+    if options.blend_u != default.blend_u {
+        write!(writer, " -blendu {}", on_off(options.blend_u))?;
+    }
+    if options.blend_v != default.blend_v {
+        write!(writer, " -blendv {}", on_off(options.blend_v))?;
+    }
+    if let Some(boost) = options.boost {
+        write!(writer, " -boost {boost}")?;
+    }
+    if options.bump_multiplier != default.bump_multiplier {
+        write!(writer, " -bm {}", options.bump_multiplier)?;
+    }
+    if options.color_correction != default.color_correction {
+        write!(writer, " -cc {}", on_off(options.color_correction))?;
+    }
+    if options.clamp != default.clamp {
+        write!(writer, " -clamp {}", on_off(options.clamp))?;
+    }
+    if let Some(channel) = options.channel {
+        let channel = match channel {
+            TextureChannel::Red => "r",
+            TextureChannel::Green => "g",
+            TextureChannel::Blue => "b",
+            TextureChannel::Matte => "m",
+            TextureChannel::Luminance => "l",
+            TextureChannel::Depth => "z",
+        };
+        write!(writer, " -imfchan {channel}")?;
+    }
+    if options.base_gain != default.base_gain {
+        let [base, gain] = options.base_gain;
+        write!(writer, " -mm {base} {gain}")?;
+    }
+    for (option, v, default) in [
+        ("-o", options.offset, default.offset),
+        ("-s", options.scale, default.scale),
+        ("-t", options.turbulence, default.turbulence),
+    ] {
+        if v != default {
+            write!(writer, " {option} {} {} {}", v[0], v[1], v[2])?;
+        }
+    }
+    if let Some(resolution) = options.resolution {
+        write!(writer, " -texres {resolution}")?;
+    }
+    Ok(())
+}
+
+/// Returns names of materials that can be used in `newmtl` and `usemtl`.
+///
+/// Materials with the same name are assumed to be the same material, since
+/// loaders clone the material for each mesh that uses it.
+fn material_names(materials: &[Material]) -> Vec<String> {
+    materials
+        .iter()
+        .enumerate()
+        .map(|(i, m)| {
+            if m.name.is_empty() {
+                format!("material_{i}")
+            } else {
+                sanitize_name(&m.name, "")
+            }
+        })
+        .collect()
+}
+
+/// Names in OBJ and MTL end at the line break, and leading and trailing
+/// whitespaces are trimmed.
+fn sanitize_name(name: &str, default: &str) -> String {
+    let name = name.trim();
+    if name.is_empty() {
+        return default.to_owned();
+    }
+    name.replace(['\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::Mesh;
+
+    #[test]
+    fn round_trip() {
+        let mut scene = Scene::default();
+        for name in ["a", "b"] {
+            let mut mesh = Mesh {
+                name: name.to_owned(),
+                vertices: vec![[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 1., 0.]],
+                normals: vec![[0., 0., 1.]; 4],
+                faces: vec![[0, 1, 2], [0, 2, 3]],
+                lines: vec![[1, 3]],
+                points: vec![2],
+                ..Default::default()
+            };
+            mesh.texcoords = vec![vec![[0., 0.], [1., 0.], [1., 1.], [0., 1.]]];
+            scene.meshes.push(mesh);
+            let mut mat = Material {
+                name: format!("mat_{name}"),
+                shininess: Some(10.),
+                ..Default::default()
+            };
+            mat.color.diffuse = Some([1., 0.5, 0., 1.]);
+            scene.materials.push(mat);
+        }
+
+        let mut obj = vec![];
+        to_writer(&mut obj, &scene, Some("test.mtl")).unwrap();
+        let mut mtl = vec![];
+        mtl_to_writer(&mut mtl, &scene, None).unwrap();
+        let read = super::super::from_slice(&obj, Some(Path::new("test.obj")), |_| {
+            Ok::<_, io::Error>(&mtl)
+        })
+        .unwrap();
+        assert_eq!(read.meshes.len(), 2);
+        for (i, (m, mat)) in read.meshes.iter().zip(&read.materials).enumerate() {
+            let expected = &scene.meshes[i];
+            assert_eq!(m.name, expected.name);
+            assert_eq!(m.faces.len(), 2);
+            // The reader de-indexes faces.
+            for (face, expected_face) in m.faces.iter().zip(&expected.faces) {
+                for (&vi, &ei) in face.iter().zip(expected_face) {
+                    assert_eq!(m.vertices[vi as usize], expected.vertices[ei as usize]);
+                    assert_eq!(m.normals[vi as usize], expected.normals[ei as usize]);
+                    assert_eq!(
+                        m.texcoords[0][vi as usize],
+                        expected.texcoords[0][ei as usize]
+                    );
+                }
+            }
+            assert_eq!(m.lines.len(), 1);
+            for (&vi, &ei) in m.lines[0].iter().zip(&expected.lines[0]) {
+                assert_eq!(m.vertices[vi as usize], expected.vertices[ei as usize]);
+            }
+            assert_eq!(m.points.len(), 1);
+            assert_eq!(m.vertices[m.points[0] as usize], expected.vertices[2]);
+            assert_eq!(mat.name, scene.materials[i].name);
+            assert_eq!(mat.color.diffuse, Some([1., 0.5, 0., 1.]));
+            assert_eq!(mat.shininess, Some(10.));
+        }
+    }
+
+    #[test]
+    fn polygons() {
+        let obj = b"v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 2 0 0\nf 1 2 3 4\nf 2 5 3\n";
+        let read = |obj: &[u8]| {
+            super::super::from_slice_internal(
+                obj,
+                None,
+                |_| Ok::<_, io::Error>(vec![]),
+                false,
+                true,
+                &crate::TextureResolver::default(),
+            )
+            .unwrap()
+        };
+        let scene = read(obj);
+        assert_eq!(scene.meshes[0].polygons().count(), 1);
+        assert_eq!(scene.meshes[0].faces.len(), 1);
+
+        let mut written = vec![];
+        to_writer(&mut written, &scene, None).unwrap();
+        let written = String::from_utf8(written).unwrap();
+        assert!(written.contains("f 5 6 7\n"), "{written}");
+        assert!(written.contains("f 1 2 3 4\n"), "{written}");
+        let read = read(written.as_bytes());
+        let m = &read.meshes[0];
+        let polygons: Vec<_> = m.polygons().collect();
+        assert_eq!(polygons.len(), 1);
+        let positions: Vec<_> = polygons[0]
+            .iter()
+            .map(|&i| m.vertices[i as usize])
+            .collect();
+        assert_eq!(
+            positions,
+            [[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 1., 0.]]
+        );
+        assert_eq!(m.faces.len(), 1);
+    }
+}
@@ -0,0 +1,81 @@
+use std::{fmt, io, path::Path};
+
+use crate::error::{Error, Format, Location};
+
+#[cfg_attr(test, derive(Debug))]
+pub(super) enum ErrorKind {
+    // header error
+    Expected(&'static str, usize),
+    UnsupportedFormat(usize),
+    UnknownType(usize),
+    Int(usize),
+    PropertyWithoutElement(usize),
+    // ASCII PLY error
+    Float(usize),
+    UnexpectedEof(usize),
+    // binary PLY error
+    UnexpectedBinaryEof,
+    // common body error
+    InvalidListLength,
+    Oob(i64),
+    TooManyVertices,
+}
+
+impl ErrorKind {
+    #[cold]
+    #[inline(never)]
+    pub(super) fn into_io_error(self, start: &[u8], path: Option<&Path>) -> io::Error {
+        let remaining = match self {
+            // header or ASCII PLY error
+            Self::Expected(.., n)
+            | Self::UnsupportedFormat(n)
+            | Self::UnknownType(n)
+            | Self::Int(n)
+            | Self::PropertyWithoutElement(n)
+            | Self::Float(n)
+            | Self::UnexpectedEof(n) => Some(n),
+            // body error (position in binary PLY is not useful)
+            _ => None,
+        };
+        let kind = match self {
+            Self::Expected(..) | Self::PropertyWithoutElement(..) => crate::ErrorKind::Syntax,
+            Self::UnsupportedFormat(..) | Self::UnknownType(..) => crate::ErrorKind::Unsupported,
+            Self::Int(..) => crate::ErrorKind::InvalidInt,
+            Self::Float(..) => crate::ErrorKind::InvalidFloat,
+            Self::UnexpectedEof(..) | Self::UnexpectedBinaryEof => crate::ErrorKind::UnexpectedEof,
+            Self::InvalidListLength => crate::ErrorKind::InvalidSize,
+            Self::Oob(..) => crate::ErrorKind::FaceIndexOutOfBounds,
+            Self::TooManyVertices => crate::ErrorKind::TooLarge,
+        };
+        let e = Error::new(kind, self.to_string()).with_format(Format::Ply);
+        match remaining {
+            Some(remaining) => Location::find(remaining, start, path).attach(e).into(),
+            None => e.with_file(path).into(),
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    #[cold]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            // header error
+            Self::Expected(msg, ..) => write!(f, "expected {msg}"),
+            Self::UnsupportedFormat(..) => f.write_str(
+                "unsupported format: expected ascii, binary_little_endian, or binary_big_endian",
+            ),
+            Self::UnknownType(..) => f.write_str("unknown property type"),
+            Self::Int(..) => f.write_str("error while parsing an integer"),
+            Self::PropertyWithoutElement(..) => f.write_str("property declared before element"),
+            // ASCII PLY error
+            Self::Float(..) => f.write_str("error while parsing a number"),
+            Self::UnexpectedEof(..) | Self::UnexpectedBinaryEof => {
+                f.write_str("unexpected end of file")
+            }
+            // common body error
+            Self::InvalidListLength => f.write_str("invalid list length"),
+            Self::Oob(i) => write!(f, "vertex index out of bounds ({i})"),
+            Self::TooManyVertices => f.write_str("too many vertices"),
+        }
+    }
+}
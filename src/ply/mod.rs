@@ -0,0 +1,668 @@
+//! [PLY] (.ply) parser.
+//!
+//! [PLY]: https://en.wikipedia.org/wiki/PLY_(file_format)
+
+mod error;
+
+use std::{cmp, io, path::Path};
+
+use self::error::ErrorKind;
+use crate::{
+    utils::{bytes::memchr_naive, float, int},
+    Material, Mesh, Scene,
+};
+
+/// Parses meshes from bytes of ASCII or binary (little-endian or big-endian) PLY.
+#[inline]
+pub fn from_slice(bytes: &[u8]) -> io::Result<Scene> {
+    from_slice_internal(bytes, None)
+}
+
+pub(crate) fn from_slice_internal(bytes: &[u8], path: Option<&Path>) -> io::Result<Scene> {
+    let mut s = bytes;
+    let header = read_header(&mut s).map_err(|e| e.into_io_error(bytes, path))?;
+    let mesh = match header.format {
+        Format::Ascii => read_body(&mut Ascii { s }, &header),
+        Format::BinaryLittleEndian => read_body(&mut Binary::<false> { s }, &header),
+        Format::BinaryBigEndian => read_body(&mut Binary::<true> { s }, &header),
+    }
+    .map_err(|e| e.into_io_error(bytes, path))?;
+    Ok(Scene {
+        materials: vec![Material::default()],
+        meshes: vec![mesh],
+        ..Default::default()
+    })
+}
+
+// -----------------------------------------------------------------------------
+// Header
+
+/*
+http://paulbourke.net/dataformats/ply/
+
+ply
+format ascii 1.0           { ascii/binary, format version number }
+comment made by Greg Turk  { comments keyword specified, like all lines }
+element vertex 8           { define "vertex" element, 8 of them in file }
+property float x           { vertex contains float "x" coordinate }
+property float y           { y coordinate is also a vertex property }
+property float z           { z coordinate, too }
+element face 6             { there are 6 "face" elements in the file }
+property list uchar int vertex_index { "vertex_indices" is a list of ints }
+end_header                 { delimits the end of the header }
+*/
+
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy)]
+enum Type {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Type {
+    fn from_bytes(b: &[u8]) -> Option<Self> {
+        Some(match b {
+            b"char" | b"int8" => Self::I8,
+            b"uchar" | b"uint8" => Self::U8,
+            b"short" | b"int16" => Self::I16,
+            b"ushort" | b"uint16" => Self::U16,
+            b"int" | b"int32" => Self::I32,
+            b"uint" | b"uint32" => Self::U32,
+            b"float" | b"float32" => Self::F32,
+            b"double" | b"float64" => Self::F64,
+            _ => return None,
+        })
+    }
+
+    /// Returns the size of a value of this type in binary PLY.
+    fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+
+    /// Returns the factor to normalize the color component of this type to 0.0-1.0.
+    #[allow(clippy::cast_precision_loss)]
+    fn color_scale(self) -> f32 {
+        match self {
+            Self::I8 => 1. / i8::MAX as f32,
+            Self::U8 => 1. / u8::MAX as f32,
+            Self::I16 => 1. / i16::MAX as f32,
+            Self::U16 => 1. / u16::MAX as f32,
+            Self::I32 => 1. / i32::MAX as f32,
+            Self::U32 => 1. / u32::MAX as f32,
+            Self::F32 | Self::F64 => 1.,
+        }
+    }
+}
+
+struct Header<'a> {
+    format: Format,
+    elements: Vec<Element<'a>>,
+}
+
+struct Element<'a> {
+    name: &'a [u8],
+    count: usize,
+    properties: Vec<Property<'a>>,
+}
+
+struct Property<'a> {
+    name: &'a [u8],
+    ty: Type,
+    /// The type of the list length if this is a list property.
+    list: Option<Type>,
+}
+
+fn read_header<'a>(s: &mut &'a [u8]) -> Result<Header<'a>, ErrorKind> {
+    let mut remaining = s.len();
+    let mut line = next_line(s);
+    if tokens(line).next() != Some(b"ply") {
+        return Err(ErrorKind::Expected("ply", remaining));
+    }
+
+    remaining = s.len();
+    line = next_line(s);
+    let mut t = tokens(line);
+    if t.next() != Some(b"format") {
+        return Err(ErrorKind::Expected("format", remaining));
+    }
+    let format = match t.next() {
+        Some(b"ascii") => Format::Ascii,
+        Some(b"binary_little_endian") => Format::BinaryLittleEndian,
+        Some(b"binary_big_endian") => Format::BinaryBigEndian,
+        _ => return Err(ErrorKind::UnsupportedFormat(remaining)),
+    };
+
+    let mut elements: Vec<Element<'a>> = vec![];
+    loop {
+        if s.is_empty() {
+            return Err(ErrorKind::Expected("end_header", s.len()));
+        }
+        remaining = s.len();
+        line = next_line(s);
+        let mut t = tokens(line);
+        match t.next() {
+            Some(b"end_header") => break,
+            Some(b"comment" | b"obj_info") | None => {}
+            Some(b"element") => {
+                let name = t
+                    .next()
+                    .ok_or(ErrorKind::Expected("element name", remaining))?;
+                let count = t
+                    .next()
+                    .ok_or(ErrorKind::Expected("element count", remaining))?;
+                let count = int::parse::<u32>(count).ok_or(ErrorKind::Int(remaining))? as usize;
+                elements.push(Element {
+                    name,
+                    count,
+                    properties: vec![],
+                });
+            }
+            Some(b"property") => {
+                let element = elements
+                    .last_mut()
+                    .ok_or(ErrorKind::PropertyWithoutElement(remaining))?;
+                let ty = t
+                    .next()
+                    .ok_or(ErrorKind::Expected("property type", remaining))?;
+                let (ty, list) = if ty == b"list" {
+                    let len_ty = t
+                        .next()
+                        .and_then(Type::from_bytes)
+                        .ok_or(ErrorKind::UnknownType(remaining))?;
+                    let ty = t
+                        .next()
+                        .and_then(Type::from_bytes)
+                        .ok_or(ErrorKind::UnknownType(remaining))?;
+                    (ty, Some(len_ty))
+                } else {
+                    (
+                        Type::from_bytes(ty).ok_or(ErrorKind::UnknownType(remaining))?,
+                        None,
+                    )
+                };
+                let name = t
+                    .next()
+                    .ok_or(ErrorKind::Expected("property name", remaining))?;
+                element.properties.push(Property { name, ty, list });
+            }
+            Some(_) => {
+                return Err(ErrorKind::Expected(
+                    "comment, element, property, or end_header",
+                    remaining,
+                ))
+            }
+        }
+    }
+
+    Ok(Header { format, elements })
+}
+
+/// Returns the next line without line terminator, and advances `s` to the next line.
+fn next_line<'a>(s: &mut &'a [u8]) -> &'a [u8] {
+    let line = match memchr_naive(b'\n', s) {
+        Some(n) => {
+            let line = &s[..n];
+            *s = &s[n + 1..];
+            line
+        }
+        None => {
+            let line = *s;
+            *s = &[];
+            line
+        }
+    };
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+fn tokens(line: &[u8]) -> impl Iterator<Item = &[u8]> {
+    line.split(u8::is_ascii_whitespace)
+        .filter(|t| !t.is_empty())
+}
+
+// -----------------------------------------------------------------------------
+// Body
+
+trait Reader {
+    /// The error returned when the body is shorter than the header declares.
+    const UNEXPECTED_EOF: ErrorKind;
+    fn read(&mut self, ty: Type) -> Result<f64, ErrorKind>;
+    fn remaining(&self) -> usize;
+    /// Returns the minimum number of bytes of a value of the given type.
+    fn min_size(ty: Type) -> usize;
+}
+
+struct Ascii<'a> {
+    s: &'a [u8],
+}
+
+impl Reader for Ascii<'_> {
+    const UNEXPECTED_EOF: ErrorKind = ErrorKind::UnexpectedEof(0);
+
+    fn read(&mut self, _ty: Type) -> Result<f64, ErrorKind> {
+        while let Some((&b, s_next)) = self.s.split_first() {
+            if !b.is_ascii_whitespace() {
+                break;
+            }
+            self.s = s_next;
+        }
+        if self.s.is_empty() {
+            return Err(ErrorKind::UnexpectedEof(0));
+        }
+        let n = self
+            .s
+            .iter()
+            .position(u8::is_ascii_whitespace)
+            .unwrap_or(self.s.len());
+        let v = float::parse::<f64>(&self.s[..n]).ok_or(ErrorKind::Float(self.s.len()))?;
+        self.s = &self.s[n..];
+        Ok(v)
+    }
+
+    fn remaining(&self) -> usize {
+        self.s.len()
+    }
+
+    fn min_size(_ty: Type) -> usize {
+        1
+    }
+}
+
+struct Binary<'a, const BIG_ENDIAN: bool> {
+    s: &'a [u8],
+}
+
+impl<const BIG_ENDIAN: bool> Binary<'_, BIG_ENDIAN> {
+    #[inline]
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], ErrorKind> {
+        if self.s.len() < N {
+            return Err(ErrorKind::UnexpectedBinaryEof);
+        }
+        let (b, s_next) = self.s.split_at(N);
+        self.s = s_next;
+        Ok(b.try_into().unwrap())
+    }
+}
+
+macro_rules! from_bytes {
+    ($ty:ident, $b:expr) => {
+        if BIG_ENDIAN {
+            $ty::from_be_bytes($b)
+        } else {
+            $ty::from_le_bytes($b)
+        }
+    };
+}
+
+impl<const BIG_ENDIAN: bool> Reader for Binary<'_, BIG_ENDIAN> {
+    const UNEXPECTED_EOF: ErrorKind = ErrorKind::UnexpectedBinaryEof;
+
+    #[inline]
+    fn read(&mut self, ty: Type) -> Result<f64, ErrorKind> {
+        Ok(match ty {
+            Type::I8 => f64::from(from_bytes!(i8, self.take()?)),
+            Type::U8 => f64::from(self.take::<1>()?[0]),
+            Type::I16 => f64::from(from_bytes!(i16, self.take()?)),
+            Type::U16 => f64::from(from_bytes!(u16, self.take()?)),
+            Type::I32 => f64::from(from_bytes!(i32, self.take()?)),
+            Type::U32 => f64::from(from_bytes!(u32, self.take()?)),
+            Type::F32 => f64::from(from_bytes!(f32, self.take()?)),
+            Type::F64 => from_bytes!(f64, self.take()?),
+        })
+    }
+
+    fn remaining(&self) -> usize {
+        self.s.len()
+    }
+
+    fn min_size(ty: Type) -> usize {
+        ty.size()
+    }
+}
+
+fn read_body<R: Reader>(r: &mut R, header: &Header<'_>) -> Result<Mesh, ErrorKind> {
+    let mut mesh = Mesh::default();
+    for element in &header.elements {
+        // Elements without properties have no data.
+        if element.properties.is_empty() {
+            continue;
+        }
+        // Do not trust the count in the header: the body must be large enough
+        // to hold all records. (List properties can be empty.)
+        let min_record_size: usize = element
+            .properties
+            .iter()
+            .map(|p| R::min_size(p.list.unwrap_or(p.ty)))
+            .sum();
+        if r.remaining() / min_record_size < element.count {
+            return Err(R::UNEXPECTED_EOF);
+        }
+        match element.name {
+            b"vertex" => read_vertices(r, element, &mut mesh)?,
+            b"face" => read_faces(r, element, &mut mesh.faces)?,
+            _ => {
+                for _ in 0..element.count {
+                    for property in &element.properties {
+                        read_property(r, property, |_| {})?;
+                    }
+                }
+            }
+        }
+    }
+    let num_vertices =
+        u32::try_from(mesh.vertices.len()).map_err(|_| ErrorKind::TooManyVertices)?;
+    for face in &mesh.faces {
+        for &i in face {
+            if i >= num_vertices {
+                return Err(ErrorKind::Oob(i.into()));
+            }
+        }
+    }
+    Ok(mesh)
+}
+
+/// Reads a property, and calls `f` with each value.
+#[inline]
+fn read_property<R: Reader, F: FnMut(f64)>(
+    r: &mut R,
+    property: &Property<'_>,
+    mut f: F,
+) -> Result<(), ErrorKind> {
+    match property.list {
+        Some(len_ty) => {
+            let len = list_len(r.read(len_ty)?)?;
+            for _ in 0..len {
+                f(r.read(property.ty)?);
+            }
+        }
+        None => f(r.read(property.ty)?),
+    }
+    Ok(())
+}
+
+fn list_len(len: f64) -> Result<usize, ErrorKind> {
+    if len >= 0. && len.fract() == 0. && len <= u32::MAX as f64 {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Ok(len as usize)
+    } else {
+        Err(ErrorKind::InvalidListLength)
+    }
+}
+
+// Indices of the vertex attributes in the buffer used by read_vertices.
+const X: usize = 0;
+const NX: usize = 3;
+const U: usize = 6;
+const RED: usize = 8;
+const ALPHA: usize = 11;
+
+fn vertex_attribute(name: &[u8]) -> Option<usize> {
+    Some(match name {
+        b"x" => X,
+        b"y" => X + 1,
+        b"z" => X + 2,
+        b"nx" => NX,
+        b"ny" => NX + 1,
+        b"nz" => NX + 2,
+        b"u" | b"s" | b"texture_u" | b"texture_s" => U,
+        b"v" | b"t" | b"texture_v" | b"texture_t" => U + 1,
+        b"red" | b"diffuse_red" => RED,
+        b"green" | b"diffuse_green" => RED + 1,
+        b"blue" | b"diffuse_blue" => RED + 2,
+        b"alpha" => ALPHA,
+        _ => return None,
+    })
+}
+
+fn read_vertices<R: Reader>(
+    r: &mut R,
+    element: &Element<'_>,
+    mesh: &mut Mesh,
+) -> Result<(), ErrorKind> {
+    // (attribute index, scale)
+    let mut attributes = Vec::with_capacity(element.properties.len());
+    let mut has = [false; ALPHA + 1];
+    for property in &element.properties {
+        let attr = match (property.list, vertex_attribute(property.name)) {
+            (None, Some(attr)) => {
+                has[attr] = true;
+                let scale = if attr >= RED {
+                    property.ty.color_scale()
+                } else {
+                    1.
+                };
+                Some((attr, scale))
+            }
+            _ => None,
+        };
+        attributes.push(attr);
+    }
+    let has_normals = has[NX..NX + 3].iter().all(|&b| b);
+    let has_texcoords = has[U..U + 2].iter().all(|&b| b);
+    let has_colors = has[RED..RED + 3].iter().all(|&b| b);
+
+    // Do not trust the count in the header too much when allocating.
+    let cap = cmp::min(element.count, r.remaining());
+    mesh.vertices.reserve(cap);
+    if has_normals {
+        mesh.normals.reserve(cap);
+    }
+    if has_texcoords {
+        mesh.texcoords = vec![Vec::with_capacity(cap)];
+    }
+    if has_colors {
+        mesh.colors = vec![Vec::with_capacity(cap)];
+    }
+    for _ in 0..element.count {
+        let mut v = [0.; ALPHA + 1];
+        v[ALPHA] = 1.;
+        for (property, attr) in element.properties.iter().zip(&attributes) {
+            read_property(r, property, |value| {
+                if let Some((attr, scale)) = *attr {
+                    #[allow(clippy::cast_possible_truncation)]
+                    {
+                        v[attr] = value as f32 * scale;
+                    }
+                }
+            })?;
+        }
+        mesh.vertices.push([v[X], v[X + 1], v[X + 2]]);
+        if has_normals {
+            mesh.normals.push([v[NX], v[NX + 1], v[NX + 2]]);
+        }
+        if has_texcoords {
+            mesh.texcoords[0].push([v[U], v[U + 1]]);
+        }
+        if has_colors {
+            mesh.colors[0].push([v[RED], v[RED + 1], v[RED + 2], v[ALPHA]]);
+        }
+    }
+    Ok(())
+}
+
+fn read_faces<R: Reader>(
+    r: &mut R,
+    element: &Element<'_>,
+    faces: &mut Vec<[u32; 3]>,
+) -> Result<(), ErrorKind> {
+    let indices_property = element
+        .properties
+        .iter()
+        .position(|p| p.list.is_some() && matches!(p.name, b"vertex_indices" | b"vertex_index"));
+    faces.reserve(cmp::min(element.count, r.remaining()));
+    let mut indices = vec![];
+    for _ in 0..element.count {
+        for (i, property) in element.properties.iter().enumerate() {
+            if Some(i) != indices_property {
+                read_property(r, property, |_| {})?;
+                continue;
+            }
+            indices.clear();
+            let mut oob = None;
+            read_property(r, property, |value| {
+                if value >= 0. && value.fract() == 0. && value <= u32::MAX as f64 {
+                    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                    indices.push(value as u32);
+                } else if oob.is_none() {
+                    oob = Some(value);
+                }
+            })?;
+            if let Some(value) = oob {
+                #[allow(clippy::cast_possible_truncation)]
+                return Err(ErrorKind::Oob(value as i64));
+            }
+            // Triangulate polygon as triangle fan.
+            if let Some((&first, rest)) = indices.split_first() {
+                for w in rest.windows(2) {
+                    faces.push([first, w[0], w[1]]);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "ply
+format ascii 1.0
+comment test
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+";
+
+    fn assert_quad(scene: &Scene) {
+        let mesh = &scene.meshes[0];
+        assert_eq!(
+            mesh.vertices,
+            [[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 1., 0.]]
+        );
+        assert_eq!(mesh.colors[0][0], [1., 0., 0., 1.]);
+        assert_eq!(mesh.colors[0][3], [0., 0., 0., 1.]);
+        // The quad is triangulated as a triangle fan.
+        assert_eq!(mesh.faces, [[0, 1, 2], [0, 2, 3]]);
+    }
+
+    fn binary<const BIG_ENDIAN: bool>() -> Vec<u8> {
+        let format = if BIG_ENDIAN {
+            "binary_big_endian"
+        } else {
+            "binary_little_endian"
+        };
+        let mut bytes = HEADER.replace("ascii", format).into_bytes();
+        for (v, c) in [
+            ([0_f32, 0., 0.], [255_u8, 0, 0]),
+            ([1., 0., 0.], [0, 255, 0]),
+            ([1., 1., 0.], [0, 0, 255]),
+            ([0., 1., 0.], [0, 0, 0]),
+        ] {
+            for x in v {
+                bytes.extend(if BIG_ENDIAN {
+                    x.to_be_bytes()
+                } else {
+                    x.to_le_bytes()
+                });
+            }
+            bytes.extend(c);
+        }
+        bytes.push(4);
+        for i in 0_i32..4 {
+            bytes.extend(if BIG_ENDIAN {
+                i.to_be_bytes()
+            } else {
+                i.to_le_bytes()
+            });
+        }
+        bytes
+    }
+
+    fn kind(e: &io::Error) -> crate::ErrorKind {
+        crate::Error::from_io_error(e).unwrap().kind()
+    }
+
+    #[test]
+    fn ascii() {
+        let ply = format!(
+            "{HEADER}0 0 0 255 0 0\n1 0 0 0 255 0\n1 1 0 0 0 255\n0 1 0 0 0 0\n4 0 1 2 3\n"
+        );
+        assert_quad(&from_slice(ply.as_bytes()).unwrap());
+        // CRLF line terminators
+        let crlf = ply.replace('\n', "\r\n");
+        assert_quad(&from_slice(crlf.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn binary_little_endian() {
+        assert_quad(&from_slice(&binary::<false>()).unwrap());
+    }
+
+    #[test]
+    fn binary_big_endian() {
+        assert_quad(&from_slice(&binary::<true>()).unwrap());
+    }
+
+    #[test]
+    fn invalid_index() {
+        let vertices = "0 0 0 255 0 0\n1 0 0 0 255 0\n1 1 0 0 0 255\n0 1 0 0 0 0\n";
+        let e = from_slice(format!("{HEADER}{vertices}3 0 1 4\n").as_bytes()).unwrap_err();
+        assert_eq!(kind(&e), crate::ErrorKind::FaceIndexOutOfBounds);
+        let e = from_slice(format!("{HEADER}{vertices}3 0 1 -1\n").as_bytes()).unwrap_err();
+        assert_eq!(kind(&e), crate::ErrorKind::FaceIndexOutOfBounds);
+
+        let mut bytes = binary::<false>();
+        let len = bytes.len();
+        bytes[len - 4..].copy_from_slice(&(-1_i32).to_le_bytes());
+        let e = from_slice(&bytes).unwrap_err();
+        assert_eq!(kind(&e), crate::ErrorKind::FaceIndexOutOfBounds);
+        bytes.pop();
+        let e = from_slice(&bytes).unwrap_err();
+        assert_eq!(kind(&e), crate::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn element_count() {
+        // Elements without properties have no data.
+        let scene =
+            from_slice(b"ply\nformat ascii 1.0\nelement vertex 400000000\nend_header\n").unwrap();
+        assert!(scene.meshes[0].vertices.is_empty());
+
+        // The count is larger than the body can hold.
+        for format in ["ascii", "binary_little_endian", "binary_big_endian"] {
+            let ply = format!(
+                "ply\nformat {format} 1.0\nelement vertex 4294967295\nproperty float x\n\
+                 property float y\nproperty float z\nend_header\n0 0 0\n"
+            );
+            let e = from_slice(ply.as_bytes()).unwrap_err();
+            assert_eq!(kind(&e), crate::ErrorKind::UnexpectedEof, "{format}");
+        }
+        let mut bytes = binary::<false>();
+        bytes.truncate(bytes.len() - 17);
+        let e = from_slice(&bytes).unwrap_err();
+        assert_eq!(kind(&e), crate::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,579 @@
+#[cfg(any(feature = "collada", feature = "gltf", feature = "obj"))]
+use std::path::Component;
+use std::{
+    env, fmt, fs, io, mem,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::utils::uri::percent_decode;
+
+#[cfg(any(feature = "collada", feature = "gltf", feature = "obj"))]
+use crate::{Warning, WarningKind};
+
+type CustomResolver = dyn Fn(&str, Option<&Path>) -> Option<PathBuf> + Send + Sync;
+type ExistsFn = dyn Fn(&Path) -> bool + Send + Sync;
+
+/// Provides files to a [`Loader`](crate::Loader).
+///
+/// The resolver is used to read the loaded file itself and the files it
+/// depends on, such as OBJ material libraries and glTF buffers, and to check
+/// whether texture files exist. This allows loading assets from a store other
+/// than the file system, such as an archive or a cache.
+///
+/// This is implemented for functions and closures that read a file, which
+/// check the existence of files on the file system.
+///
+/// See [`Loader::resource_resolver`](crate::Loader::resource_resolver).
+///
+/// # Example
+///
+/// ```
+/// use std::{collections::HashMap, io, path::{Path, PathBuf}};
+///
+/// use mesh_loader::{Loader, ResourceResolver};
+///
+/// struct Store(HashMap<PathBuf, Vec<u8>>);
+///
+/// impl ResourceResolver for Store {
+///     fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+///         self.0.get(path).cloned().ok_or_else(|| io::ErrorKind::NotFound.into())
+///     }
+///     fn exists(&self, path: &Path) -> bool {
+///         self.0.contains_key(path)
+///     }
+/// }
+///
+/// let loader = Loader::default().resource_resolver(Store(HashMap::new()));
+/// ```
+pub trait ResourceResolver<B = Vec<u8>>: Send + Sync {
+    /// Reads the file at the given path.
+    fn read(&self, path: &Path) -> io::Result<B>;
+
+    /// Returns `true` if the file at the given path exists.
+    ///
+    /// This is used to look up textures. Default: [`Path::exists`]
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+}
+
+impl<B, F> ResourceResolver<B> for F
+where
+    F: Fn(&Path) -> io::Result<B> + Send + Sync,
+{
+    fn read(&self, path: &Path) -> io::Result<B> {
+        self(path)
+    }
+}
+
+/// How texture paths referenced by materials are resolved to files.
+///
+/// Texture references are resolved in the same way for all formats:
+///
+/// 1. `http://` and `https://` URLs are returned as is.
+/// 2. `file://` prefixes are removed, and backslashes are replaced with
+///    slashes, so that paths written on Windows can be found on other platforms.
+/// 3. The custom resolver, if set, is called with the normalized reference
+///    and the directory of the referencing file. If it returns `Some`, the
+///    returned path is used.
+/// 4. The reference is looked up relative to the directory of the referencing
+///    file, and then relative to each search path. If it is not found, only
+///    its file name is looked up in the same directories, since exporters
+///    often write absolute paths of the machine the file was created on.
+///
+/// Textures that are not found are reported as
+/// [`WarningKind::MissingTexture`](crate::WarningKind::MissingTexture)
+/// warnings. When the referencing file has no known directory (e.g., loaded
+/// from a slice without a path) and no search path matches, the reference is
+/// returned as is.
+///
+/// See [`Loader::texture_resolver`](crate::Loader::texture_resolver).
+///
+/// # Example
+///
+/// ```
+/// use mesh_loader::{Loader, TextureResolver};
+///
+/// let loader = Loader::default().texture_resolver(
+///     TextureResolver::default()
+///         .search_path("assets/textures")
+///         .case_insensitive(true),
+/// );
+/// ```
+#[derive(Clone, Default)]
+pub struct TextureResolver {
+    search_paths: Vec<PathBuf>,
+    case_insensitive: bool,
+    keep_missing: bool,
+    custom: Option<Arc<CustomResolver>>,
+    // The following are set by Loader.
+    exists: Option<Arc<ExistsFn>>,
+    packages: PackagePaths,
+}
+
+impl TextureResolver {
+    /// Adds a directory to look up textures in, after the directory of the
+    /// referencing file.
+    ///
+    /// Search paths are looked up in the order they are added.
+    #[must_use]
+    pub fn search_path<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.search_paths.push(dir.into());
+        self
+    }
+
+    /// Sets whether or not to match file and directory names
+    /// case-insensitively if there is no exact match.
+    ///
+    /// This is useful for assets created on case-insensitive file systems.
+    /// Directories are listed on the file system, so this does not find files
+    /// that are only provided by a custom [`ResourceResolver`].
+    ///
+    /// Default: `false`
+    #[must_use]
+    pub fn case_insensitive(mut self, enable: bool) -> Self {
+        self.case_insensitive = enable;
+        self
+    }
+
+    /// Sets whether or not to keep references to textures that are not found.
+    ///
+    /// If set to `true`, textures that are not found are kept as paths
+    /// relative to the directory of the referencing file. Otherwise, they are
+    /// removed from the material. They are reported as warnings either way.
+    ///
+    /// Default: `false`
+    #[must_use]
+    pub fn keep_missing(mut self, enable: bool) -> Self {
+        self.keep_missing = enable;
+        self
+    }
+
+    /// Use the given function or closure to resolve texture references before
+    /// the built-in lookup.
+    ///
+    /// The function is called with the normalized reference and the directory
+    /// of the referencing file (if known), and returns the path of the texture,
+    /// or `None` to fall back to the built-in lookup.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use mesh_loader::TextureResolver;
+    ///
+    /// let resolver = TextureResolver::default().custom(|reference, _dir| {
+    ///     // Textures of our assets are stored in a separate directory.
+    ///     let path = PathBuf::from("textures").join(reference.rsplit('/').next()?);
+    ///     if path.exists() { Some(path) } else { None }
+    /// });
+    /// ```
+    #[must_use]
+    pub fn custom<F>(mut self, resolver: F) -> Self
+    where
+        F: Fn(&str, Option<&Path>) -> Option<PathBuf> + Send + Sync + 'static,
+    {
+        self.custom = Some(Arc::new(resolver));
+        self
+    }
+
+    pub(crate) fn set_exists(&mut self, exists: Option<Arc<ExistsFn>>) {
+        self.exists = exists;
+    }
+
+    pub(crate) fn set_packages(&mut self, packages: PackagePaths) {
+        self.packages = packages;
+    }
+
+    /// Takes over the options set by the loader from `old`.
+    pub(crate) fn inherit(&mut self, old: &mut Self) {
+        self.exists = old.exists.take();
+        self.packages = mem::take(&mut old.packages);
+    }
+
+    /// Converts a `package://` or `file://` URI to a path.
+    ///
+    /// Returns `None` if `path` is not such a URI, and an error if the package
+    /// is not found.
+    pub(crate) fn uri_to_path(&self, path: &Path) -> Option<io::Result<PathBuf>> {
+        let uri = path.to_str()?;
+        if uri.starts_with("package://") {
+            Some(self.packages.resolve(uri).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("package of {uri:?} is not found"),
+                )
+            }))
+        } else {
+            let p = file_uri_to_path(uri.strip_prefix("file://")?);
+            Some(Ok(PathBuf::from(&*percent_decode(p))))
+        }
+    }
+
+    #[cfg(any(feature = "collada", feature = "gltf", feature = "obj"))]
+    fn exists(&self, path: &Path) -> bool {
+        match &self.exists {
+            Some(exists) => exists(path),
+            None => path.exists(),
+        }
+    }
+
+    /// Resolves the texture reference written in `file`.
+    ///
+    /// Returns `None` if the reference is empty, or if the texture is not
+    /// found and `keep_missing` is not set.
+    #[cfg(any(feature = "collada", feature = "gltf", feature = "obj"))]
+    pub(crate) fn resolve(
+        &self,
+        reference: &str,
+        dir: Option<&Path>,
+        file: Option<&Path>,
+        warnings: &mut Vec<Warning>,
+    ) -> Option<PathBuf> {
+        if reference.is_empty() {
+            return None;
+        }
+        if reference.starts_with("https://") || reference.starts_with("http://") {
+            return Some(reference.into());
+        }
+        let normalized = match self.packages.resolve(reference) {
+            Some(p) => p.to_string_lossy().into_owned(),
+            None => normalize(reference),
+        };
+        if let Some(custom) = &self.custom {
+            if let Some(p) = custom(&normalized, dir) {
+                return Some(p);
+            }
+        }
+
+        let p = Path::new(&*normalized);
+        if p.is_absolute() && self.exists(p) {
+            return Some(p.to_owned());
+        }
+        let file_name = p.file_name().map(Path::new).filter(|&n| n != p);
+        let bases = dir
+            .into_iter()
+            .chain(self.search_paths.iter().map(PathBuf::as_path));
+        for rel in Some(p).into_iter().chain(file_name) {
+            for base in bases.clone() {
+                if let Some(p) = self.find(base, rel) {
+                    return Some(p);
+                }
+            }
+        }
+
+        match dir {
+            // Relative paths without the base directory are returned as is.
+            None => Some(p.to_owned()),
+            Some(dir) => {
+                warnings.push(Warning::new(
+                    WarningKind::MissingTexture,
+                    format!("texture {reference:?} is not found"),
+                    file,
+                ));
+                if self.keep_missing {
+                    Some(dir.join(p))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    #[cfg(any(feature = "collada", feature = "gltf", feature = "obj"))]
+    fn find(&self, base: &Path, rel: &Path) -> Option<PathBuf> {
+        let p = base.join(rel);
+        if self.exists(&p) {
+            return Some(p);
+        }
+        if !self.case_insensitive || rel.is_absolute() {
+            return None;
+        }
+        let mut p = base.to_owned();
+        for component in rel.components() {
+            match component {
+                Component::Normal(name) => {
+                    let exact = p.join(name);
+                    if self.exists(&exact) {
+                        p = exact;
+                        continue;
+                    }
+                    let name = name.to_str()?.to_lowercase();
+                    let entry = fs::read_dir(&p).ok()?.filter_map(Result::ok).find(|e| {
+                        e.file_name()
+                            .to_str()
+                            .map_or(false, |n| n.to_lowercase() == name)
+                    })?;
+                    p = entry.path();
+                }
+                _ => p.push(component),
+            }
+        }
+        Some(p)
+    }
+}
+
+impl fmt::Debug for TextureResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TextureResolver")
+            .field("search_paths", &self.search_paths)
+            .field("case_insensitive", &self.case_insensitive)
+            .field("keep_missing", &self.keep_missing)
+            .field("custom", &self.custom.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Decodes `file://` URIs, and replaces backslashes with slashes.
+#[cfg(any(feature = "collada", feature = "gltf", feature = "obj"))]
+fn normalize(reference: &str) -> String {
+    let mut p = match reference.strip_prefix("file://") {
+        Some(p) => percent_decode(file_uri_to_path(p)).into_owned(),
+        None => reference.to_owned(),
+    }
+    .replace('\\', "/");
+    // Some exporters write "/../foo" for "../foo".
+    if p.starts_with("/..") {
+        p.remove(0);
+    }
+    while p.starts_with("./") {
+        p.drain(..2);
+    }
+    p
+}
+
+/// Converts the part of a `file://` URI after the scheme to a path.
+fn file_uri_to_path(p: &str) -> &str {
+    let p = p.strip_prefix("localhost").unwrap_or(p);
+    match p.as_bytes() {
+        // file:///C:/foo -> C:/foo
+        [b'/', _, b':', ..] => &p[1..],
+        _ => p,
+    }
+}
+
+/// A table of ROS package paths used to resolve `package://` URIs, such as
+/// `package://robot_description/meshes/base.dae`.
+///
+/// Packages are looked up in the following order:
+///
+/// 1. Packages added by [`package`](Self::package).
+/// 2. Search paths added by [`search_path`](Self::search_path) (e.g., entries
+///    of `ROS_PACKAGE_PATH`): the search path itself if its directory name is
+///    the package name, and otherwise a directory with the package name
+///    anywhere under the search path.
+///
+/// See [`Loader::package_paths`](crate::Loader::package_paths).
+///
+/// # Example
+///
+/// ```
+/// use mesh_loader::{Loader, PackagePaths};
+///
+/// let loader = Loader::default().package_paths(
+///     PackagePaths::from_env().package("robot_description", "/opt/robot/description"),
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PackagePaths {
+    packages: Vec<(String, PathBuf)>,
+    search_paths: Vec<PathBuf>,
+}
+
+impl PackagePaths {
+    /// Creates an empty table.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a table from the `ROS_PACKAGE_PATH` (ROS 1) and
+    /// `AMENT_PREFIX_PATH` (ROS 2) environment variables.
+    #[must_use]
+    pub fn from_env() -> Self {
+        let mut search_paths = vec![];
+        if let Some(paths) = env::var_os("ROS_PACKAGE_PATH") {
+            search_paths.extend(env::split_paths(&paths).filter(|p| !p.as_os_str().is_empty()));
+        }
+        if let Some(paths) = env::var_os("AMENT_PREFIX_PATH") {
+            search_paths.extend(
+                env::split_paths(&paths)
+                    .filter(|p| !p.as_os_str().is_empty())
+                    .map(|p| p.join("share")),
+            );
+        }
+        Self {
+            packages: vec![],
+            search_paths,
+        }
+    }
+
+    /// Adds a package located in the given directory.
+    #[must_use]
+    pub fn package<N: Into<String>, P: Into<PathBuf>>(mut self, name: N, dir: P) -> Self {
+        self.packages.push((name.into(), dir.into()));
+        self
+    }
+
+    /// Adds a directory to search packages in.
+    #[must_use]
+    pub fn search_path<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.search_paths.push(dir.into());
+        self
+    }
+
+    /// Returns the directory of the given package.
+    pub fn find(&self, name: &str) -> Option<PathBuf> {
+        // Packages under a search path are nested in a few directories at most.
+        const MAX_DEPTH: usize = 4;
+        fn crawl(dir: &Path, name: &str, depth: usize) -> Option<PathBuf> {
+            let candidate = dir.join(name);
+            if candidate.is_dir() {
+                return Some(candidate);
+            }
+            // Packages do not contain other packages.
+            if depth == 0 || dir.join("package.xml").exists() {
+                return None;
+            }
+            let mut entries: Vec<_> = fs::read_dir(dir)
+                .ok()?
+                .filter_map(Result::ok)
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.is_dir()
+                        && !p
+                            .file_name()
+                            .and_then(|n| n.to_str())
+                            .map_or(true, |n| n.starts_with('.'))
+                })
+                .collect();
+            entries.sort();
+            entries.iter().find_map(|dir| crawl(dir, name, depth - 1))
+        }
+
+        if let Some((_, dir)) = self.packages.iter().find(|(n, _)| n == name) {
+            return Some(dir.clone());
+        }
+        for dir in &self.search_paths {
+            if dir.file_name().and_then(|n| n.to_str()) == Some(name) && dir.is_dir() {
+                return Some(dir.clone());
+            }
+        }
+        self.search_paths
+            .iter()
+            .find_map(|dir| crawl(dir, name, MAX_DEPTH))
+    }
+
+    /// Converts a `package://` URI to a path.
+    ///
+    /// Returns `None` if `uri` is not a `package://` URI or the package is
+    /// not found.
+    pub fn resolve(&self, uri: &str) -> Option<PathBuf> {
+        let rest = uri.strip_prefix("package://")?;
+        let (name, rest) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i + 1..]),
+            None => (rest, ""),
+        };
+        Some(self.find(name)?.join(&*percent_decode(rest)))
+    }
+}
+
+#[cfg(test)]
+#[cfg(any(feature = "collada", feature = "gltf", feature = "obj"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve() {
+        let dir = std::env::temp_dir().join("mesh-loader-texture-resolver");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("Textures")).unwrap();
+        fs::write(dir.join("Textures/Wood.PNG"), b"").unwrap();
+        let mut warnings = vec![];
+
+        let resolver = TextureResolver::default();
+        for reference in [
+            "Textures/Wood.PNG",
+            "./Textures\\Wood.PNG",
+            "file:///C:/Users/foo/Textures/Wood.PNG",
+            // Only the file name is found in the search path.
+            "C:\\Users\\foo\\Wood.PNG",
+        ] {
+            let resolved = resolver.clone().search_path(dir.join("Textures")).resolve(
+                reference,
+                Some(&dir),
+                None,
+                &mut warnings,
+            );
+            assert_eq!(resolved, Some(dir.join("Textures/Wood.PNG")), "{reference}");
+        }
+        assert!(warnings.is_empty());
+
+        assert_eq!(
+            resolver.resolve("textures/wood.png", Some(&dir), None, &mut warnings),
+            None
+        );
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, WarningKind::MissingTexture);
+        assert_eq!(
+            resolver.clone().keep_missing(true).resolve(
+                "textures/wood.png",
+                Some(&dir),
+                None,
+                &mut warnings
+            ),
+            Some(dir.join("textures/wood.png"))
+        );
+        assert_eq!(
+            resolver.case_insensitive(true).resolve(
+                "textures/wood.png",
+                Some(&dir),
+                None,
+                &mut warnings
+            ),
+            Some(dir.join("Textures/Wood.PNG"))
+        );
+        assert_eq!(warnings.len(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn package_paths() {
+        let dir = std::env::temp_dir().join("mesh-loader-package-paths");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src/robot_description/meshes")).unwrap();
+        fs::write(dir.join("src/robot_description/package.xml"), b"").unwrap();
+
+        let packages = PackagePaths::new().search_path(&dir);
+        assert_eq!(
+            packages.resolve("package://robot_description/meshes/base%20link.dae"),
+            Some(dir.join("src/robot_description/meshes/base link.dae"))
+        );
+        assert_eq!(packages.resolve("package://unknown/base.dae"), None);
+        assert_eq!(packages.resolve("meshes/base.dae"), None);
+        let packages = packages.package("robot_description", "/opt/robot");
+        assert_eq!(
+            packages.resolve("package://robot_description/meshes/base.dae"),
+            Some(Path::new("/opt/robot/meshes/base.dae").to_owned())
+        );
+
+        let resolver = TextureResolver::default();
+        assert!(resolver.uri_to_path(Path::new("meshes/base.dae")).is_none());
+        assert_eq!(
+            resolver
+                .uri_to_path(Path::new("file://localhost/opt/robot/base%20link.dae"))
+                .unwrap()
+                .unwrap(),
+            Path::new("/opt/robot/base link.dae")
+        );
+        assert!(resolver
+            .uri_to_path(Path::new("package://unknown/base.dae"))
+            .unwrap()
+            .is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
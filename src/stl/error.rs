@@ -1,5 +1,7 @@
 use std::{fmt, io, path::Path};
 
+use crate::error::{Error, Format, Location};
+
 #[cfg_attr(test, derive(Debug))]
 pub(super) enum ErrorKind {
     // ASCII STL error
@@ -11,6 +13,7 @@ pub(super) enum ErrorKind {
     // binary STL error
     TooSmall,
     InvalidSize,
+    TriangleCountMismatch(u32, usize),
     TooManyTriangles,
 }
 
@@ -28,10 +31,19 @@ impl ErrorKind {
             // binary STL error (always points file:1:1, as error occurs only during reading the header)
             _ => start.len(),
         };
-        crate::error::with_location(
-            &crate::error::invalid_data(self.to_string()),
-            &crate::error::Location::find(remaining, start, path),
-        )
+        let kind = match self {
+            Self::Expected(..)
+            | Self::ExpectedNewline(..)
+            | Self::ExpectedSpace(..)
+            | Self::NotAscii(..) => crate::ErrorKind::Syntax,
+            Self::Float(..) => crate::ErrorKind::InvalidFloat,
+            Self::TooSmall | Self::InvalidSize | Self::TriangleCountMismatch(..) => {
+                crate::ErrorKind::InvalidSize
+            }
+            Self::TooManyTriangles => crate::ErrorKind::TooLarge,
+        };
+        let e = Error::new(kind, self.to_string()).with_format(Format::Stl);
+        Location::find(remaining, start, path).attach(e).into()
     }
 }
 
@@ -76,6 +88,10 @@ impl fmt::Display for ErrorKind {
                 "failed to determine STL storage representation: \
                  not valid ASCII STL and size is invalid as binary STL",
             ),
+            Self::TriangleCountMismatch(count, actual) => write!(
+                f,
+                "number of triangles in header ({count}) does not match the size ({actual})"
+            ),
             Self::TooManyTriangles => f.write_str("too many triangles"),
         }
     }
@@ -1,12 +1,19 @@
-//! [STL] (.stl) parser.
+//! [STL] (.stl) parser and writer.
 //!
 //! [STL]: https://en.wikipedia.org/wiki/STL_(file_format)
 
 mod error;
+mod read;
+mod write;
 
 use std::{io, path::Path, str};
 
 use self::error::ErrorKind;
+pub(crate) use self::read::from_binary_reader_internal;
+pub use self::{
+    read::{from_binary_reader, BinaryTriangles},
+    write::{to_vec, to_writer, WriteOptions},
+};
 use crate::{
     utils::{
         bytes::{memchr_naive_table, starts_with},
@@ -18,20 +25,25 @@ use crate::{
 /// Parses meshes from bytes of binary or ASCII STL.
 #[inline]
 pub fn from_slice(bytes: &[u8]) -> io::Result<Scene> {
-    from_slice_internal(bytes, None, false)
+    from_slice_internal(bytes, None, false, false)
 }
 
 pub(crate) fn from_slice_internal(
     bytes: &[u8],
     path: Option<&Path>,
     parse_color: bool,
+    strict: bool,
 ) -> io::Result<Scene> {
     let mut meshes = Vec::with_capacity(1);
     if is_ascii_stl(bytes) {
         match read_ascii_stl(bytes, &mut meshes) {
             Ok(()) => {
                 let materials = (0..meshes.len()).map(|_| Material::default()).collect();
-                return Ok(Scene { materials, meshes });
+                return Ok(Scene {
+                    materials,
+                    meshes,
+                    ..Default::default()
+                });
             }
             // If there is solid but no space or line break after solid or no
             // facet normal, even valid ASCII text may be binary STL.
@@ -44,11 +56,11 @@ pub(crate) fn from_slice_internal(
             Err(e) => return Err(e.into_io_error(bytes, path)),
         }
     }
-    match read_binary_header(bytes, parse_color) {
+    match read_binary_header(bytes, parse_color, strict) {
         Ok(header) => {
             let mesh = read_binary_triangles(&header);
             let mut material = Material::default();
-            if header.reverse_color && mesh.colors[0].is_empty() {
+            if header.reverse_color && mesh.colors.is_empty() {
                 let color = header.default_color;
                 material.color.diffuse = Some(color);
                 material.color.specular = Some(color);
@@ -57,6 +69,7 @@ pub(crate) fn from_slice_internal(
             Ok(Scene {
                 materials: vec![material],
                 meshes,
+                ..Default::default()
             })
         }
         Err(e) => Err(e.into_io_error(bytes, path)),
@@ -113,7 +126,11 @@ struct BinaryHeader<'a> {
     triangle_bytes: &'a [u8],
 }
 
-fn read_binary_header(bytes: &[u8], parse_color: bool) -> Result<BinaryHeader<'_>, ErrorKind> {
+fn read_binary_header(
+    bytes: &[u8],
+    parse_color: bool,
+    strict: bool,
+) -> Result<BinaryHeader<'_>, ErrorKind> {
     if bytes.len() < TRIANGLE_START {
         return Err(ErrorKind::TooSmall);
     }
@@ -123,8 +140,9 @@ fn read_binary_header(bytes: &[u8], parse_color: bool) -> Result<BinaryHeader<'_
 
     let extra_bytes = triangle_bytes.len() % TRIANGLE_SIZE;
     if extra_bytes != 0 {
-        if extra_bytes == 1 && triangle_bytes.ends_with(b"\n")
-            || extra_bytes == 2 && triangle_bytes.ends_with(b"\r\n")
+        if !strict
+            && (extra_bytes == 1 && triangle_bytes.ends_with(b"\n")
+                || extra_bytes == 2 && triangle_bytes.ends_with(b"\r\n"))
         {
             // Some buggy STL files have a newline after triangles...
         } else {
@@ -134,37 +152,26 @@ fn read_binary_header(bytes: &[u8], parse_color: bool) -> Result<BinaryHeader<'_
 
     // Some STL files contain bogus count.
     // So we calculate num_triangles based on the size of the input.
-    // let num_triangles = &bytes[HEADER_SIZE..TRIANGLE_START];
-    // let num_triangles = u32::from_le_bytes(num_triangles.try_into().unwrap());
-    // assert_eq!(triangle_bytes.len() / TRIANGLE_SIZE, num_triangles as usize);
     let num_triangles = triangle_bytes.len() / TRIANGLE_SIZE;
+    if strict {
+        let count = &bytes[HEADER_SIZE..TRIANGLE_START];
+        let count = u32::from_le_bytes(count.try_into().unwrap());
+        if count as usize != num_triangles {
+            return Err(ErrorKind::TriangleCountMismatch(count, num_triangles));
+        }
+    }
     let num_vertices = num_triangles * 3;
     if u32::try_from(num_vertices).is_err() {
         // face is [u32; 3], so num_vertices must not exceed u32::MAX.
         return Err(ErrorKind::TooManyTriangles);
     }
 
-    // Use the same default color (light gray) as assimp: https://github.com/assimp/assimp/blob/v5.3.1/code/AssetLib/STL/STLLoader.cpp#L183-L184
-    let mut default_color = [0.6, 0.6, 0.6, 0.6];
+    let mut default_color = DEFAULT_COLOR;
     let mut reverse_color = false;
     if parse_color {
-        // Handling colors in STL is not standardized. We use the same way as assimp.
-        // https://github.com/assimp/assimp/blob/v5.3.1/code/AssetLib/STL/STLLoader.cpp#L413-L431
-        let mut s = header;
-        let expect = b"COLOR=";
-        while s.len() >= expect.len() + 4 {
-            if token(&mut s, expect) {
-                const INV_BYTE: f32 = 1. / 255.;
-                reverse_color = true;
-                default_color = [
-                    s[0] as f32 * INV_BYTE,
-                    s[1] as f32 * INV_BYTE,
-                    s[2] as f32 * INV_BYTE,
-                    s[3] as f32 * INV_BYTE,
-                ];
-                break;
-            }
-            s = &s[1..];
+        if let Some(color) = header_color(header) {
+            reverse_color = true;
+            default_color = color;
         }
     }
 
@@ -176,6 +183,50 @@ fn read_binary_header(bytes: &[u8], parse_color: bool) -> Result<BinaryHeader<'_
     })
 }
 
+// Use the same default color (light gray) as assimp: https://github.com/assimp/assimp/blob/v5.3.1/code/AssetLib/STL/STLLoader.cpp#L183-L184
+const DEFAULT_COLOR: Color4 = [0.6, 0.6, 0.6, 0.6];
+
+/// Returns the default color stored in the header, which also means that the
+/// facet colors are stored in RGB order.
+fn header_color(header: &[u8]) -> Option<Color4> {
+    // Handling colors in STL is not standardized. We use the same way as assimp.
+    // https://github.com/assimp/assimp/blob/v5.3.1/code/AssetLib/STL/STLLoader.cpp#L413-L431
+    let mut s = header;
+    let expect = b"COLOR=";
+    while s.len() >= expect.len() + 4 {
+        if token(&mut s, expect) {
+            const INV_BYTE: f32 = 1. / 255.;
+            return Some([
+                s[0] as f32 * INV_BYTE,
+                s[1] as f32 * INV_BYTE,
+                s[2] as f32 * INV_BYTE,
+                s[3] as f32 * INV_BYTE,
+            ]);
+        }
+        s = &s[1..];
+    }
+    None
+}
+
+/// Returns the facet color stored in the attribute byte count.
+fn facet_color(attribute: u16, reverse_color: bool) -> Color4 {
+    // Handling colors in STL is not standardized. We use the same way as assimp.
+    // https://github.com/assimp/assimp/blob/v5.3.1/code/AssetLib/STL/STLLoader.cpp#L502-L529
+    const INV_VAL: f32 = 1. / 31.;
+    let a = 1.;
+    if reverse_color {
+        let r = (attribute & 0x1F) as f32 * INV_VAL;
+        let g = ((attribute & (0x1F << 5)) >> 5) as f32 * INV_VAL;
+        let b = ((attribute & (0x1F << 10)) >> 10) as f32 * INV_VAL;
+        [r, g, b, a]
+    } else {
+        let b = (attribute & 0x1F) as f32 * INV_VAL;
+        let g = ((attribute & (0x1F << 5)) >> 5) as f32 * INV_VAL;
+        let r = ((attribute & (0x1F << 10)) >> 10) as f32 * INV_VAL;
+        [r, g, b, a]
+    }
+}
+
 fn read_binary_triangles(header: &BinaryHeader<'_>) -> Mesh {
     let bytes = header.triangle_bytes;
 
@@ -211,25 +262,11 @@ fn read_binary_triangles(header: &BinaryHeader<'_>) -> Mesh {
         normals.clone_from_slice(&[triangle.normal; 3]);
         *face = [vertices_len, vertices_len + 1, vertices_len + 2];
 
-        // Handling colors in STL is not standardized. We use the same way as assimp.
-        // https://github.com/assimp/assimp/blob/v5.3.1/code/AssetLib/STL/STLLoader.cpp#L502-L529
-        if triangle.color & has_color_mask != 0 {
-            const INV_VAL: f32 = 1. / 31.;
-            if mesh.colors[0].is_empty() {
-                mesh.colors[0] = vec![header.default_color; num_vertices];
+        if triangle.attribute & has_color_mask != 0 {
+            if mesh.colors.is_empty() {
+                mesh.colors.push(vec![header.default_color; num_vertices]);
             }
-            let a = 1.;
-            let color = if header.reverse_color {
-                let r = (triangle.color & 0x1F) as f32 * INV_VAL;
-                let g = ((triangle.color & (0x1F << 5)) >> 5) as f32 * INV_VAL;
-                let b = ((triangle.color & (0x1F << 10)) >> 10) as f32 * INV_VAL;
-                [r, g, b, a]
-            } else {
-                let b = (triangle.color & 0x1F) as f32 * INV_VAL;
-                let g = ((triangle.color & (0x1F << 5)) >> 5) as f32 * INV_VAL;
-                let r = ((triangle.color & (0x1F << 10)) >> 10) as f32 * INV_VAL;
-                [r, g, b, a]
-            };
+            let color = facet_color(triangle.attribute, header.reverse_color);
             mesh.colors[0][vertices_len as usize..vertices_len as usize + 3]
                 .copy_from_slice(&[color, color, color]);
         }
@@ -253,11 +290,11 @@ fn read_binary_triangle(mut buf: &[u8]) -> Triangle {
     let vertex1 = [f32le(&mut buf), f32le(&mut buf), f32le(&mut buf)];
     let vertex2 = [f32le(&mut buf), f32le(&mut buf), f32le(&mut buf)];
     let vertex3 = [f32le(&mut buf), f32le(&mut buf), f32le(&mut buf)];
-    let color = u16::from_le_bytes(buf[..2].try_into().unwrap());
+    let attribute = u16::from_le_bytes(buf[..2].try_into().unwrap());
     Triangle {
         normal,
         vertices: [vertex1, vertex2, vertex3],
-        color,
+        attribute,
     }
 }
 
@@ -421,7 +458,7 @@ fn read_ascii_stl(mut s: &[u8], meshes: &mut Vec<Mesh>) -> Result<(), ErrorKind>
                 Triangle {
                     normal,
                     vertices,
-                    color: 0,
+                    attribute: 0,
                 },
             );
         }
@@ -603,10 +640,15 @@ fn skip_spaces_and_lines_until_token(s: &mut &[u8], token: &'static [u8]) -> boo
     false
 }
 
-struct Triangle {
-    normal: Vec3,
-    vertices: [Vec3; 3],
-    color: u16,
+/// Triangle (facet) of STL.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct Triangle {
+    pub normal: Vec3,
+    pub vertices: [Vec3; 3],
+    /// The attribute byte count of binary STL, which some software uses to
+    /// store the facet color. This is always `0` for ASCII STL.
+    pub attribute: u16,
 }
 
 trait FromStl: Sized {
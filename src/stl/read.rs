@@ -0,0 +1,267 @@
+use std::{
+    fmt,
+    io::{self, Read},
+};
+
+use super::{
+    facet_color, header_color, read_binary_triangle, FromStl, Triangle, DEFAULT_COLOR, HEADER_SIZE,
+    TRIANGLE_COUNT_SIZE, TRIANGLE_SIZE,
+};
+use crate::{Error, ErrorKind, Format, Material, Mesh, Scene};
+
+/// Iterator over triangles of binary STL read from [`io::Read`].
+///
+/// Unlike [`from_slice`](super::from_slice), this reads one triangle at a
+/// time, so the whole file does not need to be in memory.
+///
+/// Note that this reads from `reader` in small chunks, so it is often
+/// preferable to wrap `reader` with [`io::BufReader`].
+///
+/// # Example
+///
+/// ```no_run
+/// use std::io;
+///
+/// use mesh_loader::stl::BinaryTriangles;
+///
+/// // Compute the height of a mesh piped from stdin.
+/// let mut min_z = f32::INFINITY;
+/// let mut max_z = f32::NEG_INFINITY;
+/// for triangle in BinaryTriangles::new(io::stdin().lock())? {
+///     for vertex in triangle?.vertices {
+///         min_z = min_z.min(vertex[2]);
+///         max_z = max_z.max(vertex[2]);
+///     }
+/// }
+/// println!("{}", max_z - min_z);
+/// # Ok::<(), io::Error>(())
+/// ```
+pub struct BinaryTriangles<R> {
+    reader: R,
+    header: [u8; HEADER_SIZE],
+    num_triangles: u32,
+    done: bool,
+    trailing_newline: bool,
+}
+
+impl<R: Read> BinaryTriangles<R> {
+    /// Reads the header of binary STL from the given reader.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut buf = [0; HEADER_SIZE + TRIANGLE_COUNT_SIZE];
+        reader.read_exact(&mut buf).map_err(|e| {
+            if e.kind() == io::ErrorKind::UnexpectedEof {
+                invalid_data(ErrorKind::InvalidSize, "size is too small as binary STL")
+            } else {
+                e
+            }
+        })?;
+        let mut header = [0; HEADER_SIZE];
+        header.copy_from_slice(&buf[..HEADER_SIZE]);
+        let num_triangles = u32::from_le_bytes(buf[HEADER_SIZE..].try_into().unwrap());
+        Ok(Self {
+            reader,
+            header,
+            num_triangles,
+            done: false,
+            trailing_newline: false,
+        })
+    }
+
+    /// Returns the 80-byte header.
+    pub fn header(&self) -> &[u8; HEADER_SIZE] {
+        &self.header
+    }
+
+    /// Returns the number of triangles stored in the header.
+    ///
+    /// Some STL files contain bogus count, so the actual number of triangles
+    /// returned by this iterator may be different.
+    pub fn num_triangles(&self) -> u32 {
+        self.num_triangles
+    }
+
+    fn read_triangle(&mut self) -> io::Result<Option<Triangle>> {
+        let mut buf = [0; TRIANGLE_SIZE];
+        let mut filled = 0;
+        while filled < TRIANGLE_SIZE {
+            match self.reader.read(&mut buf[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        match &buf[..filled] {
+            [] => Ok(None),
+            // Some buggy STL files have a newline after triangles...
+            b"\n" | b"\r\n" => {
+                self.trailing_newline = true;
+                Ok(None)
+            }
+            [..] if filled == TRIANGLE_SIZE => Ok(Some(read_binary_triangle(&buf))),
+            _ => Err(invalid_data(
+                ErrorKind::InvalidSize,
+                "size is invalid as binary STL",
+            )),
+        }
+    }
+}
+
+impl<R> fmt::Debug for BinaryTriangles<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BinaryTriangles")
+            .field("num_triangles", &self.num_triangles)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<R: Read> Iterator for BinaryTriangles<R> {
+    type Item = io::Result<Triangle>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let res = self.read_triangle().transpose();
+        if !matches!(res, Some(Ok(_))) {
+            self.done = true;
+        }
+        res
+    }
+}
+
+/// Parses meshes from binary STL read from [`io::Read`].
+///
+/// Unlike [`from_slice`](super::from_slice), this does not support ASCII STL,
+/// but does not require the whole file to be in memory.
+///
+/// Note that this reads from `reader` in small chunks, so it is often
+/// preferable to wrap `reader` with [`io::BufReader`].
+#[inline]
+pub fn from_binary_reader<R: Read>(reader: R) -> io::Result<Scene> {
+    from_binary_reader_internal(reader, false, false)
+}
+
+pub(crate) fn from_binary_reader_internal<R: Read>(
+    reader: R,
+    parse_color: bool,
+    strict: bool,
+) -> io::Result<Scene> {
+    let mut triangles = BinaryTriangles::new(reader)?;
+    let header_color = if parse_color {
+        header_color(triangles.header())
+    } else {
+        None
+    };
+    let reverse_color = header_color.is_some();
+    let default_color = header_color.unwrap_or(DEFAULT_COLOR);
+    let has_color_mask = if parse_color { 1 << 15 } else { 0 };
+
+    let mut mesh = Mesh::default();
+    for triangle in &mut triangles {
+        let triangle = triangle?;
+        // face is [u32; 3], so the number of vertices must not exceed u32::MAX.
+        if mesh.vertices.len() > u32::MAX as usize - 3 {
+            return Err(invalid_data(ErrorKind::TooLarge, "too many triangles"));
+        }
+        if triangle.attribute & has_color_mask != 0 {
+            if mesh.colors.is_empty() {
+                mesh.colors.push(vec![default_color; mesh.vertices.len()]);
+            }
+            let color = facet_color(triangle.attribute, reverse_color);
+            mesh.colors[0].extend_from_slice(&[color; 3]);
+        } else if let Some(colors) = mesh.colors.first_mut() {
+            colors.extend_from_slice(&[default_color; 3]);
+        }
+        Mesh::push_triangle(&mut mesh, triangle);
+    }
+
+    if strict {
+        if triangles.trailing_newline {
+            return Err(invalid_data(
+                ErrorKind::InvalidSize,
+                "size is invalid as binary STL",
+            ));
+        }
+        let num_triangles = mesh.faces.len();
+        if triangles.num_triangles as usize != num_triangles {
+            return Err(invalid_data(
+                ErrorKind::InvalidSize,
+                &format!(
+                    "number of triangles in header ({}) does not match the size ({num_triangles})",
+                    triangles.num_triangles
+                ),
+            ));
+        }
+    }
+
+    let mut material = Material::default();
+    if reverse_color && mesh.colors.is_empty() {
+        material.color.diffuse = Some(default_color);
+        material.color.specular = Some(default_color);
+    }
+    Ok(Scene {
+        materials: vec![material],
+        meshes: vec![mesh],
+        ..Default::default()
+    })
+}
+
+#[cold]
+fn invalid_data(kind: ErrorKind, msg: &str) -> io::Error {
+    Error::new(kind, msg).with_format(Format::Stl).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stl::{to_vec, WriteOptions};
+
+    #[test]
+    fn same_as_from_slice() {
+        let mut mesh = Mesh {
+            vertices: vec![[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 1., 0.]],
+            faces: vec![[0, 1, 2], [0, 2, 3]],
+            ..Default::default()
+        };
+        mesh.colors = vec![vec![[1., 0., 0., 1.]; 4]];
+        let mut scene = Scene::default();
+        scene.meshes.push(mesh);
+
+        for options in [WriteOptions::new(), WriteOptions::new().write_color(true)] {
+            let mut bytes = to_vec(&scene, options).unwrap();
+            let triangles = BinaryTriangles::new(&bytes[..]).unwrap();
+            assert_eq!(triangles.num_triangles(), 2);
+            assert_eq!(triangles.count(), 2);
+
+            let expected = super::super::from_slice_internal(&bytes, None, true, false).unwrap();
+            let read = from_binary_reader_internal(&bytes[..], true, false).unwrap();
+            assert_eq!(read.meshes[0].vertices, expected.meshes[0].vertices);
+            assert_eq!(read.meshes[0].normals, expected.meshes[0].normals);
+            assert_eq!(read.meshes[0].faces, expected.meshes[0].faces);
+            assert_eq!(read.meshes[0].colors, expected.meshes[0].colors);
+
+            assert!(from_binary_reader_internal(&bytes[..], false, true).is_ok());
+            bytes.extend_from_slice(b"\r\n");
+            assert_eq!(
+                from_binary_reader(&bytes[..]).unwrap().meshes[0]
+                    .faces
+                    .len(),
+                2
+            );
+            // Trailing newline and bogus count are rejected in strict mode.
+            assert!(from_binary_reader_internal(&bytes[..], false, true).is_err());
+            assert!(super::super::from_slice_internal(&bytes, None, false, true).is_err());
+            bytes.truncate(bytes.len() - 2);
+            bytes[80] = 3;
+            assert!(from_binary_reader_internal(&bytes[..], false, true).is_err());
+            assert!(super::super::from_slice_internal(&bytes, None, false, true).is_err());
+            assert!(from_binary_reader(&bytes[..]).is_ok());
+            bytes.extend_from_slice(b"\r\n");
+            bytes.pop();
+            bytes.push(0);
+            assert!(from_binary_reader(&bytes[..]).is_err());
+        }
+        assert!(from_binary_reader(&[0; 83][..]).is_err());
+    }
+}
@@ -0,0 +1,298 @@
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
+
+use super::{HEADER_SIZE, TRIANGLE_SIZE};
+use crate::{Color4, Mesh, Scene, Vec3};
+
+/// Options for [`to_writer`] and [`to_vec`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WriteOptions {
+    ascii: bool,
+    write_color: bool,
+}
+
+impl WriteOptions {
+    /// Creates options to write binary STL without colors.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes ASCII STL instead of binary STL.
+    ///
+    /// Default: `false`
+    #[must_use]
+    pub fn ascii(mut self, enable: bool) -> Self {
+        self.ascii = enable;
+        self
+    }
+
+    /// Writes `colors[0]` of each mesh as facet colors.
+    ///
+    /// This uses the same convention as VisCAM and SolidView: the header
+    /// contains `COLOR=` followed by the default color (the diffuse color of
+    /// the first material), and each facet color is stored in the attribute
+    /// byte count. `Loader::stl_parse_color` reads this back.
+    ///
+    /// This option is ignored when writing ASCII STL.
+    ///
+    /// Default: `false`
+    #[must_use]
+    pub fn write_color(mut self, enable: bool) -> Self {
+        self.write_color = enable;
+        self
+    }
+}
+
+/// Serializes meshes in the scene as STL into the given writer.
+///
+/// When writing ASCII STL, each mesh is written as a separate `solid`.
+/// When writing binary STL, all meshes are written as one mesh.
+///
+/// Polygons in `polygon_indices` are triangulated. Facet normals are
+/// computed from vertices if the mesh has no normals.
+///
+/// Note that this writes to `writer` in small chunks, so it is often
+/// preferable to wrap `writer` with [`io::BufWriter`].
+pub fn to_writer<W: Write>(mut writer: W, scene: &Scene, options: WriteOptions) -> io::Result<()> {
+    // STL supports only triangles.
+    let meshes = if scene.meshes.iter().any(|m| !m.polygon_offsets.is_empty()) {
+        Cow::Owned(
+            scene
+                .meshes
+                .iter()
+                .map(|m| {
+                    let mut m = m.clone();
+                    m.triangulate_polygons();
+                    m
+                })
+                .collect(),
+        )
+    } else {
+        Cow::Borrowed(&scene.meshes[..])
+    };
+    if options.ascii {
+        write_ascii_stl(&mut writer, &meshes)
+    } else {
+        let default_color = scene
+            .materials
+            .first()
+            .and_then(|m| m.color.diffuse)
+            .unwrap_or(DEFAULT_COLOR);
+        write_binary_stl(
+            &mut writer,
+            &meshes,
+            options.write_color.then(|| default_color),
+        )
+    }
+}
+
+/// Serializes meshes in the scene as STL into a byte vector.
+///
+/// See [`to_writer`] for details.
+pub fn to_vec(scene: &Scene, options: WriteOptions) -> io::Result<Vec<u8>> {
+    let mut out = vec![];
+    to_writer(&mut out, scene, options)?;
+    Ok(out)
+}
+
+// Same as the default color used by the reader.
+const DEFAULT_COLOR: Color4 = [0.6, 0.6, 0.6, 0.6];
+
+fn facets(mesh: &Mesh) -> impl Iterator<Item = io::Result<(Vec3, [Vec3; 3], &[u32; 3])>> {
+    let has_normals = mesh.normals.len() == mesh.vertices.len();
+    mesh.faces.iter().map(move |face| {
+        let mut vertices = [[0.; 3]; 3];
+        for (v, &i) in vertices.iter_mut().zip(face) {
+            *v = match mesh.vertices.get(i as usize) {
+                Some(&v) => v,
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("vertex index out of bounds ({i})"),
+                    ))
+                }
+            };
+        }
+        let normal = if has_normals {
+            let [n0, n1, n2] = [0, 1, 2].map(|j| mesh.normals[face[j] as usize]);
+            normalize([0, 1, 2].map(|j| n0[j] + n1[j] + n2[j]))
+        } else {
+            let [v0, v1, v2] = vertices;
+            let e1 = [v1[0] - v0[0], v1[1] - v0[1], v1[2] - v0[2]];
+            let e2 = [v2[0] - v0[0], v2[1] - v0[1], v2[2] - v0[2]];
+            normalize([
+                e1[1] * e2[2] - e1[2] * e2[1],
+                e1[2] * e2[0] - e1[0] * e2[2],
+                e1[0] * e2[1] - e1[1] * e2[0],
+            ])
+        };
+        Ok((normal, vertices, face))
+    })
+}
+
+fn normalize(v: Vec3) -> Vec3 {
+    let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    if len == 0. || !len.is_finite() {
+        [0., 0., 0.]
+    } else {
+        [v[0] / len, v[1] / len, v[2] / len]
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Binary STL
+
+fn write_binary_stl<W: Write>(
+    writer: &mut W,
+    meshes: &[Mesh],
+    default_color: Option<Color4>,
+) -> io::Result<()> {
+    let num_triangles: usize = meshes.iter().map(|m| m.faces.len()).sum();
+    let num_triangles = u32::try_from(num_triangles)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many triangles"))?;
+
+    // The header must not start with "solid", otherwise it can be read as ASCII STL.
+    let mut header = [0; HEADER_SIZE];
+    if let Some(color) = default_color {
+        let expect = b"COLOR=";
+        header[..expect.len()].copy_from_slice(expect);
+        header[expect.len()..expect.len() + 4].copy_from_slice(&color.map(color_to_u8));
+    }
+    writer.write_all(&header)?;
+    writer.write_all(&num_triangles.to_le_bytes())?;
+
+    let mut buf = [0; TRIANGLE_SIZE];
+    for mesh in meshes {
+        let colors = mesh
+            .colors
+            .first()
+            .filter(|c| c.len() == mesh.vertices.len());
+        let colors = default_color.and(colors);
+        for facet in facets(mesh) {
+            let (normal, vertices, face) = facet?;
+            let mut pos = 0;
+            for v in [normal, vertices[0], vertices[1], vertices[2]] {
+                for f in v {
+                    buf[pos..pos + 4].copy_from_slice(&f.to_le_bytes());
+                    pos += 4;
+                }
+            }
+            let attribute = if let Some(colors) = colors {
+                let [c0, c1, c2] = [0, 1, 2].map(|j| colors[face[j] as usize]);
+                // Handling colors in STL is not standardized. We use the same way as
+                // the reader (which follows assimp) when COLOR= is in the header:
+                // red is in the lowest 5 bits, and bit 15 indicates the color is valid.
+                let [r, g, b] = [0, 1, 2].map(|j| color_to_u5((c0[j] + c1[j] + c2[j]) / 3.));
+                (1 << 15) | r | (g << 5) | (b << 10)
+            } else {
+                0
+            };
+            buf[pos..].copy_from_slice(&attribute.to_le_bytes());
+            writer.write_all(&buf)?;
+        }
+    }
+    Ok(())
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn color_to_u8(c: f32) -> u8 {
+    (c.clamp(0., 1.) * 255.).round() as u8
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn color_to_u5(c: f32) -> u16 {
+    (c.clamp(0., 1.) * 31.).round() as u16
+}
+
+// -----------------------------------------------------------------------------
+// ASCII STL
+
+fn write_ascii_stl<W: Write>(writer: &mut W, meshes: &[Mesh]) -> io::Result<()> {
+    if meshes.is_empty() {
+        // ASCII STL requires at least one solid.
+        return writer.write_all(b"solid \nendsolid \n");
+    }
+    for mesh in meshes {
+        // The name ends at the first whitespace, and must be ASCII.
+        let name: String = mesh
+            .name
+            .chars()
+            .map(|c| if c.is_ascii_graphic() { c } else { '_' })
+            .collect();
+        writeln!(writer, "solid {name}")?;
+        for facet in facets(mesh) {
+            let (n, [v1, v2, v3], _) = facet?;
+            writeln!(writer, "facet normal {:e} {:e} {:e}", n[0], n[1], n[2])?;
+            writeln!(writer, "  outer loop")?;
+            for v in [v1, v2, v3] {
+                writeln!(writer, "    vertex {:e} {:e} {:e}", v[0], v[1], v[2])?;
+            }
+            writeln!(writer, "  endloop")?;
+            writeln!(writer, "endfacet")?;
+        }
+        writeln!(writer, "endsolid {name}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut mesh = Mesh {
+            name: "quad".to_owned(),
+            vertices: vec![[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 1., 0.]],
+            faces: vec![[0, 1, 2], [0, 2, 3]],
+            ..Default::default()
+        };
+        mesh.colors = vec![vec![[1., 0., 0., 1.]; 4]];
+        let mut scene = Scene::default();
+        scene.meshes.push(mesh);
+
+        for options in [
+            WriteOptions::new(),
+            WriteOptions::new().write_color(true),
+            WriteOptions::new().ascii(true),
+        ] {
+            let bytes = to_vec(&scene, options).unwrap();
+            let read = super::super::from_slice_internal(&bytes, None, true, false).unwrap();
+            assert_eq!(read.meshes.len(), 1);
+            let m = &read.meshes[0];
+            assert_eq!(m.faces.len(), 2);
+            assert_eq!(m.vertices[3..6], [[0., 0., 0.], [1., 1., 0.], [0., 1., 0.]]);
+            assert_eq!(m.normals, vec![[0., 0., 1.]; 6]);
+            if options.ascii {
+                assert_eq!(m.name, "quad");
+            }
+            if options.write_color {
+                assert_eq!(m.colors, [vec![[1., 0., 0., 1.]; 6]]);
+            } else {
+                assert!(m.colors.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn polygons() {
+        let mesh = Mesh {
+            vertices: vec![[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 1., 0.]],
+            polygon_indices: vec![0, 1, 2, 3],
+            polygon_offsets: vec![0],
+            ..Default::default()
+        };
+        let mut scene = Scene::default();
+        scene.meshes.push(mesh);
+
+        for options in [WriteOptions::new(), WriteOptions::new().ascii(true)] {
+            let bytes = to_vec(&scene, options).unwrap();
+            let read = super::super::from_slice_internal(&bytes, None, true, false).unwrap();
+            let m = &read.meshes[0];
+            assert_eq!(m.faces.len(), 2);
+            assert_eq!(m.normals, vec![[0., 0., 1.]; 6]);
+        }
+    }
+}
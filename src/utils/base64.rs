@@ -0,0 +1,108 @@
+// Base64 decoder used to decode glTF data URIs.
+//
+// Only the standard alphabet (RFC 4648 section 4) is supported. Padding is
+// optional, and ASCII whitespace is not allowed.
+
+use std::io;
+
+// Lookup table for ascii to base64 decoding.
+#[rustfmt::skip]
+static DECODE_TABLE: [u8; 256] = {
+    const __: u8 = u8::MAX;
+    [
+        //  _1  _2  _3  _4  _5  _6  _7  _8  _9  _A  _B  _C  _D  _E  _F
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // 0_
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // 1_
+        __, __, __, __, __, __, __, __, __, __, __, 62, __, __, __, 63, // 2_
+        52, 53, 54, 55, 56, 57, 58, 59, 60, 61, __, __, __, __, __, __, // 3_
+        __,  0,  1,  2,  3,  4,  5,  6,  7,  8,  9, 10, 11, 12, 13, 14, // 4_
+        15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, __, __, __, __, __, // 5_
+        __, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, // 6_
+        41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51, __, __, __, __, __, // 7_
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // 8_
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // 9_
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // A_
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // B_
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // C_
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // D_
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // E_
+        __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // F_
+    ]
+};
+
+pub(crate) fn decode(mut bytes: &[u8]) -> io::Result<Vec<u8>> {
+    // Strip padding.
+    if bytes.len() % 4 == 0 {
+        if let Some(b) = bytes.strip_suffix(b"==") {
+            bytes = b;
+        } else if let Some(b) = bytes.strip_suffix(b"=") {
+            bytes = b;
+        }
+    }
+    if bytes.len() % 4 == 1 {
+        bail!(
+            ErrorKind::InvalidSize,
+            "invalid base64 length {}",
+            bytes.len()
+        );
+    }
+    let mut out = Vec::with_capacity(bytes.len() / 4 * 3 + 2);
+    let mut chunks = bytes.chunks_exact(4);
+    for chunk in chunks.by_ref() {
+        let n = (sextet(chunk[0])? << 18)
+            | (sextet(chunk[1])? << 12)
+            | (sextet(chunk[2])? << 6)
+            | sextet(chunk[3])?;
+        out.extend_from_slice(&n.to_be_bytes()[1..]);
+    }
+    match *chunks.remainder() {
+        [] => {}
+        [a, b] => {
+            let n = (sextet(a)? << 18) | (sextet(b)? << 12);
+            out.push(n.to_be_bytes()[1]);
+        }
+        [a, b, c] => {
+            let n = (sextet(a)? << 18) | (sextet(b)? << 12) | (sextet(c)? << 6);
+            out.extend_from_slice(&n.to_be_bytes()[1..3]);
+        }
+        _ => unreachable!(),
+    }
+    Ok(out)
+}
+
+#[inline]
+fn sextet(b: u8) -> io::Result<u32> {
+    let v = DECODE_TABLE[b as usize];
+    if v == u8::MAX {
+        bail!(
+            ErrorKind::Syntax,
+            "invalid base64 character {:?}",
+            b as char
+        );
+    }
+    Ok(u32::from(v))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc4648() {
+        // https://www.rfc-editor.org/rfc/rfc4648#section-10
+        for (encoded, decoded) in [
+            ("", ""),
+            ("Zg==", "f"),
+            ("Zm8=", "fo"),
+            ("Zm9v", "foo"),
+            ("Zm9vYg==", "foob"),
+            ("Zm9vYmE=", "fooba"),
+            ("Zm9vYmFy", "foobar"),
+            ("Zm9vYmE", "fooba"),
+        ] {
+            assert_eq!(decode(encoded.as_bytes()).unwrap(), decoded.as_bytes());
+        }
+        decode(b"Zm9vY").unwrap_err();
+        decode(b"Zm9v!mFy").unwrap_err();
+    }
+}
@@ -3,6 +3,7 @@ use std::{borrow::Cow, ffi::OsStr, path::Path, str};
 
 // This is the same as s.starts_with(needle), but faster if the length of the
 // needle is known at compile time.
+#[cfg(any(feature = "gltf", feature = "obj", feature = "stl"))]
 #[inline(always)] // Ensure the code getting the length of the needle is inlined.
 pub(crate) fn starts_with(mut s: &[u8], mut needle: &'static [u8]) -> bool {
     if s.len() < needle.len() {
@@ -44,7 +45,7 @@ pub(crate) fn starts_with(mut s: &[u8], mut needle: &'static [u8]) -> bool {
     s.starts_with(needle)
 }
 
-#[cfg(any(feature = "collada", feature = "obj"))]
+#[cfg(any(feature = "collada", feature = "obj", feature = "ply"))]
 #[inline]
 pub(crate) const fn memchr_naive(needle: u8, mut s: &[u8]) -> Option<usize> {
     let start = s;
@@ -74,7 +75,7 @@ pub(crate) const fn memchr_naive_table(
     None
 }
 
-#[cfg(any(feature = "obj", feature = "stl"))]
+#[cfg(any(feature = "obj", feature = "ply", feature = "stl"))]
 #[inline]
 pub(crate) const fn memrchr_naive(needle: u8, mut s: &[u8]) -> Option<usize> {
     let start = s;
@@ -87,7 +88,7 @@ pub(crate) const fn memrchr_naive(needle: u8, mut s: &[u8]) -> Option<usize> {
     None
 }
 
-#[cfg(any(feature = "obj", feature = "stl"))]
+#[cfg(any(feature = "obj", feature = "ply", feature = "stl"))]
 #[inline]
 pub(crate) const fn bytecount_naive(needle: u8, mut s: &[u8]) -> usize {
     let mut n = 0;
@@ -0,0 +1,153 @@
+// Gzip decoder used to load compressed files (e.g., .stl.gz).
+//
+// Files with multiple members (e.g., concatenated gzip files) are supported.
+// The CRC-32 of the contents is not checked, but the size of the decompressed
+// contents is.
+
+use std::io;
+
+use miniz_oxide::{
+    inflate::stream::{inflate, InflateState},
+    DataFormat, MZFlush, MZStatus,
+};
+
+const FHCRC: u8 = 1 << 1;
+const FEXTRA: u8 = 1 << 2;
+const FNAME: u8 = 1 << 3;
+const FCOMMENT: u8 = 1 << 4;
+
+const MAGIC: &[u8] = b"\x1f\x8b";
+
+pub(crate) fn decompress(mut bytes: &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(bytes.len() * 4);
+    loop {
+        bytes = decompress_member(bytes, &mut out)?;
+        if bytes.is_empty() {
+            return Ok(out);
+        }
+    }
+}
+
+/// Decompresses a member of a gzip file and returns the bytes after it.
+fn decompress_member<'a>(bytes: &'a [u8], out: &mut Vec<u8>) -> io::Result<&'a [u8]> {
+    fn invalid() -> io::Error {
+        crate::error::invalid_data("invalid gzip header")
+    }
+
+    if bytes.len() < 10 || !bytes.starts_with(MAGIC) {
+        return Err(invalid());
+    }
+    if bytes[2] != 8 {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("gzip compression method {} is not supported", bytes[2]),
+        ));
+    }
+    let flags = bytes[3];
+    let mut data = &bytes[10..];
+    if flags & FEXTRA != 0 {
+        let len = match data {
+            [l, h, ..] => u16::from_le_bytes([*l, *h]) as usize,
+            _ => return Err(invalid()),
+        };
+        data = data.get(2 + len..).ok_or_else(invalid)?;
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            // Zero-terminated string
+            let len = data.iter().position(|&b| b == 0).ok_or_else(invalid)?;
+            data = &data[len + 1..];
+        }
+    }
+    if flags & FHCRC != 0 {
+        data = data.get(2..).ok_or_else(invalid)?;
+    }
+
+    let start = out.len();
+    let mut state = InflateState::new_boxed(DataFormat::Raw);
+    loop {
+        let len = out.len();
+        out.resize(len + (len - start).max(1 << 16), 0);
+        let res = inflate(&mut state, data, &mut out[len..], MZFlush::None);
+        data = &data[res.bytes_consumed..];
+        out.truncate(len + res.bytes_written);
+        match res.status {
+            Ok(MZStatus::StreamEnd) => break,
+            Ok(_) if res.bytes_consumed != 0 || res.bytes_written != 0 => {}
+            _ => return Err(crate::error::invalid_data("failed to decompress gzip")),
+        }
+    }
+
+    // CRC-32 and ISIZE (the size of the contents modulo 2^32)
+    let size = match data.get(4..8) {
+        Some(size) => u32::from_le_bytes(size.try_into().unwrap()),
+        None => return Err(crate::error::invalid_data("unexpected end of gzip")),
+    };
+    #[allow(clippy::cast_possible_truncation)]
+    let out_size = (out.len() - start) as u32;
+    if out_size != size {
+        return Err(crate::error::invalid_data(
+            "size of decompressed gzip is inconsistent with its trailer",
+        ));
+    }
+    Ok(&data[8..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn gzip(contents: &[u8], name: Option<&str>) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&[8, if name.is_some() { FNAME } else { 0 }]);
+        bytes.extend_from_slice(&[0; 6]); // mtime, xfl, os
+        if let Some(name) = name {
+            bytes.extend_from_slice(name.as_bytes());
+            bytes.push(0);
+        }
+        bytes.extend_from_slice(&miniz_oxide::deflate::compress_to_vec(contents, 6));
+        bytes.extend_from_slice(&[0; 4]); // crc-32
+        bytes.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn decode() {
+        let contents = b"solid a\nendsolid a\n";
+        assert_eq!(decompress(&gzip(contents, None)).unwrap(), contents);
+        assert_eq!(
+            decompress(&gzip(contents, Some("a.stl"))).unwrap(),
+            contents
+        );
+        assert_eq!(decompress(&gzip(b"", None)).unwrap(), b"");
+
+        // Multiple members
+        let mut bytes = gzip(contents, None);
+        bytes.extend_from_slice(&gzip(b"solid b\n", None));
+        assert_eq!(
+            decompress(&bytes).unwrap(),
+            b"solid a\nendsolid a\nsolid b\n"
+        );
+
+        assert!(decompress(&bytes[..12]).is_err());
+        let len = bytes.len();
+        assert!(decompress(&bytes[..len - 1]).is_err());
+        bytes[len - 4] ^= 1;
+        assert!(decompress(&bytes).is_err());
+        assert!(decompress(b"solid a\nendsolid a\n").is_err());
+    }
+
+    #[cfg(feature = "obj")]
+    #[test]
+    fn load() {
+        let obj = b"v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n";
+        let loader = crate::Loader::default();
+        for path in ["a.obj.gz", "a.obj"] {
+            let scene = loader
+                .load_from_slice(&gzip(obj, Some("a.obj")), path)
+                .unwrap();
+            assert_eq!(scene.meshes[0].faces, [[0, 1, 2]], "{path}");
+        }
+    }
+}
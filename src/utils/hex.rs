@@ -30,7 +30,7 @@ static DECODE_TABLE: [u8; 256] = {
 #[inline]
 pub(crate) fn decode(bytes: &[u8]) -> io::Result<Vec<u8>> {
     if bytes.len() % 2 != 0 {
-        bail!("invalid length {}", bytes.len());
+        bail!(ErrorKind::InvalidSize, "invalid length {}", bytes.len());
     }
     let mut out = vec![0; bytes.len() / 2];
     // Using hex2byte16 instead of hex2byte here increases throughput by 1.5x,
@@ -73,10 +73,18 @@ fn hex2byte(bytes: &[u8], out: &mut u8) -> io::Result<()> {
     let upper = DECODE_TABLE[bytes[0] as usize];
     let lower = DECODE_TABLE[bytes[1] as usize];
     if upper == u8::MAX {
-        bail!("invalid hex character {}", bytes[0] as char);
+        bail!(
+            ErrorKind::Syntax,
+            "invalid hex character {}",
+            bytes[0] as char
+        );
     }
     if lower == u8::MAX {
-        bail!("invalid hex character {}", bytes[1] as char);
+        bail!(
+            ErrorKind::Syntax,
+            "invalid hex character {}",
+            bytes[1] as char
+        );
     }
     *out = (upper << 4) | lower;
     Ok(())
@@ -0,0 +1,484 @@
+// A minimal JSON parser used to parse the JSON part of glTF.
+//
+// This only supports what is needed to parse glTF (RFC 8259 compliant input
+// is accepted, but no serialization, no streaming, etc.).
+
+use std::{borrow::Cow, fmt, io, str};
+
+use super::float;
+use crate::{Error, ErrorKind};
+
+pub(crate) enum Value<'a> {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(Cow<'a, str>),
+    Array(Vec<Value<'a>>),
+    Object(Vec<(Cow<'a, str>, Value<'a>)>),
+}
+
+impl<'a> Value<'a> {
+    pub(crate) fn get(&self, key: &str) -> Option<&Value<'a>> {
+        match self {
+            Self::Object(v) => v.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+    pub(crate) fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Self::Bool(v) => Some(v),
+            _ => None,
+        }
+    }
+    pub(crate) fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Self::Number(v) => Some(v),
+            _ => None,
+        }
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    pub(crate) fn as_f32(&self) -> Option<f32> {
+        self.as_f64().map(|v| v as f32)
+    }
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    pub(crate) fn as_usize(&self) -> Option<usize> {
+        match *self {
+            Self::Number(v) if v >= 0. && v.fract() == 0. && v <= usize::MAX as f64 => {
+                Some(v as usize)
+            }
+            _ => None,
+        }
+    }
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(v) => Some(v),
+            _ => None,
+        }
+    }
+    pub(crate) fn as_array(&self) -> Option<&[Value<'a>]> {
+        match self {
+            Self::Array(v) => Some(v),
+            _ => None,
+        }
+    }
+    pub(crate) fn as_object(&self) -> Option<&[(Cow<'a, str>, Value<'a>)]> {
+        match self {
+            Self::Object(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    fn ty(&self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Bool(..) => "boolean",
+            Self::Number(..) => "number",
+            Self::String(..) => "string",
+            Self::Array(..) => "array",
+            Self::Object(..) => "object",
+        }
+    }
+}
+
+impl fmt::Debug for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Null => f.write_str("null"),
+            Self::Bool(v) => fmt::Debug::fmt(v, f),
+            Self::Number(v) => fmt::Debug::fmt(v, f),
+            Self::String(v) => fmt::Debug::fmt(v, f),
+            Self::Array(..) | Self::Object(..) => f.write_str(self.ty()),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Typed access helpers
+
+/// Gets an optional member of the object, and converts it with `f`.
+///
+/// Returns an error if the member exists but has an unexpected type.
+pub(crate) fn opt<'b, 'a: 'b, T, F>(
+    object: &'b Value<'a>,
+    key: &str,
+    expected: &'static str,
+    f: F,
+) -> io::Result<Option<T>>
+where
+    F: FnOnce(&'b Value<'a>) -> Option<T>,
+{
+    match object.get(key) {
+        Some(v) => match f(v) {
+            Some(v) => Ok(Some(v)),
+            None => bail!(
+                ErrorKind::Syntax,
+                "expected {expected} for {key:?}, but found {v:?}"
+            ),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Gets a required member of the object, and converts it with `f`.
+pub(crate) fn req<'b, 'a: 'b, T, F>(
+    object: &'b Value<'a>,
+    key: &str,
+    expected: &'static str,
+    f: F,
+) -> io::Result<T>
+where
+    F: FnOnce(&'b Value<'a>) -> Option<T>,
+{
+    match opt(object, key, expected, f)? {
+        Some(v) => Ok(v),
+        None => Err(Error::new(
+            ErrorKind::MissingElement,
+            format!("expected {key:?} property"),
+        )
+        .into()),
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Parsing
+
+pub(crate) fn parse(s: &str) -> io::Result<Value<'_>> {
+    let mut p = Parser {
+        s: s.as_bytes(),
+        start: s.as_bytes(),
+        depth: 0,
+    };
+    p.skip_whitespace();
+    let v = p.parse_value()?;
+    p.skip_whitespace();
+    if !p.s.is_empty() {
+        return Err(p.error("trailing characters"));
+    }
+    Ok(v)
+}
+
+// Prevent stack overflow on deeply nested input.
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'a> {
+    s: &'a [u8],
+    start: &'a [u8],
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    #[cold]
+    fn error(&self, msg: &str) -> io::Error {
+        let pos = self.start.len() - self.s.len();
+        let consumed = &self.start[..pos];
+        let line = consumed.iter().filter(|&&b| b == b'\n').count() + 1;
+        let column = pos
+            - consumed
+                .iter()
+                .rposition(|&b| b == b'\n')
+                .map_or(0, |p| p + 1)
+            + 1;
+        Error::new(ErrorKind::Syntax, format!("{msg} in JSON"))
+            .with_position(line, column, pos)
+            .into()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some((&b, s_next)) = self.s.split_first() {
+            if matches!(b, b' ' | b'\t' | b'\n' | b'\r') {
+                self.s = s_next;
+                continue;
+            }
+            break;
+        }
+    }
+
+    fn eat(&mut self, b: u8) -> bool {
+        if self.s.first() == Some(&b) {
+            self.s = &self.s[1..];
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_value(&mut self) -> io::Result<Value<'a>> {
+        match self.s.first() {
+            Some(b'{') => self.parse_object(),
+            Some(b'[') => self.parse_array(),
+            Some(b'"') => self.parse_string().map(Value::String),
+            Some(b't') => self.parse_literal(b"true", Value::Bool(true)),
+            Some(b'f') => self.parse_literal(b"false", Value::Bool(false)),
+            Some(b'n') => self.parse_literal(b"null", Value::Null),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(_) => Err(self.error("expected value")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn parse_literal(&mut self, lit: &[u8], v: Value<'a>) -> io::Result<Value<'a>> {
+        if self.s.starts_with(lit) {
+            self.s = &self.s[lit.len()..];
+            Ok(v)
+        } else {
+            Err(self.error("expected value"))
+        }
+    }
+
+    fn parse_number(&mut self) -> io::Result<Value<'a>> {
+        // Check the JSON number grammar first, since our float parser accepts
+        // a superset of it (e.g., "inf", "+1", ".5").
+        let start = self.s;
+        let mut n = 0;
+        if start.get(n) == Some(&b'-') {
+            n += 1;
+        }
+        match start.get(n) {
+            Some(b'0') => n += 1,
+            Some(b'1'..=b'9') => {
+                while matches!(start.get(n), Some(b'0'..=b'9')) {
+                    n += 1;
+                }
+            }
+            _ => return Err(self.error("invalid number")),
+        }
+        if start.get(n) == Some(&b'.') {
+            n += 1;
+            if !matches!(start.get(n), Some(b'0'..=b'9')) {
+                return Err(self.error("invalid number"));
+            }
+            while matches!(start.get(n), Some(b'0'..=b'9')) {
+                n += 1;
+            }
+        }
+        if matches!(start.get(n), Some(b'e' | b'E')) {
+            n += 1;
+            if matches!(start.get(n), Some(b'+' | b'-')) {
+                n += 1;
+            }
+            if !matches!(start.get(n), Some(b'0'..=b'9')) {
+                return Err(self.error("invalid number"));
+            }
+            while matches!(start.get(n), Some(b'0'..=b'9')) {
+                n += 1;
+            }
+        }
+        match float::parse::<f64>(&start[..n]) {
+            Some(v) => {
+                self.s = &start[n..];
+                Ok(Value::Number(v))
+            }
+            None => Err(self.error("invalid number")),
+        }
+    }
+
+    fn parse_string(&mut self) -> io::Result<Cow<'a, str>> {
+        debug_assert_eq!(self.s.first(), Some(&b'"'));
+        self.s = &self.s[1..];
+        let start = self.s;
+        // Fast path: no escape sequences.
+        let mut n = 0;
+        loop {
+            match start.get(n) {
+                Some(b'"') => {
+                    self.s = &start[n + 1..];
+                    return match str::from_utf8(&start[..n]) {
+                        Ok(s) => Ok(Cow::Borrowed(s)),
+                        Err(_) => Err(self.error("invalid UTF-8 in string")),
+                    };
+                }
+                Some(b'\\') => break,
+                Some(&b) if b < 0x20 => {
+                    self.s = &start[n..];
+                    return Err(self.error("control character in string"));
+                }
+                Some(_) => n += 1,
+                None => {
+                    self.s = &start[n..];
+                    return Err(self.error("unterminated string"));
+                }
+            }
+        }
+        // Slow path: unescape.
+        let mut buf = start[..n].to_vec();
+        self.s = &start[n..];
+        loop {
+            match self.s.split_first() {
+                Some((b'"', s_next)) => {
+                    self.s = s_next;
+                    return match String::from_utf8(buf) {
+                        Ok(s) => Ok(Cow::Owned(s)),
+                        Err(_) => Err(self.error("invalid UTF-8 in string")),
+                    };
+                }
+                Some((b'\\', s_next)) => {
+                    self.s = s_next;
+                    let c = match self.s.split_first() {
+                        Some((&c, s_next)) => {
+                            self.s = s_next;
+                            c
+                        }
+                        None => return Err(self.error("unterminated string")),
+                    };
+                    match c {
+                        b'"' | b'\\' | b'/' => buf.push(c),
+                        b'b' => buf.push(b'\x08'),
+                        b'f' => buf.push(b'\x0C'),
+                        b'n' => buf.push(b'\n'),
+                        b'r' => buf.push(b'\r'),
+                        b't' => buf.push(b'\t'),
+                        b'u' => {
+                            let mut c = u32::from(self.parse_hex4()?);
+                            if (0xD800..0xDC00).contains(&c) {
+                                // surrogate pair
+                                if !(self.eat(b'\\') && self.eat(b'u')) {
+                                    return Err(self.error("lone surrogate in string"));
+                                }
+                                let low = u32::from(self.parse_hex4()?);
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return Err(self.error("lone surrogate in string"));
+                                }
+                                c = 0x10000 + ((c - 0xD800) << 10) + (low - 0xDC00);
+                            }
+                            match char::from_u32(c) {
+                                Some(c) => {
+                                    buf.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                                }
+                                None => return Err(self.error("lone surrogate in string")),
+                            }
+                        }
+                        _ => return Err(self.error("invalid escape sequence")),
+                    }
+                }
+                Some((&b, _)) if b < 0x20 => {
+                    return Err(self.error("control character in string"));
+                }
+                Some((&b, s_next)) => {
+                    buf.push(b);
+                    self.s = s_next;
+                }
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn parse_hex4(&mut self) -> io::Result<u16> {
+        if self.s.len() < 4 {
+            return Err(self.error("invalid escape sequence"));
+        }
+        let mut v = 0;
+        for &b in &self.s[..4] {
+            let d = match b {
+                b'0'..=b'9' => b - b'0',
+                b'a'..=b'f' => b - b'a' + 10,
+                b'A'..=b'F' => b - b'A' + 10,
+                _ => return Err(self.error("invalid escape sequence")),
+            };
+            v = (v << 4) | u16::from(d);
+        }
+        self.s = &self.s[4..];
+        Ok(v)
+    }
+
+    fn parse_array(&mut self) -> io::Result<Value<'a>> {
+        debug_assert_eq!(self.s.first(), Some(&b'['));
+        self.s = &self.s[1..];
+        self.enter()?;
+        let mut values = vec![];
+        self.skip_whitespace();
+        if !self.eat(b']') {
+            loop {
+                self.skip_whitespace();
+                values.push(self.parse_value()?);
+                self.skip_whitespace();
+                if self.eat(b',') {
+                    continue;
+                }
+                if self.eat(b']') {
+                    break;
+                }
+                return Err(self.error("expected ',' or ']'"));
+            }
+        }
+        self.depth -= 1;
+        Ok(Value::Array(values))
+    }
+
+    fn parse_object(&mut self) -> io::Result<Value<'a>> {
+        debug_assert_eq!(self.s.first(), Some(&b'{'));
+        self.s = &self.s[1..];
+        self.enter()?;
+        let mut members = vec![];
+        self.skip_whitespace();
+        if !self.eat(b'}') {
+            loop {
+                self.skip_whitespace();
+                if self.s.first() != Some(&b'"') {
+                    return Err(self.error("expected string"));
+                }
+                let key = self.parse_string()?;
+                self.skip_whitespace();
+                if !self.eat(b':') {
+                    return Err(self.error("expected ':'"));
+                }
+                self.skip_whitespace();
+                let value = self.parse_value()?;
+                members.push((key, value));
+                self.skip_whitespace();
+                if self.eat(b',') {
+                    continue;
+                }
+                if self.eat(b'}') {
+                    break;
+                }
+                return Err(self.error("expected ',' or '}'"));
+            }
+        }
+        self.depth -= 1;
+        Ok(Value::Object(members))
+    }
+
+    fn enter(&mut self) -> io::Result<()> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.error("recursion limit exceeded"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic() {
+        let v = parse(r#" {"a": [1, -2.5e1, true, null], "b\u00e9\n": "\ud83d\ude00"} "#).unwrap();
+        let a = v.get("a").unwrap().as_array().unwrap();
+        assert_eq!(a[0].as_usize(), Some(1));
+        assert_eq!(a[1].as_f64(), Some(-25.));
+        assert_eq!(a[2].as_bool(), Some(true));
+        assert!(matches!(a[3], Value::Null));
+        assert_eq!(v.get("b\u{e9}\n").unwrap().as_str(), Some("\u{1F600}"));
+
+        for s in [
+            "",
+            "{",
+            "[1,]",
+            "{\"a\" 1}",
+            "01",
+            "1.",
+            "-",
+            "\"\\x\"",
+            "\"\\ud83d\"",
+            "[] []",
+        ] {
+            parse(s).unwrap_err();
+        }
+        parse(&"[".repeat(MAX_DEPTH + 1)).unwrap_err();
+    }
+}
@@ -1,10 +1,24 @@
+#[cfg(feature = "gltf")]
+pub(crate) mod base64;
 pub(crate) mod bytes;
-#[cfg(any(feature = "collada", feature = "obj", feature = "stl"))]
+#[cfg(any(
+    feature = "collada",
+    feature = "gltf",
+    feature = "obj",
+    feature = "ply",
+    feature = "stl",
+))]
 pub mod float;
+#[cfg(feature = "gzip")]
+pub(crate) mod gzip;
 #[cfg(feature = "collada")]
 pub(crate) mod hex;
-#[cfg(any(feature = "collada", feature = "obj"))]
+#[cfg(any(feature = "collada", feature = "obj", feature = "ply"))]
 pub mod int;
+#[cfg(feature = "gltf")]
+pub(crate) mod json;
+pub(crate) mod triangulate;
+pub(crate) mod uri;
 #[cfg(feature = "collada")]
 pub(crate) mod xml;
 
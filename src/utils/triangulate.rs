@@ -0,0 +1,157 @@
+// Ear clipping triangulation of polygons.
+
+use crate::Vec3;
+
+type Vec2 = [f32; 2];
+
+/// Triangulates the polygon, and pushes the triangles as indices into
+/// `polygon` to `triangles`.
+///
+/// The polygon is projected onto the plane that it is closest to, so it does
+/// not need to be exactly planar. Convex polygons are triangulated as a
+/// triangle fan around the first vertex, and concave polygons are
+/// triangulated by ear clipping.
+pub(crate) fn triangulate(polygon: &[Vec3], triangles: &mut Vec<[usize; 3]>) {
+    let n = polygon.len();
+    if n < 3 {
+        return;
+    }
+    let points = match project(polygon) {
+        Some(points) if n > 3 && !is_convex(&points) => points,
+        // Degenerate or convex polygons.
+        _ => {
+            triangles.extend((1..n - 1).map(|i| [0, i, i + 1]));
+            return;
+        }
+    };
+
+    let mut remaining: Vec<usize> = (0..n).collect();
+    while remaining.len() > 3 {
+        let len = remaining.len();
+        // Start from the second vertex so that convex parts are triangulated
+        // in the same way as a triangle fan. If no ear is found (e.g., the
+        // polygon is self-intersecting), clip any vertex.
+        let ear = (1..=len)
+            .map(|i| i % len)
+            .find(|&i| is_ear(&points, &remaining, i))
+            .unwrap_or(1);
+        triangles.push([
+            remaining[(ear + len - 1) % len],
+            remaining[ear],
+            remaining[(ear + 1) % len],
+        ]);
+        remaining.remove(ear);
+    }
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+}
+
+/// Projects the polygon onto the axis-aligned plane that is closest to it,
+/// so that the projected polygon is counterclockwise.
+///
+/// Returns `None` if the polygon has no area.
+fn project(polygon: &[Vec3]) -> Option<Vec<Vec2>> {
+    // Newell's method
+    let mut normal = [0.; 3];
+    for (i, a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        normal[0] += (a[1] - b[1]) * (a[2] + b[2]);
+        normal[1] += (a[2] - b[2]) * (a[0] + b[0]);
+        normal[2] += (a[0] - b[0]) * (a[1] + b[1]);
+    }
+    let abs = normal.map(f32::abs);
+    // Drop the axis that the normal is closest to, and flip the other axis
+    // if the normal points in the negative direction.
+    let (axes, sign) = if abs[2] >= abs[0] && abs[2] >= abs[1] {
+        ([0, 1], normal[2])
+    } else if abs[0] >= abs[1] {
+        ([1, 2], normal[0])
+    } else {
+        ([2, 0], normal[1])
+    };
+    if sign.abs() > 0. {
+        let sign = sign.signum();
+        Some(
+            polygon
+                .iter()
+                .map(|p| [p[axes[0]], p[axes[1]] * sign])
+                .collect(),
+        )
+    } else {
+        None
+    }
+}
+
+fn is_convex(points: &[Vec2]) -> bool {
+    let n = points.len();
+    (0..n).all(|i| cross(points[(i + n - 1) % n], points[i], points[(i + 1) % n]) >= 0.)
+}
+
+fn is_ear(points: &[Vec2], remaining: &[usize], i: usize) -> bool {
+    let len = remaining.len();
+    let prev = (i + len - 1) % len;
+    let next = (i + 1) % len;
+    let (a, b, c) = (
+        points[remaining[prev]],
+        points[remaining[i]],
+        points[remaining[next]],
+    );
+    if cross(a, b, c) <= 0. {
+        return false;
+    }
+    // No other vertex may be inside the triangle.
+    remaining.iter().enumerate().all(|(j, &k)| {
+        if j == prev || j == i || j == next {
+            return true;
+        }
+        let point = points[k];
+        cross(a, b, point) < 0. || cross(b, c, point) < 0. || cross(c, a, point) < 0.
+    })
+}
+
+/// Returns the z component of the cross product of `b - a` and `c - a`,
+/// which is positive if `a`, `b`, and `c` are counterclockwise.
+fn cross(a: Vec2, b: Vec2, c: Vec2) -> f32 {
+    (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concave() {
+        let mut triangles = vec![];
+        // Convex polygons are triangulated as a triangle fan.
+        triangulate(
+            &[[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 1., 0.]],
+            &mut triangles,
+        );
+        assert_eq!(triangles, [[0, 1, 2], [0, 2, 3]]);
+
+        // An arrow-shaped quad whose fan triangulation would cover the notch.
+        // Flipping the polygon or moving it to another plane does not change
+        // the result.
+        for polygon in [
+            [[0., 0., 0.], [2., 1., 0.], [0., 2., 0.], [1., 1., 0.]],
+            [[0., 0., 0.], [0., 1., 2.], [0., 2., 0.], [0., 1., 1.]],
+            [[0., 0., 0.], [1., 0., 2.], [2., 0., 0.], [1., 0., 1.]],
+            [[0., 0., 0.], [2., -1., 0.], [0., -2., 0.], [1., -1., 0.]],
+        ] {
+            triangles.clear();
+            triangulate(&polygon, &mut triangles);
+            assert_eq!(triangles, [[1, 2, 3], [0, 1, 3]]);
+        }
+
+        // An L-shaped hexagon.
+        let points = [[0., 0.], [2., 0.], [2., 1.], [1., 1.], [1., 2.], [0., 2.]];
+        let polygon: Vec<_> = points.iter().map(|p| [p[0], p[1], 0.]).collect();
+        triangles.clear();
+        triangulate(&polygon, &mut triangles);
+        assert_eq!(triangles.len(), 4);
+        let area: f32 = triangles
+            .iter()
+            .map(|t| cross(points[t[0]], points[t[1]], points[t[2]]) / 2.)
+            .sum();
+        assert!((area - 3.).abs() < f32::EPSILON);
+    }
+}
@@ -1,10 +1,11 @@
 // A module that provides utilities for parsing and visiting XML nodes.
 
-use std::{borrow::Cow, fmt, io, iter, marker::PhantomData, str::FromStr};
+use std::{borrow::Cow, io, iter, marker::PhantomData, str::FromStr};
 
 pub(crate) use roxmltree::*;
 
 use super::{bytes::memchr_naive, float, int};
+use crate::{Error, ErrorKind};
 
 #[inline]
 #[must_use]
@@ -67,7 +68,10 @@ where
                 self.text = trim_start(self.text.get(n..).unwrap_or_default());
                 Some(Ok(value))
             }
-            None => Some(Err(format_err!("error while parsing an integer"))),
+            None => Some(Err(format_err!(
+                ErrorKind::InvalidInt,
+                "error while parsing an integer"
+            ))),
         }
     }
 }
@@ -142,6 +146,7 @@ where
                 return None;
             }
             return Some(Err(format_err!(
+                ErrorKind::InvalidSize,
                 "unexpected text {:?} after {} floats",
                 self.text,
                 self.num
@@ -153,7 +158,10 @@ where
                 self.count += 1;
                 Some(Ok(value))
             }
-            None => Some(Err(format_err!("error while parsing a float"))),
+            None => Some(Err(format_err!(
+                ErrorKind::InvalidFloat,
+                "error while parsing a float"
+            ))),
         }
     }
 }
@@ -168,15 +176,16 @@ pub(crate) trait XmlNodeExt<'a, 'input> {
     fn parse_attribute<T>(&self, name: &str) -> io::Result<Option<T>>
     where
         T: FromStr,
-        T::Err: fmt::Display;
+        T::Err: std::error::Error + 'static;
     fn parse_required_attribute<T>(&self, name: &str) -> io::Result<T>
     where
         T: FromStr,
-        T::Err: fmt::Display;
+        T::Err: std::error::Error + 'static;
     fn trimmed_text(&self) -> &'a str;
     fn node_location(&self) -> TextPos;
     fn text_location(&self) -> TextPos;
     fn attr_value_location(&self, name: &str) -> TextPos;
+    fn error_at_node(&self, kind: ErrorKind, msg: String) -> io::Error;
 }
 
 impl<'a, 'input> XmlNodeExt<'a, 'input> for Node<'a, 'input> {
@@ -193,34 +202,37 @@ impl<'a, 'input> XmlNodeExt<'a, 'input> for Node<'a, 'input> {
     fn required_attribute(&self, name: &str) -> io::Result<&'a str> {
         match self.attribute(name) {
             Some(v) => Ok(v),
-            None => {
-                bail!(
-                    "expected {} attribute in <{}> element at {}",
+            None => Err(self.error_at_node(
+                ErrorKind::MissingElement,
+                format!(
+                    "expected {} attribute in <{}> element",
                     name,
                     if self.is_element() {
                         self.tag_name().name()
                     } else {
                         self.parent_element().unwrap().tag_name().name()
                     },
-                    self.node_location(),
-                )
-            }
+                ),
+            )),
         }
     }
 
     fn parse_attribute<T>(&self, name: &str) -> io::Result<Option<T>>
     where
         T: FromStr,
-        T::Err: fmt::Display,
+        T::Err: std::error::Error + 'static,
     {
         match self.attribute(name) {
             Some(v) => Ok(Some(v.parse::<T>().map_err(|e| {
-                format_err!(
-                    "{} in <{}> element at {}: {:?}",
-                    e,
-                    self.tag_name().name(),
-                    self.attr_value_location(name),
-                    v
+                Error::new(
+                    parse_error_kind(&e),
+                    format!(
+                        "{} in <{}> element at {}: {:?}",
+                        e,
+                        self.tag_name().name(),
+                        self.attr_value_location(name),
+                        v
+                    ),
                 )
             })?)),
             None => Ok(None),
@@ -230,17 +242,21 @@ impl<'a, 'input> XmlNodeExt<'a, 'input> for Node<'a, 'input> {
     fn parse_required_attribute<T>(&self, name: &str) -> io::Result<T>
     where
         T: FromStr,
-        T::Err: fmt::Display,
+        T::Err: std::error::Error + 'static,
     {
         let v = self.required_attribute(name)?;
         v.parse::<T>().map_err(|e| {
-            format_err!(
-                "{} in <{}> element at {}: {:?}",
-                e,
-                self.tag_name().name(),
-                self.attr_value_location(name),
-                v
+            Error::new(
+                parse_error_kind(&e),
+                format!(
+                    "{} in <{}> element at {}: {:?}",
+                    e,
+                    self.tag_name().name(),
+                    self.attr_value_location(name),
+                    v
+                ),
             )
+            .into()
         })
     }
 
@@ -265,6 +281,27 @@ impl<'a, 'input> XmlNodeExt<'a, 'input> for Node<'a, 'input> {
         let start = self.attribute_node(name).unwrap().range_value().start;
         self.document().text_pos_at(start)
     }
+    #[cold]
+    fn error_at_node(&self, kind: ErrorKind, msg: String) -> io::Error {
+        let pos = self.node_location();
+        Error::new(kind, msg)
+            .with_position(pos.row as usize, pos.col as usize, self.range().start)
+            .into()
+    }
+}
+
+/// Returns the kind of the error returned by [`FromStr::from_str`].
+#[cold]
+fn parse_error_kind(e: &(dyn std::error::Error + 'static)) -> ErrorKind {
+    if e.is::<std::num::ParseIntError>() {
+        ErrorKind::InvalidInt
+    } else if e.is::<std::num::ParseFloatError>() {
+        ErrorKind::InvalidFloat
+    } else if let Some(e) = e.downcast_ref::<io::Error>() {
+        crate::error::kind(e)
+    } else {
+        ErrorKind::Syntax
+    }
 }
 
 pub(crate) type ElementChildren<'a, 'input> =
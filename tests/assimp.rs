@@ -211,29 +211,41 @@ fn test() {
                     | "kwxport_test_vcolors.dae"
             ) {
                 panic::catch_unwind(|| {
-                    assert_full_matches(&ml.texcoords[0], &ai.texcoords[0], f32::EPSILON * 1000.);
+                    assert_full_matches(
+                        set(&ml.texcoords, 0),
+                        set(&ai.texcoords, 0),
+                        f32::EPSILON * 1000.,
+                    );
                 })
                 .unwrap_err();
             } else {
-                assert_full_matches(&ml.texcoords[0], &ai.texcoords[0], f32::EPSILON);
+                assert_full_matches(set(&ml.texcoords, 0), set(&ai.texcoords, 0), f32::EPSILON);
             }
             if matches!(filename, "cube_with_2UVs.DAE") {
                 panic::catch_unwind(|| {
-                    assert_full_matches(&ml.texcoords[1], &ai.texcoords[1], f32::EPSILON * 1000.);
+                    assert_full_matches(
+                        set(&ml.texcoords, 1),
+                        set(&ai.texcoords, 1),
+                        f32::EPSILON * 1000.,
+                    );
                 })
                 .unwrap_err();
             } else {
-                assert_full_matches(&ml.texcoords[1], &ai.texcoords[1], f32::EPSILON);
+                assert_full_matches(set(&ml.texcoords, 1), set(&ai.texcoords, 1), f32::EPSILON);
             }
             if matches!(filename, "kwxport_test_vcolors.dae") {
                 panic::catch_unwind(|| {
-                    assert_full_matches(&ml.colors[0], &ai.colors[0], f32::EPSILON * 1000.);
+                    assert_full_matches(
+                        set(&ml.colors, 0),
+                        set(&ai.colors, 0),
+                        f32::EPSILON * 1000.,
+                    );
                 })
                 .unwrap_err();
             } else {
-                assert_full_matches(&ml.colors[0], &ai.colors[0], f32::EPSILON);
+                assert_full_matches(set(&ml.colors, 0), set(&ai.colors, 0), f32::EPSILON);
             }
-            assert_full_matches(&ml.colors[1], &ai.colors[1], f32::EPSILON);
+            assert_full_matches(set(&ml.colors, 1), set(&ai.colors, 1), f32::EPSILON);
         }
     }
 
@@ -366,17 +378,21 @@ fn test() {
             } else {
                 assert_full_matches(&ml.normals, &ai.normals, f32::EPSILON);
             }
-            assert_full_matches(&ml.texcoords[0], &ai.texcoords[0], f32::EPSILON);
-            assert_full_matches(&ml.texcoords[1], &ai.texcoords[1], f32::EPSILON);
+            assert_full_matches(set(&ml.texcoords, 0), set(&ai.texcoords, 0), f32::EPSILON);
+            assert_full_matches(set(&ml.texcoords, 1), set(&ai.texcoords, 1), f32::EPSILON);
             if matches!(filename, "only_a_part_of_vertexcolors.obj") {
                 panic::catch_unwind(|| {
-                    assert_full_matches(&ml.colors[0], &ai.colors[0], f32::EPSILON * 1000.);
+                    assert_full_matches(
+                        set(&ml.colors, 0),
+                        set(&ai.colors, 0),
+                        f32::EPSILON * 1000.,
+                    );
                 })
                 .unwrap_err();
             } else {
-                assert_full_matches(&ml.colors[0], &ai.colors[0], f32::EPSILON);
+                assert_full_matches(set(&ml.colors, 0), set(&ai.colors, 0), f32::EPSILON);
             }
-            assert_full_matches(&ml.colors[1], &ai.colors[1], f32::EPSILON);
+            assert_full_matches(set(&ml.colors, 1), set(&ai.colors, 1), f32::EPSILON);
         }
     }
 
@@ -425,11 +441,11 @@ fn test() {
         }
         assert_faces(ml, ai);
         assert_full_matches(&ml.vertices, &ai.vertices, f32::EPSILON * 10.);
-        assert_full_matches(&ml.texcoords[0], &ai.texcoords[0], f32::EPSILON);
-        assert_full_matches(&ml.texcoords[1], &ai.texcoords[1], f32::EPSILON);
+        assert_full_matches(set(&ml.texcoords, 0), set(&ai.texcoords, 0), f32::EPSILON);
+        assert_full_matches(set(&ml.texcoords, 1), set(&ai.texcoords, 1), f32::EPSILON);
         assert_full_matches(&ml.normals, &ai.normals, f32::EPSILON);
-        assert_full_matches(&ml.colors[0], &ai.colors[0], f32::EPSILON);
-        assert_full_matches(&ml.colors[1], &ai.colors[1], f32::EPSILON);
+        assert_full_matches(set(&ml.colors, 0), set(&ai.colors, 0), f32::EPSILON);
+        assert_full_matches(set(&ml.colors, 1), set(&ai.colors, 1), f32::EPSILON);
     }
 }
 
@@ -475,6 +491,13 @@ fn assert_vertices(ml: &mesh_loader::Mesh, ai: &mesh_loader::Mesh, eps: f32) {
     //     }
     // }
 }
+
+/// Returns the given texture coordinate or color set, or an empty slice if
+/// the mesh does not have it.
+fn set<T>(sets: &[Vec<T>], set: usize) -> &[T] {
+    sets.get(set).map_or(&[], Vec::as_slice)
+}
+
 // Asserts length, order, and values are all matched.
 #[track_caller]
 fn assert_full_matches<const N: usize>(a: &[[f32; N]], b: &[[f32; N]], eps: f32) {
@@ -55,12 +55,10 @@ pub(crate) fn assimp_scene_to_scene(ai: &assimp::Scene<'_>) -> mesh_loader::Scen
         let mut mesh = mesh_loader::Mesh::default();
         mesh.name = name;
         mesh.vertices = vertices;
-        mesh.texcoords[0] = texcoords0;
-        mesh.texcoords[1] = texcoords1;
+        mesh.texcoords = vec![texcoords0, texcoords1];
         mesh.normals = normals;
         mesh.faces = faces;
-        mesh.colors[0] = colors0;
-        mesh.colors[1] = colors1;
+        mesh.colors = vec![colors0, colors1];
         meshes.push(mesh);
     }
     let mut scene = mesh_loader::Scene::default();